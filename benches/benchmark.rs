@@ -1,4 +1,4 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use ip_network_table_deps_treebitmap::IpLookupTable;
 use ipnet::Ipv4Net;
 use prefix_trie::*;
@@ -8,6 +8,7 @@ use std::net::Ipv4Addr;
 
 const ITERS: usize = 100_000;
 const NUM_SPARSE_ADDR: usize = 20;
+const TEARDOWN_SIZE: usize = 1_000_000;
 
 enum Insn {
     Insert(Ipv4Addr, u8, u32),
@@ -145,6 +146,35 @@ fn lookup_prefix_map(map: &PrefixMap<Ipv4Net, u32>, insns: &Vec<Insn>) {
     }
 }
 
+fn lookup_stride_index(index: &map::StrideIndex<'_, Ipv4Net, u32>, insns: &Vec<Insn>) {
+    for insn in insns {
+        criterion::black_box(match insn {
+            Insn::Insert(_, _, _) => unreachable!(),
+            Insn::Remove(_, _) => unreachable!(),
+            Insn::ExactMatch(addr, len) => index
+                .map()
+                .get(&Ipv4Net::new(*addr, *len).unwrap())
+                .copied(),
+            Insn::LongestPrefixMatch(addr, len) => index
+                .get_lpm(&Ipv4Net::new(*addr, *len).unwrap())
+                .map(|(_, x)| *x),
+        });
+    }
+}
+
+fn lookup_frozen(map: &map::FrozenPrefixMap<Ipv4Net, u32>, insns: &Vec<Insn>) {
+    for insn in insns {
+        criterion::black_box(match insn {
+            Insn::Insert(_, _, _) => unreachable!(),
+            Insn::Remove(_, _) => unreachable!(),
+            Insn::ExactMatch(addr, len) => map.get(&Ipv4Net::new(*addr, *len).unwrap()).copied(),
+            Insn::LongestPrefixMatch(addr, len) => map
+                .get_lpm(&Ipv4Net::new(*addr, *len).unwrap())
+                .map(|(_, x)| *x),
+        });
+    }
+}
+
 fn execute_treebitmap(map: &mut IpLookupTable<Ipv4Addr, u32>, insns: &Vec<Insn>) {
     for insn in insns {
         criterion::black_box(match insn {
@@ -214,6 +244,18 @@ pub fn dense_lookup(c: &mut Criterion) {
             lookup_prefix_map(&prefix_map, &lookups);
         })
     });
+    let index = prefix_map.stride_index(16);
+    group.bench_function("StrideIndex", |b| {
+        b.iter(|| {
+            lookup_stride_index(&index, &lookups);
+        })
+    });
+    let frozen = prefix_map.clone().freeze();
+    group.bench_function("FrozenPrefixMap", |b| {
+        b.iter(|| {
+            lookup_frozen(&frozen, &lookups);
+        })
+    });
     group.bench_function("TreeBitMap", |b| {
         b.iter(|| {
             lookup_treebitmap(&treebitmap, &lookups);
@@ -271,11 +313,43 @@ pub fn sparse_lookup(c: &mut Criterion) {
     group.finish();
 }
 
+pub fn teardown(c: &mut Criterion) {
+    let mut group = c.benchmark_group("teardown");
+    group.sample_size(10);
+
+    let mut rng = thread_rng();
+    let mut map: PrefixMap<Ipv4Net, Vec<u32>> = PrefixMap::new();
+    let mut num_entries = 0;
+    while num_entries < TEARDOWN_SIZE {
+        let (addr, len) = random_addr(&mut rng);
+        let prefix = Ipv4Net::new(addr, len).unwrap().trunc();
+        if map.insert(prefix, vec![rng.gen()]).is_none() {
+            num_entries += 1;
+        }
+    }
+
+    group.bench_function("drop", |b| {
+        b.iter_batched(|| map.clone(), drop, BatchSize::PerIteration)
+    });
+    group.bench_function("clear", |b| {
+        b.iter_batched(
+            || map.clone(),
+            |mut map| {
+                map.clear();
+                map
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
 criterion_group!(
     benches,
     dense_lookup,
     dense_mods,
     sparse_lookup,
-    sparse_mods
+    sparse_mods,
+    teardown
 );
 criterion_main!(benches);
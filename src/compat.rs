@@ -0,0 +1,434 @@
+//! Adapters that mirror the API of other IP lookup crates on top of a [`PrefixMap`].
+//!
+//! These types allow migrating a code base to this crate incrementally. [`IpLookupTable`] mimics
+//! the table of the `treebitmap` crate, where prefixes are given as an address and a mask length,
+//! and [`IpNetworkTable`] mimics the dual-stack table of the `ip_network_table` crate (using
+//! [`ipnet::IpNet`] instead of `ip_network::IpNetwork`) on top of a [`RoutingTable`]. Both types
+//! can be converted into the underlying [`PrefixMap`]s at any time, so the remaining call sites
+//! can be ported one by one.
+
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use num_traits::{PrimInt, Zero};
+
+use crate::{map::IntoIter, prefix::mask_from_prefix_len, Prefix, PrefixMap, RoutingTable};
+
+/// Iterate over all entries in `map` that contain `prefix`, starting with the longest match.
+fn matches<'a, P: Prefix, T>(
+    map: &'a PrefixMap<P, T>,
+    prefix: &P,
+) -> impl Iterator<Item = (&'a P, &'a T)> + 'a {
+    core::iter::successors(map.get_lpm(prefix), move |(p, _)| {
+        let len = p.prefix_len().checked_sub(1)?;
+        map.get_lpm(&P::from_repr_len(p.mask() & mask_from_prefix_len(len), len))
+    })
+}
+
+/// An IP address that can be used as the key of an [`IpLookupTable`], i.e., either [`Ipv4Addr`] or
+/// [`Ipv6Addr`].
+pub trait Address: Copy {
+    /// The prefix type that stores the address and the mask length.
+    type Prefix: Prefix + Copy;
+
+    /// Create the prefix from the address and the mask length, setting all host bits to zero.
+    ///
+    /// # Panics
+    /// Panics if `masklen` is larger than the number of bits in the address.
+    fn to_prefix(self, masklen: u32) -> Self::Prefix;
+
+    /// Split a prefix into its (masked) address and its mask length.
+    fn from_prefix(prefix: &Self::Prefix) -> (Self, u32);
+}
+
+impl Address for Ipv4Addr {
+    type Prefix = Ipv4Net;
+
+    fn to_prefix(self, masklen: u32) -> Ipv4Net {
+        u8::try_from(masklen)
+            .ok()
+            .and_then(|len| Ipv4Net::new(self, len).ok())
+            .expect("Invalid mask length")
+            .trunc()
+    }
+
+    fn from_prefix(prefix: &Ipv4Net) -> (Self, u32) {
+        (prefix.network(), prefix.prefix_len() as u32)
+    }
+}
+
+impl Address for Ipv6Addr {
+    type Prefix = Ipv6Net;
+
+    fn to_prefix(self, masklen: u32) -> Ipv6Net {
+        u8::try_from(masklen)
+            .ok()
+            .and_then(|len| Ipv6Net::new(self, len).ok())
+            .expect("Invalid mask length")
+            .trunc()
+    }
+
+    fn from_prefix(prefix: &Ipv6Net) -> (Self, u32) {
+        (prefix.network(), prefix.prefix_len() as u32)
+    }
+}
+
+/// A lookup table with the API of `treebitmap::IpLookupTable`, backed by a [`PrefixMap`].
+/// Prefixes are passed as an address (of type [`Ipv4Addr`] or [`Ipv6Addr`]) and a mask length.
+/// Host bits of the address are ignored.
+///
+/// ```
+/// # use prefix_trie::compat::IpLookupTable;
+/// # use std::net::Ipv4Addr;
+/// let mut table: IpLookupTable<Ipv4Addr, _> = IpLookupTable::new();
+/// table.insert(Ipv4Addr::new(10, 0, 0, 0), 8, 1);
+/// table.insert(Ipv4Addr::new(10, 1, 0, 0), 16, 2);
+/// assert_eq!(table.len(), 2);
+/// assert_eq!(
+///     table.longest_match(Ipv4Addr::new(10, 1, 2, 3)),
+///     Some((Ipv4Addr::new(10, 1, 0, 0), 16, &2))
+/// );
+/// assert_eq!(
+///     table.matches(Ipv4Addr::new(10, 1, 2, 3)).collect::<Vec<_>>(),
+///     vec![
+///         (Ipv4Addr::new(10, 1, 0, 0), 16, &2),
+///         (Ipv4Addr::new(10, 0, 0, 0), 8, &1),
+///     ]
+/// );
+/// assert_eq!(table.remove(Ipv4Addr::new(10, 1, 0, 0), 16), Some(2));
+/// assert_eq!(table.exact_match(Ipv4Addr::new(10, 0, 0, 0), 8), Some(&1));
+/// ```
+#[derive(Clone)]
+pub struct IpLookupTable<A: Address, T> {
+    map: PrefixMap<A::Prefix, T>,
+    len: usize,
+}
+
+impl<A: Address, T> Default for IpLookupTable<A, T> {
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<A: Address, T> IpLookupTable<A, T> {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of entries in the table.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the table contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Insert a value for the prefix `ip/masklen`. The old value is returned if the prefix was
+    /// already present.
+    ///
+    /// # Panics
+    /// Panics if `masklen` is larger than the number of bits in the address.
+    pub fn insert(&mut self, ip: A, masklen: u32, value: T) -> Option<T> {
+        let old = self.map.insert(ip.to_prefix(masklen), value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Remove the prefix `ip/masklen` from the table, and return its value.
+    ///
+    /// # Panics
+    /// Panics if `masklen` is larger than the number of bits in the address.
+    pub fn remove(&mut self, ip: A, masklen: u32) -> Option<T> {
+        let old = self.map.remove(&ip.to_prefix(masklen));
+        if old.is_some() {
+            self.len -= 1;
+        }
+        old
+    }
+
+    /// Get the value of the prefix `ip/masklen`.
+    ///
+    /// # Panics
+    /// Panics if `masklen` is larger than the number of bits in the address.
+    pub fn exact_match(&self, ip: A, masklen: u32) -> Option<&T> {
+        self.map.get(&ip.to_prefix(masklen))
+    }
+
+    /// Get a mutable reference to the value of the prefix `ip/masklen`.
+    ///
+    /// # Panics
+    /// Panics if `masklen` is larger than the number of bits in the address.
+    pub fn exact_match_mut(&mut self, ip: A, masklen: u32) -> Option<&mut T> {
+        self.map.get_mut(&ip.to_prefix(masklen))
+    }
+
+    /// Get the longest prefix that contains `ip`, together with its value.
+    pub fn longest_match(&self, ip: A) -> Option<(A, u32, &T)> {
+        self.map
+            .get_lpm(&ip.to_prefix(host_len::<A>()))
+            .map(|(p, t)| {
+                let (addr, masklen) = A::from_prefix(p);
+                (addr, masklen, t)
+            })
+    }
+
+    /// Get the longest prefix that contains `ip`, together with a mutable reference to its value.
+    pub fn longest_match_mut(&mut self, ip: A) -> Option<(A, u32, &mut T)> {
+        self.map
+            .get_lpm_mut(&ip.to_prefix(host_len::<A>()))
+            .map(|(p, t)| {
+                let (addr, masklen) = A::from_prefix(p);
+                (addr, masklen, t)
+            })
+    }
+
+    /// Iterate over all prefixes that contain `ip`, starting with the longest match.
+    pub fn matches(&self, ip: A) -> impl Iterator<Item = (A, u32, &T)> {
+        matches(&self.map, &ip.to_prefix(host_len::<A>())).map(|(p, t)| {
+            let (addr, masklen) = A::from_prefix(p);
+            (addr, masklen, t)
+        })
+    }
+
+    /// Iterate over all entries in lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (A, u32, &T)> {
+        self.map.iter().map(|(p, t)| {
+            let (addr, masklen) = A::from_prefix(p);
+            (addr, masklen, t)
+        })
+    }
+
+    /// Iterate over all entries in lexicographic order, with mutable references to the values.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (A, u32, &mut T)> {
+        self.map.iter_mut().map(|(p, t)| {
+            let (addr, masklen) = A::from_prefix(p);
+            (addr, masklen, t)
+        })
+    }
+
+    /// Get a reference to the underlying [`PrefixMap`].
+    pub fn as_map(&self) -> &PrefixMap<A::Prefix, T> {
+        &self.map
+    }
+
+    /// Turn the table into the underlying [`PrefixMap`].
+    pub fn into_map(self) -> PrefixMap<A::Prefix, T> {
+        self.map
+    }
+}
+
+impl<A: Address, T> From<PrefixMap<A::Prefix, T>> for IpLookupTable<A, T> {
+    fn from(map: PrefixMap<A::Prefix, T>) -> Self {
+        let len = map.iter().count();
+        Self { map, len }
+    }
+}
+
+impl<A: Address, T> IntoIterator for IpLookupTable<A, T> {
+    type Item = (A, u32, T);
+    type IntoIter = core::iter::Map<IntoIter<A::Prefix, T>, fn((A::Prefix, T)) -> (A, u32, T)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter().map(|(p, t)| {
+            let (addr, masklen) = A::from_prefix(&p);
+            (addr, masklen, t)
+        })
+    }
+}
+
+/// The number of bits of the address type `A`.
+fn host_len<A: Address>() -> u32 {
+    <A::Prefix as Prefix>::R::zero().count_zeros()
+}
+
+/// A dual-stack table with the API of `ip_network_table::IpNetworkTable`, backed by a
+/// [`RoutingTable`]. Networks are given as [`IpNet`], and host bits are ignored.
+///
+/// ```
+/// # use prefix_trie::compat::IpNetworkTable;
+/// # use ipnet::IpNet;
+/// # use std::net::IpAddr;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut table = IpNetworkTable::new();
+/// table.insert("10.0.0.0/8".parse::<IpNet>()?, 1);
+/// table.insert("2001:db8::/32".parse::<IpNet>()?, 2);
+/// assert_eq!(table.len(), (1, 1));
+/// assert_eq!(
+///     table.longest_match("10.1.1.1".parse::<IpAddr>()?),
+///     Some(("10.0.0.0/8".parse()?, &1))
+/// );
+/// assert_eq!(
+///     table.longest_match("2001:db8::1".parse::<IpAddr>()?),
+///     Some(("2001:db8::/32".parse()?, &2))
+/// );
+/// assert_eq!(table.exact_match("10.0.0.0/8".parse::<IpNet>()?), Some(&1));
+/// assert_eq!(table.remove("2001:db8::/32".parse::<IpNet>()?), Some(2));
+/// assert_eq!(table.len(), (1, 0));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct IpNetworkTable<T> {
+    table: RoutingTable<T>,
+    len: (usize, usize),
+}
+
+impl<T> Default for IpNetworkTable<T> {
+    fn default() -> Self {
+        Self {
+            table: RoutingTable::new(),
+            len: (0, 0),
+        }
+    }
+}
+
+impl<T> IpNetworkTable<T> {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of IPv4 and IPv6 entries in the table.
+    pub fn len(&self) -> (usize, usize) {
+        self.len
+    }
+
+    /// Check if the table contains neither IPv4 nor IPv6 entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == (0, 0)
+    }
+
+    /// Get the counter of entries with the same address family as `network`.
+    fn len_mut(&mut self, network: &IpNet) -> &mut usize {
+        match network {
+            IpNet::V4(_) => &mut self.len.0,
+            IpNet::V6(_) => &mut self.len.1,
+        }
+    }
+
+    /// Insert a value for the network. The old value is returned if the network was already
+    /// present.
+    pub fn insert<N: Into<IpNet>>(&mut self, network: N, data: T) -> Option<T> {
+        let network = network.into().trunc();
+        let old = self.table.insert(network, data);
+        if old.is_none() {
+            *self.len_mut(&network) += 1;
+        }
+        old
+    }
+
+    /// Remove the network from the table, and return its value.
+    pub fn remove<N: Into<IpNet>>(&mut self, network: N) -> Option<T> {
+        let network = network.into().trunc();
+        let old = self.table.remove(&network);
+        if old.is_some() {
+            *self.len_mut(&network) -= 1;
+        }
+        old
+    }
+
+    /// Get the value of the network.
+    pub fn exact_match<N: Into<IpNet>>(&self, network: N) -> Option<&T> {
+        self.table.get(&network.into().trunc())
+    }
+
+    /// Get a mutable reference to the value of the network.
+    pub fn exact_match_mut<N: Into<IpNet>>(&mut self, network: N) -> Option<&mut T> {
+        self.table.get_mut(&network.into().trunc())
+    }
+
+    /// Get the longest network that contains `ip`, together with its value.
+    pub fn longest_match<I: Into<IpAddr>>(&self, ip: I) -> Option<(IpNet, &T)> {
+        self.table.get_lpm(&ip.into().into())
+    }
+
+    /// Get the longest IPv4 network that contains `ip`, together with its value.
+    pub fn longest_match_ipv4(&self, ip: Ipv4Addr) -> Option<(Ipv4Net, &T)> {
+        self.table.ipv4().get_lpm(&ip.into()).map(|(p, t)| (*p, t))
+    }
+
+    /// Get the longest IPv6 network that contains `ip`, together with its value.
+    pub fn longest_match_ipv6(&self, ip: Ipv6Addr) -> Option<(Ipv6Net, &T)> {
+        self.table.ipv6().get_lpm(&ip.into()).map(|(p, t)| (*p, t))
+    }
+
+    /// Get the longest network that contains `ip`, together with a mutable reference to its value.
+    pub fn longest_match_mut<I: Into<IpAddr>>(&mut self, ip: I) -> Option<(IpNet, &mut T)> {
+        self.table.get_lpm_mut(&ip.into().into())
+    }
+
+    /// Iterate over all networks that contain `ip`, starting with the longest match.
+    pub fn matches<I: Into<IpAddr>>(&self, ip: I) -> impl Iterator<Item = (IpNet, &T)> {
+        let (ipv4, ipv6) = match ip.into() {
+            IpAddr::V4(ip) => (Some(matches(self.table.ipv4(), &ip.into())), None),
+            IpAddr::V6(ip) => (None, Some(matches(self.table.ipv6(), &ip.into()))),
+        };
+        let ipv4 = ipv4.into_iter().flatten().map(|(p, t)| (IpNet::V4(*p), t));
+        let ipv6 = ipv6.into_iter().flatten().map(|(p, t)| (IpNet::V6(*p), t));
+        ipv4.chain(ipv6)
+    }
+
+    /// Iterate over all entries, first all IPv4 networks and then all IPv6 networks, each in
+    /// lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (IpNet, &T)> {
+        self.table.iter()
+    }
+
+    /// Iterate over all IPv4 entries in lexicographic order.
+    pub fn iter_ipv4(&self) -> impl Iterator<Item = (Ipv4Net, &T)> {
+        self.table.ipv4().iter().map(|(p, t)| (*p, t))
+    }
+
+    /// Iterate over all IPv6 entries in lexicographic order.
+    pub fn iter_ipv6(&self) -> impl Iterator<Item = (Ipv6Net, &T)> {
+        self.table.ipv6().iter().map(|(p, t)| (*p, t))
+    }
+
+    /// Only keep the entries for which `f` returns `true`.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(IpNet, &T) -> bool,
+    {
+        self.table.retain(f);
+        self.len = (
+            self.table.ipv4().iter().count(),
+            self.table.ipv6().iter().count(),
+        );
+    }
+
+    /// Get references to the underlying IPv4 and IPv6 [`PrefixMap`]s.
+    pub fn as_maps(&self) -> (&PrefixMap<Ipv4Net, T>, &PrefixMap<Ipv6Net, T>) {
+        (self.table.ipv4(), self.table.ipv6())
+    }
+
+    /// Turn the table into the underlying IPv4 and IPv6 [`PrefixMap`]s.
+    pub fn into_maps(self) -> (PrefixMap<Ipv4Net, T>, PrefixMap<Ipv6Net, T>) {
+        self.table.into_maps()
+    }
+
+    /// Turn the table into the underlying [`RoutingTable`].
+    pub fn into_routing_table(self) -> RoutingTable<T> {
+        self.table
+    }
+}
+
+impl<T> From<(PrefixMap<Ipv4Net, T>, PrefixMap<Ipv6Net, T>)> for IpNetworkTable<T> {
+    fn from((ipv4, ipv6): (PrefixMap<Ipv4Net, T>, PrefixMap<Ipv6Net, T>)) -> Self {
+        RoutingTable::from_maps(ipv4, ipv6).into()
+    }
+}
+
+impl<T> From<RoutingTable<T>> for IpNetworkTable<T> {
+    fn from(table: RoutingTable<T>) -> Self {
+        let len = (table.ipv4().iter().count(), table.ipv6().iter().count());
+        Self { table, len }
+    }
+}
@@ -0,0 +1,273 @@
+//! A read-mostly [`PrefixMap`] that is shared between many reader threads and a single writer.
+//!
+//! The map is stored twice, following the left-right pattern. Readers always access the published
+//! copy, while the writer modifies the other one. Calling [`WriteHandle::publish`] makes all
+//! changes visible at once by swapping both copies, and then replays the changes on the copy that
+//! was published before, once all readers have left it.
+//!
+//! Reads are wait-free: Each [`ReadHandle`] has its own epoch counter, which it increments when
+//! entering and leaving a read. Apart from that, a read only loads the index of the published
+//! copy, so readers neither take a lock nor write to memory shared with other readers or the
+//! writer. In turn, the writer waits in [`WriteHandle::publish`] for readers that still access the
+//! old copy, so read guards should be short-lived.
+//!
+//! Since every change is applied to both copies, the map uses twice the memory, and inserted
+//! prefixes and values must implement [`Clone`].
+//!
+//! ```
+//! # use prefix_trie::*;
+//! # use ipnet::Ipv4Net;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let (mut writer, reader) = concurrent::new(PrefixMap::<Ipv4Net, u32>::new());
+//! writer.insert("10.0.0.0/8".parse()?, 1);
+//! // changes are only visible after publishing them.
+//! assert_eq!(reader.read().get_lpm(&"10.1.1.1/32".parse()?), None);
+//! writer.publish();
+//!
+//! let handle = std::thread::spawn(move || {
+//!     reader.read().get_lpm(&"10.1.1.1/32".parse().unwrap()).map(|(_, t)| *t)
+//! });
+//! assert_eq!(handle.join().unwrap(), Some(1));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::cell::{Cell, UnsafeCell};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{Prefix, PrefixMap};
+
+/// Create a writer and a reader of `map`. The map is published immediately. Additional readers
+/// are created by cloning the [`ReadHandle`] or using [`WriteHandle::reader`].
+pub fn new<P, T>(map: PrefixMap<P, T>) -> (WriteHandle<P, T>, ReadHandle<P, T>)
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    let shared = Arc::new(Shared {
+        maps: [UnsafeCell::new(map.clone()), UnsafeCell::new(map)],
+        active: AtomicUsize::new(0),
+        epochs: Mutex::new(Vec::new()),
+    });
+    let reader = ReadHandle::new(shared.clone());
+    let writer = WriteHandle {
+        shared,
+        log: Vec::new(),
+    };
+    (writer, reader)
+}
+
+/// The state shared between the writer and all readers.
+struct Shared<P, T> {
+    maps: [UnsafeCell<PrefixMap<P, T>>; 2],
+    /// The index of the published copy.
+    active: AtomicUsize,
+    /// The epoch counters of all readers. A counter is odd while its reader accesses a copy.
+    epochs: Mutex<Vec<Arc<AtomicUsize>>>,
+}
+
+// SAFETY: Readers only create shared references to the published copy, and the writer only creates
+// a mutable reference to the unpublished copy, which no reader accesses (see `WriteHandle::publish`).
+// Hence, sharing `Shared` between threads is safe if the map itself can be shared and sent.
+unsafe impl<P: Send + Sync, T: Send + Sync> Sync for Shared<P, T> {}
+
+/// A change that still needs to be applied to the copy that is currently published.
+enum Operation<P, T> {
+    Insert(P, T),
+    Remove(P),
+    RemoveChildren(P),
+    Clear,
+}
+
+/// The writer of a concurrent map. See the [module documentation](self). Changes are applied to
+/// the unpublished copy, and become visible to readers with [`WriteHandle::publish`].
+pub struct WriteHandle<P, T> {
+    shared: Arc<Shared<P, T>>,
+    log: Vec<Operation<P, T>>,
+}
+
+impl<P, T> WriteHandle<P, T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    /// Insert a new item into the map. If the key is already present, the old value is replaced
+    /// and returned. The change is only visible to readers after [`WriteHandle::publish`].
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let old = self.unpublished().insert(prefix.clone(), value.clone());
+        self.log.push(Operation::Insert(prefix, value));
+        old
+    }
+
+    /// Remove an element from the map, returning its value. The change is only visible to readers
+    /// after [`WriteHandle::publish`].
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let old = self.unpublished().remove(prefix);
+        if old.is_some() {
+            self.log.push(Operation::Remove(prefix.clone()));
+        }
+        old
+    }
+
+    /// Remove all entries that are contained within `prefix`. The change is only visible to
+    /// readers after [`WriteHandle::publish`].
+    pub fn remove_children(&mut self, prefix: &P) {
+        self.unpublished().remove_children(prefix);
+        self.log.push(Operation::RemoveChildren(prefix.clone()));
+    }
+
+    /// Remove all entries from the map. The change is only visible to readers after
+    /// [`WriteHandle::publish`].
+    pub fn clear(&mut self) {
+        self.unpublished().clear();
+        self.log.clear();
+        self.log.push(Operation::Clear);
+    }
+
+    /// Make all changes visible to readers at once. Readers that start afterwards see the new
+    /// state, while readers that are still active keep accessing the old state. This function
+    /// waits until all of them have released their [`ReadGuard`], and then applies the changes to
+    /// the old copy.
+    pub fn publish(&mut self) {
+        if self.log.is_empty() {
+            return;
+        }
+        let old = self.shared.active.load(Ordering::Relaxed);
+        self.shared.active.store(1 - old, Ordering::SeqCst);
+
+        // Wait for all readers that were inside a read when the copies were swapped. Readers
+        // increment their epoch before loading `active`, so any reader whose epoch was even at
+        // this point will see the new copy.
+        let mut epochs = self
+            .shared
+            .epochs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        epochs.retain(|epoch| Arc::strong_count(epoch) > 1);
+        let started: Vec<usize> = epochs.iter().map(|e| e.load(Ordering::SeqCst)).collect();
+        for (epoch, start) in epochs.iter().zip(started) {
+            if start % 2 == 1 {
+                while epoch.load(Ordering::SeqCst) == start {
+                    std::thread::yield_now();
+                }
+            }
+        }
+        drop(epochs);
+
+        let log = core::mem::take(&mut self.log);
+        let map = self.unpublished();
+        for op in log {
+            match op {
+                Operation::Insert(prefix, value) => {
+                    map.insert(prefix, value);
+                }
+                Operation::Remove(prefix) => {
+                    map.remove(&prefix);
+                }
+                Operation::RemoveChildren(prefix) => map.remove_children(&prefix),
+                Operation::Clear => map.clear(),
+            }
+        }
+    }
+
+    /// Check whether there are changes that are not yet published.
+    pub fn has_pending(&self) -> bool {
+        !self.log.is_empty()
+    }
+
+    /// Create a new reader of the map.
+    pub fn reader(&self) -> ReadHandle<P, T> {
+        ReadHandle::new(self.shared.clone())
+    }
+}
+
+impl<P, T> WriteHandle<P, T> {
+    /// Access the unpublished copy.
+    pub(crate) fn unpublished(&mut self) -> &mut PrefixMap<P, T> {
+        let idx = 1 - self.shared.active.load(Ordering::Relaxed);
+        // SAFETY: Only the writer changes `active`, and there is only one writer, which is
+        // borrowed mutably. Readers only enter the published copy, and `publish` waits until all
+        // readers have left a copy before the writer accesses it again.
+        unsafe { &mut *self.shared.maps[idx].get() }
+    }
+}
+
+/// A reader of a concurrent map. See the [module documentation](self). The handle can be sent to
+/// other threads, but not shared between them; clone it to create a reader for each thread.
+pub struct ReadHandle<P, T> {
+    shared: Arc<Shared<P, T>>,
+    /// The epoch counter of this reader, which is odd while any [`ReadGuard`] is held.
+    epoch: Arc<AtomicUsize>,
+    /// The number of [`ReadGuard`]s that are currently held.
+    guards: Cell<usize>,
+    /// The index of the copy that the current guards access.
+    idx: Cell<usize>,
+}
+
+impl<P, T> ReadHandle<P, T> {
+    /// Create a new reader and register its epoch counter.
+    fn new(shared: Arc<Shared<P, T>>) -> Self {
+        let epoch = Arc::new(AtomicUsize::new(0));
+        shared
+            .epochs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(epoch.clone());
+        Self {
+            shared,
+            epoch,
+            guards: Cell::new(0),
+            idx: Cell::new(0),
+        }
+    }
+
+    /// Access the published state of the map. The state does not change while the guard is held,
+    /// even if the writer publishes new changes. However, the writer cannot complete the next
+    /// [`WriteHandle::publish`] until the guard is dropped. Nested guards of the same handle
+    /// access the same state.
+    pub fn read(&self) -> ReadGuard<'_, P, T> {
+        let guards = self.guards.get();
+        if guards == 0 {
+            self.epoch.fetch_add(1, Ordering::SeqCst);
+            self.idx.set(self.shared.active.load(Ordering::SeqCst));
+        }
+        self.guards.set(guards + 1);
+        // SAFETY: The epoch is odd until the guard is dropped, so the writer will not access this
+        // copy until then. See `WriteHandle::publish`.
+        let map = unsafe { &*self.shared.maps[self.idx.get()].get() };
+        ReadGuard { handle: self, map }
+    }
+}
+
+impl<P, T> Clone for ReadHandle<P, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.shared.clone())
+    }
+}
+
+/// A read guard of the published state of a [`ReadHandle`], which dereferences to the
+/// [`PrefixMap`].
+pub struct ReadGuard<'a, P, T> {
+    handle: &'a ReadHandle<P, T>,
+    map: &'a PrefixMap<P, T>,
+}
+
+impl<P, T> Deref for ReadGuard<'_, P, T> {
+    type Target = PrefixMap<P, T>;
+
+    fn deref(&self) -> &PrefixMap<P, T> {
+        self.map
+    }
+}
+
+impl<P, T> Drop for ReadGuard<'_, P, T> {
+    fn drop(&mut self) {
+        let guards = self.handle.guards.get() - 1;
+        self.handle.guards.set(guards);
+        if guards == 0 {
+            self.handle.epoch.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
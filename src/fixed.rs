@@ -0,0 +1,399 @@
+//! A prefix map with a fixed capacity that never allocates.
+//!
+//! A [`FixedPrefixMap`] stores all nodes in an array of `N` slots, which is part of the map itself.
+//! Hence, the map does not allocate any memory, and it can be placed on the stack or in a
+//! `static`. Inserting into a full map returns a [`CapacityError`] instead of allocating more
+//! slots. This is useful for embedded systems where allocation after boot is forbidden.
+//!
+//! Like [`PrefixMap`](crate::PrefixMap), a map with `n` entries uses at most `2n + 1` slots, one of
+//! which is the root.
+
+use crate::{to_right, Prefix};
+
+/// The largest number of nodes on the stack of [`Iter`]. Each node on a path from the root has a
+/// strictly longer prefix than its parent, so paths have at most 129 nodes.
+const MAX_STACK: usize = 130;
+
+/// The error returned by [`FixedPrefixMap::insert`] if there are not enough free slots to insert
+/// the entry. The map is not modified.
+pub struct CapacityError<P, T> {
+    /// The prefix that was not inserted.
+    pub prefix: P,
+    /// The value that was not inserted.
+    pub value: T,
+}
+
+impl<P: core::fmt::Debug, T: core::fmt::Debug> core::fmt::Debug for CapacityError<P, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CapacityError")
+            .field("prefix", &self.prefix)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl<P: core::fmt::Debug, T: core::fmt::Debug> core::fmt::Display for CapacityError<P, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?} with value {:?}, the map is full",
+            self.prefix, self.value
+        )
+    }
+}
+
+impl<P: core::fmt::Debug, T: core::fmt::Debug> core::error::Error for CapacityError<P, T> {}
+
+struct Node<P, T> {
+    prefix: P,
+    value: Option<T>,
+    left: Option<u32>,
+    right: Option<u32>,
+}
+
+impl<P, T> Node<P, T> {
+    fn child(&self, right: bool) -> Option<usize> {
+        if right { self.right } else { self.left }.map(|c| c as usize)
+    }
+
+    fn set_child(&mut self, right: bool, child: Option<usize>) {
+        let child = child.map(|c| c as u32);
+        if right {
+            self.right = child;
+        } else {
+            self.left = child;
+        }
+    }
+}
+
+enum Slot<P, T> {
+    Used(Node<P, T>),
+    /// A free slot, storing the next free slot.
+    Free(Option<u32>),
+}
+
+/// A prefix map that stores up to `N` nodes without allocating. See the
+/// [module documentation](self).
+///
+/// ```
+/// # use prefix_trie::fixed::FixedPrefixMap;
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: FixedPrefixMap<Ipv4Net, u32, 4> = FixedPrefixMap::new();
+/// pm.insert("10.0.0.0/8".parse()?, 1).unwrap();
+/// pm.insert("10.0.0.0/9".parse()?, 2).unwrap();
+/// pm.insert("10.128.0.0/9".parse()?, 3).unwrap();
+/// // all four slots are used.
+/// let err = pm.insert("10.64.0.0/10".parse()?, 4).unwrap_err();
+/// assert_eq!(err.value, 4);
+/// assert_eq!(pm.get_lpm(&"10.1.1.1/32".parse()?), Some((&"10.0.0.0/9".parse()?, &2)));
+/// assert_eq!(pm.remove(&"10.128.0.0/9".parse()?), Some(3));
+/// assert_eq!(pm.insert("10.64.0.0/10".parse()?, 4).unwrap(), None);
+/// # Ok(())
+/// # }
+/// ```
+pub struct FixedPrefixMap<P, T, const N: usize> {
+    slots: [Slot<P, T>; N],
+    /// The first free slot.
+    free: Option<u32>,
+    n_free: usize,
+    len: usize,
+}
+
+impl<P: Prefix, T, const N: usize> Default for FixedPrefixMap<P, T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Prefix, T, const N: usize> FixedPrefixMap<P, T, N> {
+    /// Create an empty map with `N` slots.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero (the root needs one slot), or larger than `u32::MAX`.
+    pub fn new() -> Self {
+        assert!(N > 0, "a FixedPrefixMap needs at least one slot");
+        assert!(
+            N <= u32::MAX as usize,
+            "a FixedPrefixMap cannot have more than 2^32 slots"
+        );
+        let slots = core::array::from_fn(|i| {
+            if i == 0 {
+                Slot::Used(Node {
+                    prefix: P::zero(),
+                    value: None,
+                    left: None,
+                    right: None,
+                })
+            } else {
+                Slot::Free((i + 1 < N).then_some(i as u32 + 1))
+            }
+        });
+        Self {
+            slots,
+            free: (N > 1).then_some(1),
+            n_free: N - 1,
+            len: 0,
+        }
+    }
+
+    /// Get the number of slots of the map, including the root.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Get the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all entries from the map.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    fn node(&self, idx: usize) -> &Node<P, T> {
+        match &self.slots[idx] {
+            Slot::Used(node) => node,
+            Slot::Free(_) => unreachable!("the node at index {idx} is free"),
+        }
+    }
+
+    fn node_mut(&mut self, idx: usize) -> &mut Node<P, T> {
+        match &mut self.slots[idx] {
+            Slot::Used(node) => node,
+            Slot::Free(_) => unreachable!("the node at index {idx} is free"),
+        }
+    }
+
+    /// Store a new node in a free slot. There must be a free slot.
+    fn new_node(&mut self, prefix: P, value: Option<T>) -> usize {
+        let idx = self.free.unwrap() as usize;
+        let Slot::Free(next) = self.slots[idx] else {
+            unreachable!("the free list contains a used slot")
+        };
+        self.free = next;
+        self.n_free -= 1;
+        self.slots[idx] = Slot::Used(Node {
+            prefix,
+            value,
+            left: None,
+            right: None,
+        });
+        idx
+    }
+
+    /// Free the slot of a node and return its value.
+    fn free_node(&mut self, idx: usize) -> Option<T> {
+        let slot = core::mem::replace(&mut self.slots[idx], Slot::Free(self.free));
+        self.free = Some(idx as u32);
+        self.n_free += 1;
+        match slot {
+            Slot::Used(node) => node.value,
+            Slot::Free(_) => unreachable!("the node at index {idx} is free"),
+        }
+    }
+
+    /// Get the child of `idx` in the direction of `prefix`, if it contains `prefix`.
+    fn next(&self, idx: usize, prefix: &P) -> Option<(usize, bool)> {
+        let node = self.node(idx);
+        let right = to_right(&node.prefix, prefix);
+        node.child(right)
+            .filter(|c| self.node(*c).prefix.contains(prefix))
+            .map(|c| (c, right))
+    }
+
+    /// Find the node storing exactly `prefix`.
+    fn find(&self, prefix: &P) -> Option<usize> {
+        let mut idx = 0;
+        loop {
+            if self.node(idx).prefix.eq(prefix) {
+                return Some(idx);
+            }
+            idx = self.next(idx, prefix)?.0;
+        }
+    }
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        self.node(self.find(prefix)?).value.as_ref()
+    }
+
+    /// Get a mutable reference to a value of an element by matching exactly on the prefix.
+    pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
+        let idx = self.find(prefix)?;
+        self.node_mut(idx).value.as_mut()
+    }
+
+    /// Check if a key is present in the map.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get(prefix).is_some()
+    }
+
+    /// Get the value of the longest prefix in the map that contains `prefix`.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        let mut idx = 0;
+        let mut best_match = None;
+        loop {
+            let node = self.node(idx);
+            best_match = node
+                .value
+                .as_ref()
+                .map(|t| (&node.prefix, t))
+                .or(best_match);
+            if node.prefix.eq(prefix) {
+                return best_match;
+            }
+            match self.next(idx, prefix) {
+                Some((next, _)) => idx = next,
+                None => return best_match,
+            }
+        }
+    }
+
+    /// Insert a new item into the map. If the key is already present, the old value is replaced
+    /// and returned. If the map has not enough free slots for the new entry, a [`CapacityError`]
+    /// is returned and the map is not modified. Inserting a new entry needs up to two free slots.
+    pub fn insert(&mut self, prefix: P, value: T) -> Result<Option<T>, CapacityError<P, T>> {
+        let mut idx = 0;
+        let (right, child) = loop {
+            if self.node(idx).prefix.eq(&prefix) {
+                let old = self.node_mut(idx).value.replace(value);
+                self.len += usize::from(old.is_none());
+                return Ok(old);
+            }
+            let right = to_right(&self.node(idx).prefix, &prefix);
+            match self.node(idx).child(right) {
+                Some(child) if self.node(child).prefix.contains(&prefix) => idx = child,
+                child => break (right, child),
+            }
+        };
+        let new_branch = child.is_some_and(|c| !prefix.contains(&self.node(c).prefix));
+        if self.n_free < 1 + usize::from(new_branch) {
+            return Err(CapacityError { prefix, value });
+        }
+        self.len += 1;
+        let Some(child) = child else {
+            let new = self.new_node(prefix, Some(value));
+            self.node_mut(idx).set_child(right, Some(new));
+            return Ok(None);
+        };
+        let new = if new_branch {
+            let branch_p = self.node(child).prefix.longest_common_prefix(&prefix);
+            let child_right = to_right(&branch_p, &self.node(child).prefix);
+            let branch = self.new_node(branch_p, None);
+            let leaf = self.new_node(prefix, Some(value));
+            self.node_mut(branch).set_child(child_right, Some(child));
+            self.node_mut(branch).set_child(!child_right, Some(leaf));
+            branch
+        } else {
+            let child_right = to_right(&prefix, &self.node(child).prefix);
+            let new = self.new_node(prefix, Some(value));
+            self.node_mut(new).set_child(child_right, Some(child));
+            new
+        };
+        self.node_mut(idx).set_child(right, Some(new));
+        Ok(None)
+    }
+
+    /// Remove an element from the map, returning its value. Nodes that are no longer needed are
+    /// removed, such that their slots can be reused.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let mut idx = 0;
+        let mut parent: Option<(usize, bool)> = None;
+        let mut grandparent: Option<(usize, bool)> = None;
+        while !self.node(idx).prefix.eq(prefix) {
+            let (next, right) = self.next(idx, prefix)?;
+            grandparent = parent;
+            parent = Some((idx, right));
+            idx = next;
+        }
+        let value = self.node_mut(idx).value.take()?;
+        self.len -= 1;
+        let Some((par, par_right)) = parent else {
+            // the root always remains in the tree.
+            return Some(value);
+        };
+        let node = self.node(idx);
+        match (node.left, node.right) {
+            (Some(_), Some(_)) => {}
+            (Some(child), None) | (None, Some(child)) => {
+                self.node_mut(par)
+                    .set_child(par_right, Some(child as usize));
+                self.free_node(idx);
+            }
+            (None, None) => {
+                self.node_mut(par).set_child(par_right, None);
+                self.free_node(idx);
+                // remove the parent as well if it has no value and is not the root.
+                if let Some((grp, grp_right)) = grandparent {
+                    if self.node(par).value.is_none() {
+                        let sibling = self.node(par).child(!par_right);
+                        self.node_mut(grp).set_child(grp_right, sibling);
+                        self.free_node(par);
+                    }
+                }
+            }
+        }
+        Some(value)
+    }
+
+    /// Iterate over all entries in the map in lexicographic order.
+    pub fn iter(&self) -> Iter<'_, P, T, N> {
+        // the stack initially contains the root at index 0.
+        Iter {
+            map: self,
+            stack: [0; MAX_STACK],
+            len: 1,
+        }
+    }
+}
+
+impl<P: Prefix + core::fmt::Debug, T: core::fmt::Debug, const N: usize> core::fmt::Debug
+    for FixedPrefixMap<P, T, N>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, P: Prefix, T, const N: usize> IntoIterator for &'a FixedPrefixMap<P, T, N> {
+    type Item = (&'a P, &'a T);
+    type IntoIter = Iter<'a, P, T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over all entries of a [`FixedPrefixMap`] in lexicographic order.
+pub struct Iter<'a, P, T, const N: usize> {
+    map: &'a FixedPrefixMap<P, T, N>,
+    stack: [u32; MAX_STACK],
+    len: usize,
+}
+
+impl<'a, P: Prefix, T, const N: usize> Iterator for Iter<'a, P, T, N> {
+    type Item = (&'a P, &'a T);
+
+    fn next(&mut self) -> Option<(&'a P, &'a T)> {
+        while self.len > 0 {
+            self.len -= 1;
+            let node = self.map.node(self.stack[self.len] as usize);
+            for child in [node.right, node.left].into_iter().flatten() {
+                self.stack[self.len] = child;
+                self.len += 1;
+            }
+            if let Some(value) = node.value.as_ref() {
+                return Some((&node.prefix, value));
+            }
+        }
+        None
+    }
+}
@@ -1,6 +1,7 @@
 //! Formatting implementation for the PrefixMap
 
-use std::fmt::{Debug, Formatter, Result};
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter, Result};
 
 use super::*;
 
@@ -17,7 +18,7 @@ impl<'a, P: Debug, T: Debug> Debug for DebugPrefixMap<'a, P, T> {
         let map = self.0;
         let idx = self.1;
         let node = &map.table[idx];
-        match (node.value.as_ref(), node.left, node.right) {
+        match (node.value.as_ref(), node.left(), node.right()) {
             (None, None, None) => node.prefix.fmt(f),
             (None, None, Some(child)) | (None, Some(child), None) => f
                 .debug_map()
@@ -45,3 +46,235 @@ impl<P: Debug> Debug for PrefixSet<P> {
         DebugPrefixMap(&self.0, 0).fmt(f)
     }
 }
+
+/// Display adapter that writes all entries of a [`PrefixMap`] as comma-separated lines
+/// `prefix,value`, in lexicographic order. See [`PrefixMap::display_csv`].
+pub struct DisplayCsv<'a, P, T>(&'a PrefixMap<P, T>);
+
+impl<P: Prefix + Display, T: Display> Display for DisplayCsv<'_, P, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for (p, t) in self.0.iter() {
+            writeln!(f, "{p},{t}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Display adapter that writes all elements of a [`PrefixSet`] on separate lines, in
+/// lexicographic order. See [`PrefixSet::display_lines`].
+pub struct DisplayLines<'a, P>(&'a PrefixSet<P>);
+
+impl<P: Prefix + Display> Display for DisplayLines<'_, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for p in self.0.iter() {
+            writeln!(f, "{p}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Display adapter that writes all entries of a [`PrefixMap`] on separate lines, indented by
+/// containment. See [`PrefixMap::format_tree`].
+pub struct DisplayTree<'a, P, T>(&'a PrefixMap<P, T>);
+
+impl<P: Prefix + Display, T: Display> Display for DisplayTree<'_, P, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write_tree(f, self.0.iter(), |f, p, t| write!(f, "{p}: {t}"))
+    }
+}
+
+/// Display adapter that writes all elements of a [`PrefixSet`] on separate lines, indented by
+/// containment. See [`PrefixSet::format_tree`].
+pub struct DisplaySetTree<'a, P>(&'a PrefixSet<P>);
+
+impl<P: Prefix + Display> Display for DisplaySetTree<'_, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write_tree(f, self.0 .0.iter(), |f, p, _| write!(f, "{p}"))
+    }
+}
+
+/// Write all entries (in lexicographic order) on separate lines, indented by two spaces for each
+/// entry that contains them.
+fn write_tree<'a, P, T, I, F>(f: &mut Formatter<'_>, iter: I, mut entry: F) -> Result
+where
+    P: Prefix + 'a,
+    T: 'a,
+    I: Iterator<Item = (&'a P, &'a T)>,
+    F: FnMut(&mut Formatter<'_>, &P, &T) -> Result,
+{
+    let mut parents: Vec<&P> = Vec::new();
+    for (p, t) in iter {
+        while parents.last().is_some_and(|parent| !parent.contains(p)) {
+            parents.pop();
+        }
+        for _ in 0..parents.len() {
+            f.write_str("  ")?;
+        }
+        entry(f, p, t)?;
+        f.write_str("\n")?;
+        parents.push(p);
+    }
+    Ok(())
+}
+
+impl<P: Prefix, T> PrefixMap<P, T> {
+    /// Get an adapter that displays all entries as comma-separated lines `prefix,value` in
+    /// lexicographic order. The entries are written directly into the formatter, without
+    /// allocating a `String` for each of them. Use `write!` to export the map into any
+    /// `std::io::Write`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/16".parse()?, 1);
+    /// pm.insert("10.0.0.0/8".parse()?, 2);
+    /// assert_eq!(pm.display_csv().to_string(), "10.0.0.0/8,2\n192.168.0.0/16,1\n");
+    ///
+    /// let mut out = Vec::new();
+    /// std::io::Write::write_fmt(&mut out, format_args!("{}", pm.display_csv()))?;
+    /// assert_eq!(out, b"10.0.0.0/8,2\n192.168.0.0/16,1\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn display_csv(&self) -> DisplayCsv<'_, P, T> {
+        DisplayCsv(self)
+    }
+
+    /// Get an adapter that displays all entries as `prefix: value` on separate lines in
+    /// lexicographic order, where each entry is indented by two spaces for every other entry
+    /// that contains it.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, "customers");
+    /// pm.insert("10.1.0.0/16".parse()?, "alice");
+    /// pm.insert("10.1.1.0/24".parse()?, "alice-dmz");
+    /// pm.insert("10.2.0.0/16".parse()?, "bob");
+    /// pm.insert("192.168.0.0/16".parse()?, "internal");
+    /// assert_eq!(
+    ///     pm.format_tree().to_string(),
+    ///     "10.0.0.0/8: customers
+    ///   10.1.0.0/16: alice
+    ///     10.1.1.0/24: alice-dmz
+    ///   10.2.0.0/16: bob
+    /// 192.168.0.0/16: internal
+    /// "
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn format_tree(&self) -> DisplayTree<'_, P, T> {
+        DisplayTree(self)
+    }
+}
+
+impl<P: Prefix> PrefixSet<P> {
+    /// Get an adapter that displays all elements on separate lines in lexicographic order. The
+    /// elements are written directly into the formatter, without allocating a `String` for each
+    /// of them. Use `write!` to export the set into any `std::io::Write`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("192.168.0.0/16".parse()?);
+    /// set.insert("10.0.0.0/8".parse()?);
+    /// assert_eq!(set.display_lines().to_string(), "10.0.0.0/8\n192.168.0.0/16\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn display_lines(&self) -> DisplayLines<'_, P> {
+        DisplayLines(self)
+    }
+
+    /// Get an adapter that displays all elements on separate lines in lexicographic order, where
+    /// each element is indented by two spaces for every other element that contains it.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("10.0.0.0/8".parse()?);
+    /// set.insert("10.1.0.0/16".parse()?);
+    /// set.insert("11.0.0.0/8".parse()?);
+    /// assert_eq!(
+    ///     set.format_tree().to_string(),
+    ///     "10.0.0.0/8\n  10.1.0.0/16\n11.0.0.0/8\n"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn format_tree(&self) -> DisplaySetTree<'_, P> {
+        DisplaySetTree(self)
+    }
+}
+
+#[cfg(feature = "dot")]
+impl<P: Prefix + Display, T: Debug> PrefixMap<P, T> {
+    /// Export the tree structure in the Graphviz DOT format. Each node of the tree is drawn as a
+    /// box labelled with its prefix, and nodes that store a value also show the value (formatted
+    /// with [`Debug`]). Branch nodes without a value are drawn dashed. Edges are labelled with
+    /// the bit (`0` or `1`) that leads to the child.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("11.0.0.0/8".parse()?, 2);
+    /// assert_eq!(
+    ///     pm.to_dot(),
+    ///     r#"digraph PrefixMap {
+    ///   node [shape=box];
+    ///   n0 [label="0.0.0.0/0", style=dashed];
+    ///   n0 -> n2 [label="0"];
+    ///   n2 [label="10.0.0.0/7", style=dashed];
+    ///   n2 -> n1 [label="0"];
+    ///   n2 -> n3 [label="1"];
+    ///   n1 [label="10.0.0.0/8\n1"];
+    ///   n3 [label="11.0.0.0/8\n2"];
+    /// }
+    /// "#
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_dot(&self) -> alloc::string::String {
+        use alloc::{format, string::String, vec};
+        use core::fmt::Write;
+
+        let escape = |s: String| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut out = String::from("digraph PrefixMap {\n  node [shape=box];\n");
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            let node = &self.table[idx];
+            let prefix = escape(format!("{}", node.prefix));
+            // writing into a `String` cannot fail.
+            let _ = match &node.value {
+                Some(v) => writeln!(
+                    out,
+                    "  n{idx} [label=\"{prefix}\\n{}\"];",
+                    escape(format!("{v:?}"))
+                ),
+                None => writeln!(out, "  n{idx} [label=\"{prefix}\", style=dashed];"),
+            };
+            for (child, bit) in [(node.left(), 0), (node.right(), 1)] {
+                if let Some(child) = child {
+                    let _ = writeln!(out, "  n{idx} -> n{child} [label=\"{bit}\"];");
+                }
+            }
+            stack.extend(node.right());
+            stack.extend(node.left());
+        }
+        out.push_str("}\n");
+        out
+    }
+}
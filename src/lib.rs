@@ -32,6 +32,13 @@
 //! done as follows: we look at the most significant bit that is **not** part of the prefix
 //! itself. If it is not set, then we take the left branch, and otherwise, we take the right one.
 //!
+//! The tree is path-compressed: Since each node stores its entire prefix, a child may be many bits
+//! longer than its parent, and there are no chains of nodes with a single child. Nodes exist only
+//! for prefixes that were inserted, and for the branching points where two of them diverge (whose
+//! `value` is `None`). Hence, a tree with `n` entries has at most `2n + 1` nodes, independent of
+//! the prefix lengths, and a sparse IPv6 table with a few `/128` prefixes requires as few nodes as
+//! an IPv4 table with the same number of entries.
+//!
 //! # Traversals
 //!
 //! Any iteration over all elements in the tree is implemented as a graph traversal that will yield
@@ -73,27 +80,78 @@
 //!   remove a value from a node. As soon as you call `remove_keep_tree` once on a tree structure,
 //!   the tree will no longer be optimal.
 //!
+//! # Features
+//!
+//! - `std` (enabled by default): Link against the standard library. Without this feature, the crate
+//!   is `#![no_std]` and only requires `alloc`. The implementations of [`Prefix`] for
+//!   `ipnet::Ipv4Net` and `ipnet::Ipv6Net` are available in both cases, as `ipnet` builds on
+//!   `core::net`.
+//! - `ipnet` (enabled by default): Implement [`Prefix`] for `ipnet::Ipv4Net` and
+//!   `ipnet::Ipv6Net`, and provide `RoutingTable`, the `compat` adapters, and
+//!   `map::dual_stack_summary`, which are all keyed by `ipnet` types. Without this feature, the
+//!   crate does not depend on `ipnet`, and prefixes can be stored as `(Ipv4Addr, u8)` or
+//!   `(Ipv6Addr, u8)` instead. The `mrt` feature enables this feature.
+//! - `serde`: Implement `Serialize` and `Deserialize` for [`PrefixMap`] and [`PrefixSet`]. A map is
+//!   serialized as a sequence of `(prefix, value)` pairs, and a set as a sequence of prefixes, both
+//!   in lexicographic order.
+//! - `checked`: Defensive checks for custom implementations of [`Prefix`], using
+//!   `check_prefix`, `PrefixMap::check_tree`, and checked variants of `insert`, `remove`,
+//!   `get_lpm`, and `children`. They return a `PrefixError` instead of descending into a degenerate
+//!   tree, whose depth is no longer bounded by the number of bits, for inconsistent prefixes.
+//! - `concurrent`: Share a [`PrefixMap`] between many reader threads and a single writer using
+//!   `concurrent::new`, where reads are wait-free and never wait for the writer to apply its
+//!   changes.
+//! - `dot`: Export the tree structure of a [`PrefixMap`] in the Graphviz DOT format using
+//!   `PrefixMap::to_dot`, including all branch nodes.
+//! - `mrt`: Load MRT `TABLE_DUMP_V2` RIB dumps (RFC 6396) into a [`PrefixMap`] for each address
+//!   family, using `mrt::load_rib`.
+//! - `rand`: Pick random entries or random covered addresses of a [`PrefixMap`] or a [`PrefixSet`]
+//!   using `PrefixMap::sample` and `PrefixMap::sample_addr`.
+//! - `rayon`: Parallel iterators over a [`PrefixMap`] that split the work along its subtrees, using
+//!   `PrefixMap::par_iter`, `PrefixMap::par_values`, and `PrefixMap::par_iter_mut`.
+//! - `test-utils`: Check the consistency of the internal tree structure using
+//!   `PrefixMap::assert_invariants` and `PrefixSet::assert_invariants`, e.g., in a fuzzing harness.
+//!
 //! # TODO
 //!
 //! Migrate to a TreeBitMap, described by
 //! [W. Eatherton, Z. Dittia, G. Varghes](https://doi.org/10.1145/997150.997160).
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![allow(clippy::collapsible_else_if)]
 #![deny(missing_docs)]
 
+extern crate alloc;
+
+#[cfg(feature = "ipnet")]
+pub mod compat;
+#[cfg(feature = "concurrent")]
+pub mod concurrent;
+pub mod fixed;
 mod fmt;
+pub mod mac;
+#[cfg(feature = "mrt")]
+pub mod mrt;
 mod prefix;
 #[cfg(feature = "serde")]
 mod serde;
-#[cfg(test)]
+#[cfg(all(test, feature = "ipnet"))]
 mod test;
 
 pub mod map;
+pub mod persistent;
 pub mod set;
+pub mod shadow;
+#[cfg(feature = "ipnet")]
+mod table;
 
 pub use map::PrefixMap;
-pub use prefix::Prefix;
+#[cfg(feature = "checked")]
+pub use prefix::{check_prefix, PrefixError};
+pub use prefix::{Prefix, PrefixQuery};
 pub use set::PrefixSet;
+#[cfg(feature = "ipnet")]
+pub use table::RoutingTable;
 
 #[inline(always)]
 pub(crate) fn to_right<P: Prefix>(branch_p: &P, child_p: &P) -> bool {
@@ -0,0 +1,127 @@
+//! Prefixes of MAC addresses (EUI-48).
+//!
+//! A [`MacPrefix`] is a MAC address together with a mask length of up to 48 bits, e.g., an OUI
+//! block like `00:1a:2b:00:00:00/24`. EUI-64 identifiers fit into a primitive integer, so they can
+//! be stored as `([u8; 8], u8)` directly.
+
+use core::{fmt, str::FromStr};
+
+use crate::Prefix;
+
+/// The number of bits in a MAC address.
+const MAC_BITS: u8 = 48;
+
+/// A prefix of a MAC address (EUI-48). The address is stored with all host bits cleared.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::mac::MacPrefix;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut vendors: PrefixMap<MacPrefix, _> = PrefixMap::new();
+/// vendors.insert("00:1a:2b:00:00:00/24".parse()?, "Vendor A");
+/// vendors.insert("00:1a:2b:c0:00:00/28".parse()?, "Vendor B");
+/// let device: MacPrefix = "00:1a:2b:c3:44:55".parse()?;
+/// assert_eq!(vendors.get_lpm(&device).map(|(_, v)| *v), Some("Vendor B"));
+/// assert_eq!(device.to_string(), "00:1a:2b:c3:44:55/48");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MacPrefix {
+    /// The address in the upper 48 bits.
+    repr: u64,
+    len: u8,
+}
+
+impl MacPrefix {
+    /// Create a new prefix from the address and the mask length, clearing all host bits. Returns
+    /// `None` if `len` is larger than 48.
+    pub fn new(addr: [u8; 6], len: u8) -> Option<Self> {
+        if len > MAC_BITS {
+            return None;
+        }
+        let mut bytes = [0; 8];
+        bytes[..6].copy_from_slice(&addr);
+        let repr = u64::from_be_bytes(bytes) & crate::prefix::mask_from_prefix_len::<u64>(len);
+        Some(Self { repr, len })
+    }
+
+    /// Get the (masked) address of the prefix.
+    pub fn addr(&self) -> [u8; 6] {
+        let bytes = self.repr.to_be_bytes();
+        [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5]]
+    }
+}
+
+impl Prefix for MacPrefix {
+    type R = u64;
+
+    fn repr(&self) -> u64 {
+        self.repr
+    }
+
+    fn prefix_len(&self) -> u8 {
+        self.len
+    }
+
+    fn from_repr_len(repr: u64, len: u8) -> Self {
+        let repr = repr & crate::prefix::mask_from_prefix_len::<u64>(len);
+        Self { repr, len }
+    }
+
+    fn mask(&self) -> u64 {
+        self.repr
+    }
+
+    fn eq(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl fmt::Display for MacPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.addr();
+        write!(
+            f,
+            "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}/{}",
+            self.len
+        )
+    }
+}
+
+/// The error returned when parsing an invalid [`MacPrefix`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacPrefixParseError;
+
+impl fmt::Display for MacPrefixParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid MAC address prefix")
+    }
+}
+
+impl core::error::Error for MacPrefixParseError {}
+
+impl FromStr for MacPrefix {
+    type Err = MacPrefixParseError;
+
+    /// Parse a prefix of the form `aa:bb:cc:dd:ee:ff/len`, where the bytes may also be separated by
+    /// `-`. Without a mask length, the prefix has length 48.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, len) = match s.split_once('/') {
+            Some((addr, len)) => (addr, len.parse().map_err(|_| MacPrefixParseError)?),
+            None => (s, MAC_BITS),
+        };
+        let mut bytes = [0; 6];
+        let mut parts = addr.split([':', '-']);
+        for byte in bytes.iter_mut() {
+            let part = parts.next().filter(|p| p.len() == 2);
+            *byte = part
+                .and_then(|p| u8::from_str_radix(p, 16).ok())
+                .ok_or(MacPrefixParseError)?;
+        }
+        if parts.next().is_some() {
+            return Err(MacPrefixParseError);
+        }
+        Self::new(bytes, len).ok_or(MacPrefixParseError)
+    }
+}
@@ -0,0 +1,151 @@
+//! Value-aware aggregation of a [`PrefixMap`].
+
+use alloc::{vec, vec::Vec};
+
+use num_traits::{PrimInt, Zero};
+
+use crate::prefix::mask_from_prefix_len;
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Aggregate the map by merging entries whose values are compatible, and return the
+    /// aggregated map. The function `f` decides whether two values can be merged, and returns the
+    /// merged value (or `None` if they must be kept separate). Two kinds of entries are merged:
+    ///
+    /// - Two sibling entries (with the same prefix length and the same parent prefix) are replaced
+    ///   by their parent prefix. The function is called as `f(left, right)`. If the map already
+    ///   contains the parent prefix, its value is replaced, as it was shadowed entirely by both
+    ///   siblings.
+    /// - An entry that is covered by another entry (its longest prefix match without itself) is
+    ///   removed. The function is called as `f(covering, covered)`, and the result is stored in the
+    ///   covering entry.
+    ///
+    /// The map is aggregated bottom-up, starting with the longest prefixes, such that merged
+    /// entries can be aggregated further.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, "a");
+    /// pm.insert("10.0.1.0/24".parse()?, "a");
+    /// pm.insert("10.0.2.0/24".parse()?, "b");
+    /// pm.insert("10.0.2.0/25".parse()?, "b");
+    /// pm.insert("10.0.2.128/25".parse()?, "c");
+    /// let aggr = pm.aggregate_with(|a, b| (a == b).then_some(*a));
+    /// assert_eq!(
+    ///     aggr.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("10.0.0.0/23".parse()?, "a"),
+    ///         ("10.0.2.0/24".parse()?, "b"),
+    ///         ("10.0.2.128/25".parse()?, "c"),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn aggregate_with<F>(&self, f: F) -> Self
+    where
+        P: Clone,
+        T: Clone,
+        F: FnMut(&T, &T) -> Option<T>,
+    {
+        // never cancelled
+        self.try_aggregate_with(f, || false).unwrap()
+    }
+
+    /// Aggregate the map like [`PrefixMap::aggregate_with`], but stop early if the operation gets
+    /// cancelled. The function `cancelled` is called regularly during the aggregation (usually
+    /// once per entry). As soon as it returns `true`, the aggregation is aborted and `None` is
+    /// returned. This allows an interactive application to abort the aggregation of a large map,
+    /// e.g., by checking an `AtomicBool` that is set from a different thread.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, 1);
+    /// pm.insert("10.0.1.0/24".parse()?, 1);
+    /// let merge = |a: &i32, b: &i32| (a == b).then_some(*a);
+    /// let cancel = AtomicBool::new(false);
+    /// let aggr = pm.try_aggregate_with(merge, || cancel.load(Ordering::Relaxed));
+    /// assert_eq!(aggr.map(|m| m.into_iter().count()), Some(1));
+    /// cancel.store(true, Ordering::Relaxed);
+    /// assert!(pm.try_aggregate_with(merge, || cancel.load(Ordering::Relaxed)).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_aggregate_with<F, C>(&self, mut f: F, mut cancelled: C) -> Option<Self>
+    where
+        P: Clone,
+        T: Clone,
+        F: FnMut(&T, &T) -> Option<T>,
+        C: FnMut() -> bool,
+    {
+        let mut map = self.clone();
+        let max_len = P::R::zero().count_zeros() as usize;
+        let mut levels: Vec<Vec<P>> = vec![Vec::new(); max_len + 1];
+        for p in self.keys() {
+            if cancelled() {
+                return None;
+            }
+            levels[p.prefix_len() as usize].push(p.clone());
+        }
+
+        for len in (1..=max_len).rev() {
+            let mut prefixes = core::mem::take(&mut levels[len]);
+            // prefixes created by merging siblings are appended out of order.
+            prefixes.sort_by_key(|p| p.mask());
+            prefixes.dedup_by_key(|p| p.mask());
+            let parent_mask: P::R = mask_from_prefix_len(len as u8 - 1);
+            let parent_of = |p: &P| P::from_repr_len(p.mask() & parent_mask, len as u8 - 1);
+
+            // merge siblings. Both siblings are adjacent in `prefixes`.
+            let mut remaining = Vec::with_capacity(prefixes.len());
+            let mut iter = prefixes.into_iter().peekable();
+            while let Some(left) = iter.next() {
+                if cancelled() {
+                    return None;
+                }
+                let parent = parent_of(&left);
+                let right = iter.next_if(|right| parent_of(right).mask() == parent.mask());
+                let Some(right) = right else {
+                    remaining.push(left);
+                    continue;
+                };
+                match f(map.get(&left).unwrap(), map.get(&right).unwrap()) {
+                    Some(value) => {
+                        map.remove(&left);
+                        map.remove(&right);
+                        map.insert(parent.clone(), value);
+                        levels[len - 1].push(parent);
+                    }
+                    None => remaining.extend([left, right]),
+                }
+            }
+
+            // merge covered entries into their longest prefix match.
+            for p in remaining {
+                if cancelled() {
+                    return None;
+                }
+                let Some((covering, value)) = map.get_lpm(&parent_of(&p)) else {
+                    continue;
+                };
+                if let Some(value) = f(value, map.get(&p).unwrap()) {
+                    let covering = covering.clone();
+                    map.insert(covering, value);
+                    map.remove(&p);
+                }
+            }
+        }
+        Some(map)
+    }
+}
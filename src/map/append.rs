@@ -0,0 +1,143 @@
+//! Move all entries of one map into another.
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Move all entries from `other` into `self`. If a prefix is present in both maps, the new
+    /// value is computed by `on_conflict(prefix, value_in_self, value_in_other)`.
+    ///
+    /// Instead of inserting each entry of `other` separately (starting at the root), the trees are
+    /// merged structurally. Whenever a subtree of `other` falls into an empty position of `self`,
+    /// all of its nodes are moved over without traversing `self` again. If `self` is empty, `other`
+    /// simply replaces it.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut a: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// a.insert("10.0.0.0/8".parse()?, 1);
+    /// a.insert("192.168.0.0/16".parse()?, 2);
+    /// let mut b: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// b.insert("10.0.0.0/8".parse()?, 10);
+    /// b.insert("10.1.0.0/16".parse()?, 20);
+    /// a.append(b, |_, x, y| x + y);
+    /// assert_eq!(
+    ///     a.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("10.0.0.0/8".parse()?, 11),
+    ///         ("10.1.0.0/16".parse()?, 20),
+    ///         ("192.168.0.0/16".parse()?, 2),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn append<F>(&mut self, other: PrefixMap<P, T>, mut on_conflict: F)
+    where
+        F: FnMut(&P, T, T) -> T,
+    {
+        let root = &self.table[0];
+        if root.value.is_none() && root.left().is_none() && root.right().is_none() {
+            *self = other;
+            return;
+        }
+        let mut other = other.table;
+        // both roots have the same prefix.
+        if let Some(value) = other[0].value.take() {
+            self.merge_value(0, value, &mut on_conflict);
+        }
+        for child in [other[0].left(), other[0].right()].into_iter().flatten() {
+            self.append_subtree(0, &mut other, child, &mut on_conflict);
+        }
+    }
+
+    /// Store `value` in the node `idx`, resolving a conflict with `on_conflict`.
+    fn merge_value<F>(&mut self, idx: usize, value: T, on_conflict: &mut F)
+    where
+        F: FnMut(&P, T, T) -> T,
+    {
+        let value = match self.table[idx].value.take() {
+            Some(old) => on_conflict(&self.table[idx].prefix, old, value),
+            None => value,
+        };
+        self.table[idx].value = Some(value);
+    }
+
+    /// Merge the subtree rooted at `other[node]` into `self`, starting the search at `at`, which
+    /// must contain the prefix of `other[node]`.
+    pub(super) fn append_subtree<F>(
+        &mut self,
+        mut at: usize,
+        other: &mut [Node<P, T>],
+        node: usize,
+        on_conflict: &mut F,
+    ) where
+        F: FnMut(&P, T, T) -> T,
+    {
+        loop {
+            match self.get_direction_for_insert(at, &other[node].prefix) {
+                DirectionForInsert::Enter { next, .. } => at = next,
+                DirectionForInsert::Reached => {
+                    if let Some(value) = other[node].value.take() {
+                        self.merge_value(at, value, on_conflict);
+                    }
+                    break;
+                }
+                DirectionForInsert::NewLeaf { right } => {
+                    let new = self.move_subtree(other, node);
+                    self.set_child(at, new, right);
+                    return;
+                }
+                DirectionForInsert::NewBranch {
+                    branch_prefix,
+                    right,
+                    prefix_right,
+                } => {
+                    let branch = self.new_node(branch_prefix, None);
+                    let new = self.move_subtree(other, node);
+                    let child = self.set_child(at, branch, right).unwrap();
+                    self.set_child(branch, new, prefix_right);
+                    self.set_child(branch, child, !prefix_right);
+                    return;
+                }
+                DirectionForInsert::NewChild { right, child_right } => {
+                    // nodes without a value are only branches in `other`, and are not needed here.
+                    if let Some(value) = other[node].value.take() {
+                        let prefix = core::mem::replace(&mut other[node].prefix, P::zero());
+                        let new = self.new_node(prefix, Some(value));
+                        let child = self.set_child(at, new, right).unwrap();
+                        self.set_child(new, child, child_right);
+                        at = new;
+                    }
+                    break;
+                }
+            }
+        }
+        for child in [other[node].left(), other[node].right()]
+            .into_iter()
+            .flatten()
+        {
+            self.append_subtree(at, other, child, on_conflict);
+        }
+    }
+
+    /// Move the subtree rooted at `other[node]` into the table of `self`, and return the index of
+    /// its new root. The new root is not yet attached to any node in `self`.
+    pub(super) fn move_subtree(&mut self, other: &mut [Node<P, T>], node: usize) -> usize {
+        let prefix = core::mem::replace(&mut other[node].prefix, P::zero());
+        let new = self.new_node(prefix, other[node].value.take());
+        if let Some(left) = other[node].left() {
+            let left = self.move_subtree(other, left);
+            self.set_child(new, left, false);
+        }
+        if let Some(right) = other[node].right() {
+            let right = self.move_subtree(other, right);
+            self.set_child(new, right, true);
+        }
+        new
+    }
+}
@@ -0,0 +1,369 @@
+//! A prefix map that maintains an aggregate of the values in every subtree.
+
+use alloc::{vec, vec::Vec};
+use core::marker::PhantomData;
+
+use super::*;
+
+/// An aggregation of values, used by [`AugmentedPrefixMap`]. The aggregate of multiple values is
+/// computed by combining the summaries of the individual values. `combine` must be associative,
+/// and `empty` must be its identity, i.e., they must form a monoid. Values are combined in
+/// lexicographic order of their prefixes.
+pub trait Monoid<T> {
+    /// The aggregate of a set of values.
+    type Summary: Clone;
+
+    /// The aggregate of no values.
+    fn empty() -> Self::Summary;
+
+    /// The aggregate of a single value.
+    fn lift(value: &T) -> Self::Summary;
+
+    /// Combine the aggregates of two disjoint sets of values, where all values of `a` come before
+    /// all values of `b`.
+    fn combine(a: &Self::Summary, b: &Self::Summary) -> Self::Summary;
+}
+
+/// The sum of all values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Sum;
+
+impl<T> Monoid<T> for Sum
+where
+    T: Clone + Default + core::ops::Add<Output = T>,
+{
+    type Summary = T;
+
+    fn empty() -> T {
+        T::default()
+    }
+
+    fn lift(value: &T) -> T {
+        value.clone()
+    }
+
+    fn combine(a: &T, b: &T) -> T {
+        a.clone() + b.clone()
+    }
+}
+
+/// The largest value, or `None` if there are no values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Max;
+
+impl<T: Clone + Ord> Monoid<T> for Max {
+    type Summary = Option<T>;
+
+    fn empty() -> Option<T> {
+        None
+    }
+
+    fn lift(value: &T) -> Option<T> {
+        Some(value.clone())
+    }
+
+    fn combine(a: &Option<T>, b: &Option<T>) -> Option<T> {
+        a.clone().max(b.clone())
+    }
+}
+
+/// A [`PrefixMap`] that maintains the aggregate of all values in every subtree, given by the
+/// [`Monoid`] `M`. The aggregate of all entries contained within any prefix can be queried with
+/// [`AugmentedPrefixMap::aggregate`] in `O(depth)`, without visiting the entries themselves. In
+/// addition, the map maintains the number of entries in every subtree, see
+/// [`AugmentedPrefixMap::count_under`].
+/// Every modification updates the aggregates on the path from the root to the modified prefix.
+///
+/// The map can only be modified through the methods of this type, such that the aggregates remain
+/// up to date. Use [`AugmentedPrefixMap::map`] for all other queries.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::{AugmentedPrefixMap, Sum};
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut traffic: AugmentedPrefixMap<Ipv4Net, u64, Sum> = AugmentedPrefixMap::new();
+/// traffic.insert("10.0.0.0/16".parse()?, 100);
+/// traffic.insert("10.1.0.0/16".parse()?, 20);
+/// traffic.insert("10.1.1.0/24".parse()?, 3);
+/// traffic.insert("192.168.0.0/16".parse()?, 4000);
+/// assert_eq!(traffic.aggregate(&"10.0.0.0/8".parse()?), 123);
+/// assert_eq!(traffic.aggregate(&"10.1.0.0/16".parse()?), 23);
+/// assert_eq!(traffic.aggregate(&"0.0.0.0/0".parse()?), 4123);
+/// traffic.remove(&"10.0.0.0/16".parse()?);
+/// assert_eq!(traffic.aggregate(&"10.0.0.0/8".parse()?), 23);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AugmentedPrefixMap<P, T, M: Monoid<T>> {
+    map: PrefixMap<P, T>,
+    /// The aggregate of the subtree of each node, indexed like the node table.
+    summaries: Vec<M::Summary>,
+    /// The number of entries in the subtree of each node, indexed like the node table.
+    counts: Vec<usize>,
+    monoid: PhantomData<M>,
+}
+
+impl<P: Clone, T: Clone, M: Monoid<T>> Clone for AugmentedPrefixMap<P, T, M> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+            summaries: self.summaries.clone(),
+            counts: self.counts.clone(),
+            monoid: PhantomData,
+        }
+    }
+}
+
+impl<P: Prefix, T, M: Monoid<T>> Default for AugmentedPrefixMap<P, T, M> {
+    fn default() -> Self {
+        Self {
+            map: PrefixMap::new(),
+            summaries: vec![M::empty()],
+            counts: vec![0],
+            monoid: PhantomData,
+        }
+    }
+}
+
+impl<P: Prefix, T, M: Monoid<T>> AugmentedPrefixMap<P, T, M> {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the underlying map.
+    pub fn map(&self) -> &PrefixMap<P, T> {
+        &self.map
+    }
+
+    /// Take the underlying map, dropping the aggregates.
+    pub fn into_map(self) -> PrefixMap<P, T> {
+        self.map
+    }
+
+    /// Insert a new item into the map. If the key is already present, the old value is replaced
+    /// and returned.
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let key = P::from_repr_len(prefix.mask(), prefix.prefix_len());
+        let old = self.map.insert(prefix, value);
+        self.update_path(&key);
+        old
+    }
+
+    /// Remove an element from the map, returning its value.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let old = self.map.remove(prefix)?;
+        self.update_path(prefix);
+        Some(old)
+    }
+
+    /// Remove all entries that are contained within `prefix`.
+    pub fn remove_children(&mut self, prefix: &P) {
+        self.map.remove_children(prefix);
+        self.update_path(prefix);
+    }
+
+    /// Remove all entries from the map.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.summaries.clear();
+        self.summaries.push(M::empty());
+        self.counts.clear();
+        self.counts.push(0);
+    }
+
+    /// Get the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.counts[0]
+    }
+
+    /// Check whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the number of entries whose prefixes are contained within `prefix`, including `prefix`
+    /// itself. This is not `O(1)`: it follows the path from the root to `prefix`, which takes
+    /// time linear in the prefix length (at most one step per bit), but it never visits the
+    /// entries below `prefix`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::{AugmentedPrefixMap, Sum};
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: AugmentedPrefixMap<Ipv4Net, u32, Sum> = AugmentedPrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 1);
+    /// pm.insert("10.1.1.0/24".parse()?, 1);
+    /// pm.insert("192.168.0.0/16".parse()?, 1);
+    /// assert_eq!(pm.count_under(&"10.0.0.0/8".parse()?), 3);
+    /// assert_eq!(pm.count_under(&"10.1.0.0/20".parse()?), 1);
+    /// assert_eq!(pm.count_under(&"11.0.0.0/8".parse()?), 0);
+    /// assert_eq!(pm.len(), 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn count_under(&self, prefix: &P) -> usize {
+        self.subtree_root(prefix).map_or(0, |idx| self.counts[idx])
+    }
+
+    /// Get the aggregate of all values whose prefixes are contained within `prefix`, including
+    /// `prefix` itself.
+    pub fn aggregate(&self, prefix: &P) -> M::Summary {
+        match self.subtree_root(prefix) {
+            Some(idx) => self.summaries[idx].clone(),
+            None => M::empty(),
+        }
+    }
+
+    /// Get the aggregate of all values in the map.
+    pub fn total(&self) -> M::Summary {
+        self.summaries[0].clone()
+    }
+
+    /// Get the `k`-th entry (starting at zero) in lexicographic order, or `None` if the map has at
+    /// most `k` entries. This takes `O(depth)` time using the subtree counts.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::{AugmentedPrefixMap, Sum};
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: AugmentedPrefixMap<Ipv4Net, u32, Sum> = AugmentedPrefixMap::new();
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// pm.insert("192.168.0.0/16".parse()?, 3);
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// assert_eq!(pm.nth(0), Some((&"10.0.0.0/8".parse()?, &1)));
+    /// assert_eq!(pm.nth(2), Some((&"192.168.0.0/16".parse()?, &3)));
+    /// assert_eq!(pm.nth(3), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn nth(&self, mut k: usize) -> Option<(&P, &T)> {
+        let table = &self.map.table;
+        let mut idx = 0;
+        'outer: loop {
+            if let Some(value) = table[idx].value.as_ref() {
+                if k == 0 {
+                    return Some((&table[idx].prefix, value));
+                }
+                k -= 1;
+            }
+            for child in [table[idx].left(), table[idx].right()]
+                .into_iter()
+                .flatten()
+            {
+                if k < self.counts[child] {
+                    idx = child;
+                    continue 'outer;
+                }
+                k -= self.counts[child];
+            }
+            return None;
+        }
+    }
+
+    /// Get the number of entries that come before `prefix` in lexicographic order. If `prefix` is
+    /// present in the map, this is its position, such that `self.nth(self.rank(prefix))` returns
+    /// its entry. This takes `O(depth)` time using the subtree counts.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::{AugmentedPrefixMap, Sum};
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: AugmentedPrefixMap<Ipv4Net, u32, Sum> = AugmentedPrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// pm.insert("192.168.0.0/16".parse()?, 3);
+    /// assert_eq!(pm.rank(&"10.1.0.0/16".parse()?), 1);
+    /// assert_eq!(pm.rank(&"11.0.0.0/8".parse()?), 2);
+    /// assert_eq!(pm.rank(&"0.0.0.0/0".parse()?), 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rank(&self, prefix: &P) -> usize {
+        let table = &self.map.table;
+        let mut rank = 0;
+        let mut idx = 0;
+        loop {
+            let cur = &table[idx];
+            if cur.prefix.eq(prefix) {
+                return rank;
+            }
+            // `cur` contains `prefix`, so it comes first.
+            rank += usize::from(cur.value.is_some());
+            let right = to_right(&cur.prefix, prefix);
+            if right {
+                rank += cur.left().map_or(0, |c| self.counts[c]);
+            }
+            let Some(child) = self.map.get_child(idx, right) else {
+                return rank;
+            };
+            let child_p = &table[child].prefix;
+            if child_p.contains(prefix) {
+                idx = child;
+            } else {
+                // the subtree of the child comes either entirely before or entirely after `prefix`.
+                if !prefix.contains(child_p) && child_p.mask() < prefix.mask() {
+                    rank += self.counts[child];
+                }
+                return rank;
+            }
+        }
+    }
+
+    /// Find the first node on the path to `prefix` that is contained within `prefix`.
+    fn subtree_root(&self, prefix: &P) -> Option<usize> {
+        let table = &self.map.table;
+        let mut idx = 0;
+        loop {
+            if prefix.contains(&table[idx].prefix) {
+                return Some(idx);
+            }
+            let child = self
+                .map
+                .get_child(idx, to_right(&table[idx].prefix, prefix))?;
+            if !table[child].prefix.contains(prefix) && !prefix.contains(&table[child].prefix) {
+                return None;
+            }
+            idx = child;
+        }
+    }
+
+    /// Recompute the aggregates of all nodes on the path from the root towards `prefix`. Only the
+    /// subtrees of these nodes change when inserting or removing `prefix`.
+    fn update_path(&mut self, prefix: &P) {
+        self.summaries.resize(self.map.table.len(), M::empty());
+        self.counts.resize(self.map.table.len(), 0);
+        let mut path = vec![0];
+        let mut idx = 0;
+        while let Direction::Enter { next, .. } = self.map.get_direction(idx, prefix) {
+            path.push(next);
+            idx = next;
+        }
+        for idx in path.into_iter().rev() {
+            let node = &self.map.table[idx];
+            let mut summary = node.value.as_ref().map_or_else(M::empty, M::lift);
+            let mut count = usize::from(node.value.is_some());
+            for child in [node.left(), node.right()].into_iter().flatten() {
+                summary = M::combine(&summary, &self.summaries[child]);
+                count += self.counts[child];
+            }
+            self.summaries[idx] = summary;
+            self.counts[idx] = count;
+        }
+    }
+}
+
+impl<P: Prefix, T, M: Monoid<T>> FromIterator<(P, T)> for AugmentedPrefixMap<P, T, M> {
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (p, t) in iter {
+            map.insert(p, t);
+        }
+        map
+    }
+}
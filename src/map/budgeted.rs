@@ -0,0 +1,112 @@
+//! Resumable iteration in batches of bounded size.
+
+use core::{iter::Peekable, ops::Bound};
+
+use super::*;
+
+/// The position at which a budgeted iteration can be resumed. The token stores the last prefix
+/// that was yielded, so it stays valid even if the map is modified between two batches: the
+/// iteration continues with the next prefix in lexicographic order. See
+/// [`PrefixMap::iter_budgeted`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumeToken<P>(Option<P>);
+
+/// An iterator that yields at most a given number of entries of a [`PrefixMap`] in lexicographic
+/// order. Once the batch is exhausted, [`Budgeted::resume_token`] returns the token to resume the
+/// iteration with [`PrefixMap::resume_budgeted`]. See [`PrefixMap::iter_budgeted`].
+pub struct Budgeted<'a, P: Prefix, T> {
+    iter: Peekable<Range<'a, P, T>>,
+    budget: usize,
+    /// The last element that was yielded, or the position from where the batch started.
+    last: Last<'a, P>,
+}
+
+enum Last<'a, P> {
+    Yielded(&'a P),
+    Resumed(Option<P>),
+}
+
+impl<'a, P: Prefix, T> Budgeted<'a, P, T> {
+    /// Get the token to resume the iteration after the last yielded element, or `None` if the map
+    /// contains no further elements.
+    pub fn resume_token(&mut self) -> Option<ResumeToken<P>>
+    where
+        P: Clone,
+    {
+        self.iter.peek()?;
+        Some(ResumeToken(match &self.last {
+            Last::Yielded(p) => Some((*p).clone()),
+            Last::Resumed(p) => p.clone(),
+        }))
+    }
+}
+
+impl<'a, P: Prefix, T> Iterator for Budgeted<'a, P, T> {
+    type Item = (&'a P, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.budget == 0 {
+            return None;
+        }
+        let (p, t) = self.iter.next()?;
+        self.budget -= 1;
+        self.last = Last::Yielded(p);
+        Some((p, t))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.budget))
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Iterate over at most `budget` entries in lexicographic order, and get a token to resume
+    /// the iteration afterwards. This allows iterating over a large map in small batches, e.g.,
+    /// to yield back to an async executor in between. Resuming the iteration only needs to walk
+    /// down the tree once (`O(depth)`), and does not need to skip the elements of previous batches.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("192.168.2.0/23".parse()?, 3);
+    /// let mut batch = pm.iter_budgeted(2);
+    /// assert_eq!(batch.by_ref().map(|(_, t)| *t).collect::<Vec<_>>(), vec![1, 2]);
+    /// let token = batch.resume_token().unwrap();
+    /// let mut batch = pm.resume_budgeted(&token, 2);
+    /// assert_eq!(batch.by_ref().map(|(_, t)| *t).collect::<Vec<_>>(), vec![3]);
+    /// assert_eq!(batch.resume_token(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_budgeted(&self, budget: usize) -> Budgeted<'_, P, T> {
+        Budgeted {
+            iter: self.range(..).peekable(),
+            budget,
+            last: Last::Resumed(None),
+        }
+    }
+
+    /// Resume a budgeted iteration after the position stored in `token`, yielding at most
+    /// `budget` entries. See [`PrefixMap::iter_budgeted`].
+    pub fn resume_budgeted(&self, token: &ResumeToken<P>, budget: usize) -> Budgeted<'_, P, T>
+    where
+        P: Clone,
+    {
+        let iter = match &token.0 {
+            Some(last) => self.range((Bound::Excluded(last), Bound::Unbounded)),
+            None => self.range(..),
+        };
+        Budgeted {
+            iter: iter.peekable(),
+            budget,
+            last: Last::Resumed(token.0.clone()),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Operations that validate the [`Prefix`] implementation before using it.
+//!
+//! The tree stays acyclic for any implementation of [`Prefix`], so every traversal terminates.
+//! However, if [`Prefix::contains`] is inconsistent with the prefix lengths, inserting prefixes
+//! can build a chain of nodes that is much deeper than the number of bits in the representation.
+//! Lookups then take time linear in the number of entries, and recursive operations (like
+//! [`PrefixMap::retain`]) may overflow the stack. The checked operations bound the traversal depth
+//! by the number of bits, and [`PrefixMap::check_tree`] validates the entire tree before using
+//! operations that have no checked variant, like iterators and cursors.
+
+use alloc::vec;
+
+use num_traits::{PrimInt, Zero};
+
+use crate::prefix::{check_prefix, PrefixError};
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Insert a new item into the map like [`PrefixMap::insert`], but first check that `prefix`
+    /// is consistent using [`check_prefix`], and that the path to it is not deeper than the number
+    /// of bits in the representation. The map is not modified if the check fails.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// assert_eq!(pm.insert_checked("192.168.0.0/23".parse()?, 1), Ok(None));
+    /// assert_eq!(pm.insert_checked("192.168.0.0/23".parse()?, 2), Ok(Some(1)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_checked(&mut self, prefix: P, value: T) -> Result<Option<T>, PrefixError> {
+        let (depth, reached) = self.walk_checked(&prefix)?;
+        // a new node is added below the last node on the path.
+        if !reached && depth + 1 >= max_depth::<P>() {
+            return Err(PrefixError::DepthExceeded);
+        }
+        Ok(self.insert(prefix, value))
+    }
+
+    /// Remove an element from the map like [`PrefixMap::remove`], but first check that `prefix`
+    /// is consistent using [`check_prefix`], and that the path to it is not deeper than the number
+    /// of bits in the representation. The map is not modified if the check fails.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// assert_eq!(pm.remove_checked(&"192.168.0.0/23".parse()?), Ok(Some(1)));
+    /// assert_eq!(pm.remove_checked(&"192.168.0.0/23".parse()?), Ok(None));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove_checked(&mut self, prefix: &P) -> Result<Option<T>, PrefixError> {
+        self.walk_checked(prefix)?;
+        Ok(self.remove(prefix))
+    }
+
+    /// Get the longest prefix match like [`PrefixMap::get_lpm`], but first check that `prefix`
+    /// is consistent using [`check_prefix`], and stop the traversal with
+    /// [`PrefixError::DepthExceeded`] if it visits more nodes than there are bits in the
+    /// representation.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// assert_eq!(
+    ///     pm.get_lpm_checked(&"192.168.1.1/32".parse()?),
+    ///     Ok(Some((&"192.168.0.0/23".parse()?, &1)))
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_lpm_checked<'a>(
+        &'a self,
+        prefix: &P,
+    ) -> Result<Option<(&'a P, &'a T)>, PrefixError> {
+        check_prefix(prefix)?;
+        let mut idx = 0;
+        let mut best_match: Option<(&P, &T)> = None;
+        for _ in 0..max_depth::<P>() {
+            best_match = self.table[idx].prefix_value().or(best_match);
+            match self.get_direction(idx, prefix) {
+                Direction::Enter { next, .. } => idx = next,
+                _ => return Ok(best_match),
+            }
+        }
+        Err(PrefixError::DepthExceeded)
+    }
+
+    /// Iterate over all children of `prefix` like [`PrefixMap::children`], but first check that
+    /// `prefix` is consistent using [`check_prefix`], and that the path to it is not deeper than
+    /// the number of bits in the representation. Use [`PrefixMap::check_tree`] to also validate
+    /// the subtree that is traversed by the iterator.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// pm.insert("192.168.1.0/24".parse()?, 2);
+    /// assert_eq!(pm.children_checked(&"192.168.1.0/24".parse()?)?.count(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn children_checked(&self, prefix: &P) -> Result<Iter<'_, P, T>, PrefixError> {
+        self.walk_checked(prefix)?;
+        Ok(self.children(prefix))
+    }
+
+    /// Check that the entire tree is consistent. Every prefix in the tree must pass
+    /// [`check_prefix`], and every child must be strictly contained in its parent, which bounds
+    /// the depth of the tree by the number of bits in the representation. If this check passes,
+    /// all operations on the map (including iterators, cursors, and recursive operations like
+    /// [`PrefixMap::retain`]) only traverse paths of bounded depth, until the map is modified
+    /// again. This operation is `O(n)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// pm.insert("192.168.1.0/24".parse()?, 2);
+    /// assert_eq!(pm.check_tree(), Ok(()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn check_tree(&self) -> Result<(), PrefixError> {
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            let node = &self.table[idx];
+            check_prefix(&node.prefix)?;
+            for child in [node.left(), node.right()].into_iter().flatten() {
+                let child_p = &self.table[child].prefix;
+                if child_p.prefix_len() <= node.prefix.prefix_len()
+                    || !node.prefix.contains(child_p)
+                {
+                    return Err(PrefixError::InconsistentContains);
+                }
+                stack.push(child);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check `prefix` using [`check_prefix`], and follow the path to it, returning
+    /// [`PrefixError::DepthExceeded`] if it is deeper than the number of bits in the
+    /// representation. All operations on a single prefix follow this path. On success, return
+    /// the depth of the last node on the path (the root has depth zero), and whether it stores
+    /// `prefix` itself.
+    fn walk_checked(&self, prefix: &P) -> Result<(u32, bool), PrefixError> {
+        check_prefix(prefix)?;
+        let mut idx = 0;
+        for depth in 0..max_depth::<P>() {
+            match self.get_direction(idx, prefix) {
+                Direction::Enter { next, .. } => idx = next,
+                Direction::Reached => return Ok((depth, true)),
+                Direction::Missing => return Ok((depth, false)),
+            }
+        }
+        Err(PrefixError::DepthExceeded)
+    }
+}
+
+/// The maximum number of nodes on any path from the root. Every step enters a strictly longer
+/// prefix, and the root has length zero.
+fn max_depth<P: Prefix>() -> u32 {
+    P::R::zero().count_zeros() + 1
+}
@@ -0,0 +1,183 @@
+//! Streaming longest-prefix match classification of addresses.
+
+use alloc::{collections::VecDeque, vec, vec::Vec};
+
+use super::*;
+
+/// An iterator adapter that classifies each address of the underlying iterator using longest
+/// prefix matching. See [`PrefixMap::lpm_all_addresses_of`].
+pub struct LpmAddresses<'a, P, T, I> {
+    map: &'a PrefixMap<P, T>,
+    iter: I,
+    batch_size: usize,
+    batch: VecDeque<(P, Option<&'a T>)>,
+}
+
+impl<'a, P, T, I> LpmAddresses<'a, P, T, I>
+where
+    P: Prefix,
+    I: Iterator<Item = P>,
+{
+    /// Process the addresses in batches of `batch_size`. Within each batch, the lookups are
+    /// performed in sorted order, such that lookups of nearby addresses visit the same nodes of the
+    /// tree one after the other. This improves cache locality when classifying large streams of
+    /// unordered addresses. The addresses are still yielded in their original order. A
+    /// `batch_size` of `0` or `1` disables batching.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("192.168.0.0/16".parse()?, 2);
+    /// let addrs: Vec<Ipv4Net> = vec![
+    ///     "192.168.1.1/32".parse()?,
+    ///     "10.1.1.1/32".parse()?,
+    ///     "172.16.1.1/32".parse()?,
+    /// ];
+    /// assert_eq!(
+    ///     pm.lpm_all_addresses_of(addrs.clone()).sorted_batches(2).collect::<Vec<_>>(),
+    ///     vec![(addrs[0], Some(&2)), (addrs[1], Some(&1)), (addrs[2], None)],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sorted_batches(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Read the next batch from the underlying iterator, and classify it in sorted order.
+    fn fill_batch(&mut self) {
+        let addrs: Vec<P> = self.iter.by_ref().take(self.batch_size).collect();
+        let mut order: Vec<usize> = (0..addrs.len()).collect();
+        order.sort_unstable_by_key(|i| (addrs[*i].mask(), addrs[*i].prefix_len()));
+        let mut values = vec![None; addrs.len()];
+        for i in order {
+            values[i] = self.map.get_lpm(&addrs[i]).map(|(_, t)| t);
+        }
+        self.batch.extend(addrs.into_iter().zip(values));
+    }
+}
+
+impl<'a, P, T, I> Iterator for LpmAddresses<'a, P, T, I>
+where
+    P: Prefix,
+    I: Iterator<Item = P>,
+{
+    type Item = (P, Option<&'a T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch_size <= 1 {
+            let addr = self.iter.next()?;
+            let value = self.map.get_lpm(&addr).map(|(_, t)| t);
+            return Some((addr, value));
+        }
+        if self.batch.is_empty() {
+            self.fill_batch();
+        }
+        self.batch.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        let buffered = self.batch.len();
+        (
+            lower.saturating_add(buffered),
+            upper.and_then(|u| u.checked_add(buffered)),
+        )
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Classify a stream of addresses (or prefixes) using longest prefix matching. The returned
+    /// iterator yields each address together with the value of its longest matching prefix (or
+    /// `None` if no prefix in the map contains it). The adapter is lazy, and addresses are
+    /// consumed from `addrs` as the result is iterated. Use [`LpmAddresses::sorted_batches`] to
+    /// perform the lookups in sorted batches.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, "internal");
+    /// pm.insert("0.0.0.0/0".parse()?, "external");
+    /// let flows = ["10.1.2.3/32", "8.8.8.8/32"].map(|s| s.parse::<Ipv4Net>().unwrap());
+    /// assert_eq!(
+    ///     pm.lpm_all_addresses_of(flows)
+    ///         .map(|(_, t)| t.copied())
+    ///         .collect::<Vec<_>>(),
+    ///     vec![Some("internal"), Some("external")],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lpm_all_addresses_of<I>(&self, addrs: I) -> LpmAddresses<'_, P, T, I::IntoIter>
+    where
+        I: IntoIterator<Item = P>,
+    {
+        LpmAddresses {
+            map: self,
+            iter: addrs.into_iter(),
+            batch_size: 0,
+            batch: VecDeque::new(),
+        }
+    }
+
+    /// Perform longest prefix matching for all `queries` at once, returning the result for each
+    /// query in the same order as `queries`. The queries are processed in sorted order, and each
+    /// lookup continues from the deepest node on the path of the previous lookup that still
+    /// contains the query. Queries that are close to each other therefore share most of the
+    /// traversal.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// let queries: Vec<Ipv4Net> = vec![
+    ///     "10.1.1.1/32".parse()?,
+    ///     "192.168.1.1/32".parse()?,
+    ///     "10.2.1.1/32".parse()?,
+    /// ];
+    /// assert_eq!(
+    ///     pm.get_lpm_batch(&queries),
+    ///     vec![
+    ///         Some((&"10.1.0.0/16".parse()?, &2)),
+    ///         None,
+    ///         Some((&"10.0.0.0/8".parse()?, &1)),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_lpm_batch(&self, queries: &[P]) -> Vec<Option<(&P, &T)>> {
+        let mut order: Vec<usize> = (0..queries.len()).collect();
+        order.sort_unstable_by_key(|i| (queries[*i].mask(), queries[*i].prefix_len()));
+        let mut result = vec![None; queries.len()];
+        // The path of the last lookup, together with the best match up to each node. The root
+        // contains every query, so it is never removed from the path.
+        let mut path = vec![(0, self.table[0].prefix_value())];
+        for i in order {
+            let query = &queries[i];
+            while path.len() > 1 && !self.table[path[path.len() - 1].0].prefix.contains(query) {
+                path.pop();
+            }
+            let (mut idx, mut best_match) = path[path.len() - 1];
+            while let Direction::Enter { next, .. } = self.get_direction(idx, query) {
+                idx = next;
+                best_match = self.table[idx].prefix_value().or(best_match);
+                path.push((idx, best_match));
+            }
+            result[i] = best_match;
+        }
+        result
+    }
+}
@@ -0,0 +1,261 @@
+//! Operations on the address space covered by a [`PrefixMap`].
+
+use num_traits::{CheckedAdd, One, ToPrimitive, Zero};
+
+use crate::prefix::{
+    first_prefix_in_range, last_address, mask_from_prefix_len, prefixes_in_range, CoveredRanges,
+    RangePrefixes, UncoveredRanges,
+};
+
+use super::*;
+
+type KeyIter<'a, P, T> = core::iter::Map<Iter<'a, P, T>, fn((&'a P, &'a T)) -> &'a P>;
+type CoveredKeys<'a, P, T> = CoveredRanges<'a, P, KeyIter<'a, P, T>>;
+
+/// An iterator over the largest prefixes within a prefix that are not covered by any entry of a
+/// [`PrefixMap`], in lexicographic order. See [`PrefixMap::gaps`].
+pub struct Gaps<'a, P: Prefix, T> {
+    ranges: Option<UncoveredRanges<P::R, CoveredKeys<'a, P, T>>>,
+    current: Option<RangePrefixes<P>>,
+}
+
+impl<'a, P: Prefix, T> Iterator for Gaps<'a, P, T> {
+    type Item = P;
+
+    fn next(&mut self) -> Option<P> {
+        loop {
+            if let Some(p) = self.current.as_mut().and_then(|c| c.next()) {
+                return Some(p);
+            }
+            let (start, end) = self.ranges.as_mut()?.next()?;
+            self.current = Some(prefixes_in_range(start, end));
+        }
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Iterate over the largest prefixes inside `within` that are not covered by any entry in the
+    /// map, in lexicographic order. Together, the yielded prefixes cover exactly the unallocated
+    /// address space of `within`, and no two of them could be merged into a larger prefix. If
+    /// `within` is covered by an entry itself, the iterator is empty.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, "office");
+    /// pm.insert("10.0.2.128/25".parse()?, "lab");
+    /// assert_eq!(
+    ///     pm.gaps(&"10.0.0.0/22".parse()?).collect::<Vec<_>>(),
+    ///     vec![
+    ///         "10.0.1.0/24".parse()?,
+    ///         "10.0.2.0/25".parse()?,
+    ///         "10.0.3.0/24".parse()?,
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn gaps(&self, within: &P) -> Gaps<'_, P, T> {
+        if self.get_lpm(within).is_some() {
+            return Gaps {
+                ranges: None,
+                current: None,
+            };
+        }
+        let keys: KeyIter<'_, P, T> = self.children(within).map(|(p, _)| p);
+        let covered = CoveredRanges::new(keys);
+        Gaps {
+            ranges: Some(UncoveredRanges::new(
+                within.mask(),
+                last_address(within),
+                covered,
+            )),
+            current: None,
+        }
+    }
+
+    /// Check if the entries of the map together cover every address of `prefix`. The prefix may
+    /// be covered by an entry that contains it, or by (possibly many) more-specific entries. The
+    /// search stops at the first address that is not covered, so the cost is O(depth) plus the
+    /// number of entries inside `prefix` that precede that address.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/25".parse()?, 1);
+    /// pm.insert("10.0.0.128/26".parse()?, 2);
+    /// assert!(!pm.covers_fully(&"10.0.0.0/24".parse()?));
+    /// pm.insert("10.0.0.192/26".parse()?, 3);
+    /// assert!(pm.covers_fully(&"10.0.0.0/24".parse()?));
+    /// assert!(pm.covers_fully(&"10.0.0.0/28".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn covers_fully(&self, prefix: &P) -> bool {
+        if self.get_lpm(prefix).is_some() {
+            return true;
+        }
+        // first address that is not yet known to be covered
+        let mut next = prefix.mask();
+        for (p, _) in self.children(prefix) {
+            if p.mask() > next {
+                return false;
+            }
+            match last_address(p).checked_add(&P::R::one()) {
+                Some(a) => next = next.max(a),
+                None => return true,
+            }
+        }
+        next > last_address(prefix)
+    }
+
+    /// Count the number of individual addresses covered by the entries of the map, counting
+    /// addresses covered by multiple (overlapping) entries only once. The result saturates at
+    /// `u128::MAX`, which is only reached when covering the entire IPv6 address space.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, 1);
+    /// pm.insert("10.0.0.0/25".parse()?, 2);
+    /// pm.insert("10.0.1.0/30".parse()?, 3);
+    /// assert_eq!(pm.coverage_size(), 260);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn coverage_size(&self) -> u128 {
+        let keys: KeyIter<'_, P, T> = self.iter().map(|(p, _)| p);
+        CoveredRanges::new(keys)
+            .map(|(start, end)| (end - start).to_u128().unwrap().saturating_add(1))
+            .fold(0, u128::saturating_add)
+    }
+
+    /// Get the smallest prefix that covers all entries of the map. The result need not be an entry
+    /// of the map itself. This function returns `None` if the map is empty or if the entries span
+    /// both halves of the address space, i.e., if only the zero-length prefix covers all of them
+    /// (including when it is an entry of the map).
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// assert_eq!(pm.spanning_prefix(), None);
+    /// pm.insert("10.1.0.0/16".parse()?, 1);
+    /// assert_eq!(pm.spanning_prefix(), Some("10.1.0.0/16".parse()?));
+    /// pm.insert("10.2.0.0/24".parse()?, 2);
+    /// pm.insert("10.3.0.0/24".parse()?, 3);
+    /// assert_eq!(pm.spanning_prefix(), Some("10.0.0.0/14".parse()?));
+    /// pm.insert("192.168.0.0/16".parse()?, 4);
+    /// assert_eq!(pm.spanning_prefix(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spanning_prefix(&self) -> Option<P> {
+        // all entries are sorted lexicographically, so they are all covered by the longest common
+        // prefix of the first and the last one.
+        let (first, _) = self.first()?;
+        let (last, _) = self.last()?;
+        let prefix = first.longest_common_prefix(last);
+        (prefix.prefix_len() > 0).then_some(prefix)
+    }
+
+    /// Find the first prefix of length `len` that starts at or after `start` and that does not
+    /// overlap with any entry in the map. This can be used to allocate consecutive blocks from an
+    /// addressing plan. The search does not wrap around at the end of the address space; use
+    /// [`PrefixMap::find_first_gap_wrapping`] to continue the search at the beginning.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, "a");
+    /// pm.insert("10.0.1.0/26".parse()?, "b");
+    /// pm.insert("10.0.1.128/26".parse()?, "c");
+    /// let start = "10.0.0.0/24".parse()?;
+    /// assert_eq!(pm.find_first_gap_after(&start, 26), Some("10.0.1.64/26".parse()?));
+    /// assert_eq!(pm.find_first_gap_after(&start, 25), Some("10.0.2.0/25".parse()?));
+    /// assert_eq!(pm.find_first_gap_after(&"255.0.0.0/8".parse()?, 4), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_first_gap_after(&self, start: &P, len: u8) -> Option<P> {
+        let block_mask: P::R = mask_from_prefix_len(len);
+        let mut addr = start.mask() & block_mask;
+        if addr < start.mask() {
+            addr = last_address(&P::from_repr_len(addr, len)).checked_add(&P::R::one())?;
+        }
+        self.first_gap_between(addr, start.mask(), len, false)
+    }
+
+    /// Find the first prefix of length `len` that starts at or after `start` and that does not
+    /// overlap with any entry in the map. If no such prefix exists until the end of the address
+    /// space, the search continues at the beginning, up to `start`. See
+    /// [`PrefixMap::find_first_gap_after`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("128.0.0.0/1".parse()?, "a");
+    /// pm.insert("0.0.0.0/8".parse()?, "b");
+    /// let start = "200.0.0.0/8".parse()?;
+    /// assert_eq!(pm.find_first_gap_after(&start, 8), None);
+    /// assert_eq!(pm.find_first_gap_wrapping(&start, 8), Some("1.0.0.0/8".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_first_gap_wrapping(&self, start: &P, len: u8) -> Option<P> {
+        self.find_first_gap_after(start, len)
+            .or_else(|| self.first_gap_between(P::R::zero(), start.mask(), len, true))
+    }
+
+    /// Find the first block of length `len` starting at or after the aligned address `addr` that
+    /// does not overlap with any entry. If `bounded`, only blocks that start before `until` are
+    /// considered.
+    ///
+    /// After skipping the shortest entry covering the first block, no entry that starts before
+    /// `addr` can overlap the blocks that remain. Therefore, a single pass over the entries starting
+    /// at or after `addr` finds the first gap, and each entry is visited at most once.
+    fn first_gap_between(&self, mut addr: P::R, until: P::R, len: u8, bounded: bool) -> Option<P> {
+        let one = P::R::one();
+        let block_mask: P::R = mask_from_prefix_len(len);
+        // first address of the first block that starts at or after `a`.
+        let align_up = |a: P::R| -> Option<P::R> {
+            if a & block_mask == a {
+                Some(a)
+            } else {
+                last_address(&P::from_repr_len(a, len)).checked_add(&one)
+            }
+        };
+        if let Some(covering) = self.get_spm_prefix(&P::from_repr_len(addr, len)) {
+            addr = last_address(covering).checked_add(&one)?;
+        }
+        let from = first_prefix_in_range::<P>(addr, !P::R::zero());
+        for (p, _) in self.iter_from(&from) {
+            if bounded && addr >= until {
+                return None;
+            }
+            let (start, end) = (p.mask(), last_address(p));
+            if end < addr {
+                continue;
+            }
+            if start > addr && last_address(&P::from_repr_len(addr, len)) < start {
+                return Some(P::from_repr_len(addr, len));
+            }
+            addr = align_up(end.checked_add(&one)?)?;
+        }
+        (!bounded || addr < until).then(|| P::from_repr_len(addr, len))
+    }
+}
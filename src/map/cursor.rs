@@ -0,0 +1,412 @@
+//! Cursor to traverse and modify the map in lexicographic order.
+
+use super::*;
+
+/// A cursor over a [`PrefixMap`] that can move to the next or previous entry in lexicographic
+/// order, and modify the map while keeping its position.
+///
+/// The cursor either points to an entry of the map, or to a "ghost" position that sits between
+/// the last and the first entry. Moving the cursor from the ghost position to the next element
+/// yields the first entry, while moving it backwards yields the last entry.
+///
+/// Moving the cursor is amortized `O(1)`, as the cursor keeps track of the path from the root to
+/// the current node. Inserting or removing elements through the cursor is `O(log n)`, just like
+/// the same operations on the map itself.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+/// pm.insert("192.168.0.0/22".parse()?, 1);
+/// pm.insert("192.168.0.0/23".parse()?, 2);
+/// pm.insert("192.168.2.0/23".parse()?, 3);
+/// pm.insert("192.168.0.0/24".parse()?, 4);
+/// pm.insert("192.168.2.0/24".parse()?, 5);
+///
+/// let mut cursor = pm.cursor_mut(&"192.168.0.0/23".parse()?);
+/// assert_eq!(cursor.key(), Some(&"192.168.0.0/23".parse()?));
+/// cursor.move_next();
+/// assert_eq!(cursor.key(), Some(&"192.168.0.0/24".parse()?));
+/// // remove the current element and move to the next one.
+/// assert_eq!(cursor.remove_current(), Some(4));
+/// assert_eq!(cursor.key(), Some(&"192.168.2.0/23".parse()?));
+/// // insert an element that is not at the current position
+/// cursor.insert("192.168.1.0/24".parse()?, 6);
+/// assert_eq!(cursor.key(), Some(&"192.168.2.0/23".parse()?));
+/// cursor.move_prev();
+/// assert_eq!(cursor.key(), Some(&"192.168.1.0/24".parse()?));
+///
+/// assert_eq!(
+///     pm.into_iter().collect::<Vec<_>>(),
+///     vec![
+///         ("192.168.0.0/22".parse()?, 1),
+///         ("192.168.0.0/23".parse()?, 2),
+///         ("192.168.1.0/24".parse()?, 6),
+///         ("192.168.2.0/23".parse()?, 3),
+///         ("192.168.2.0/24".parse()?, 5),
+///     ]
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub struct CursorMut<'a, P, T> {
+    map: &'a mut PrefixMap<P, T>,
+    /// Path from the root to the current node. The path is empty if the cursor points to the ghost
+    /// position.
+    path: Vec<usize>,
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Get a [`CursorMut`] pointing to the first entry that is larger or equal to `prefix`, in
+    /// lexicographic order. If no such entry exists, the cursor points to the ghost position.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// pm.insert("192.168.2.0/24".parse()?, 2);
+    /// assert_eq!(pm.cursor_mut(&"192.168.0.0/23".parse()?).key(), Some(&"192.168.0.0/23".parse()?));
+    /// assert_eq!(pm.cursor_mut(&"192.168.0.0/24".parse()?).key(), Some(&"192.168.2.0/24".parse()?));
+    /// assert_eq!(pm.cursor_mut(&"192.168.3.0/24".parse()?).key(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cursor_mut(&mut self, prefix: &P) -> CursorMut<'_, P, T> {
+        let mut cursor = CursorMut {
+            map: self,
+            path: Vec::new(),
+        };
+        cursor.seek(prefix);
+        cursor
+    }
+
+    /// Compute the path from the root to the node `idx`.
+    pub(super) fn path_to(&self, idx: usize) -> Vec<usize> {
+        let prefix = &self.table[idx].prefix;
+        let mut cur = 0;
+        let mut path = vec![0];
+        while let Direction::Enter { next, .. } = self.get_direction(cur, prefix) {
+            path.push(next);
+            cur = next;
+        }
+        path
+    }
+}
+
+impl<'a, P, T> CursorMut<'a, P, T>
+where
+    P: Prefix,
+{
+    /// Get the key of the current entry, or `None` if the cursor points to the ghost position.
+    pub fn key(&self) -> Option<&P> {
+        self.current_node().map(|n| &n.prefix)
+    }
+
+    /// Get the value of the current entry, or `None` if the cursor points to the ghost position.
+    pub fn value(&self) -> Option<&T> {
+        self.current_node().and_then(|n| n.value.as_ref())
+    }
+
+    /// Get a mutable reference to the value of the current entry, or `None` if the cursor points
+    /// to the ghost position.
+    pub fn value_mut(&mut self) -> Option<&mut T> {
+        let idx = *self.path.last()?;
+        self.map.table[idx].value.as_mut()
+    }
+
+    /// Get the key and a mutable reference to the value of the current entry, or `None` if the
+    /// cursor points to the ghost position.
+    pub fn current(&mut self) -> Option<(&P, &mut T)> {
+        let idx = *self.path.last()?;
+        self.map.table[idx].prefix_value_mut()
+    }
+
+    /// Move the cursor to the next entry in lexicographic order. If the cursor points to the last
+    /// entry, it will move to the ghost position. If it points to the ghost position, it will move
+    /// to the first entry.
+    pub fn move_next(&mut self) {
+        if self.path.is_empty() {
+            self.path.push(0);
+            if self.map.table[0].value.is_some() {
+                return;
+            }
+        }
+        self.advance();
+    }
+
+    /// Move the cursor to the previous entry in lexicographic order. If the cursor points to the
+    /// first entry, it will move to the ghost position. If it points to the ghost position, it
+    /// will move to the last entry.
+    pub fn move_prev(&mut self) {
+        if self.path.is_empty() {
+            self.path.push(0);
+            self.descend_last();
+            if self.at_entry() {
+                return;
+            }
+        }
+        while self.prev_node() {
+            if self.at_entry() {
+                return;
+            }
+        }
+    }
+
+    /// Insert a new element into the map, returning the value that was stored before. The cursor
+    /// keeps pointing to the same entry (or to the ghost position).
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let cur = self.path.last().copied();
+        let old = self.map.insert(prefix, value);
+        if let Some(cur) = cur {
+            self.path = self.map.path_to(cur);
+        }
+        old
+    }
+
+    /// Remove an element from the map, returning its value. The element may be any element in the
+    /// map. If it is the current entry, the cursor moves to the next entry. Otherwise, the cursor
+    /// keeps pointing to the same entry (or to the ghost position).
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        if self.key().map(|k| k.eq(prefix)) == Some(true) {
+            return self.remove_current();
+        }
+        let cur = self.path.last().copied();
+        let old = self.map.remove(prefix);
+        if let Some(cur) = cur {
+            self.path = self.map.path_to(cur);
+        }
+        old
+    }
+
+    /// Remove the current entry from the map, move the cursor to the next entry, and return the
+    /// removed value. If the cursor points to the ghost position, nothing is removed and `None`
+    /// is returned.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let idx = *self.path.last()?;
+
+        // first, find the next node, such that we can continue from there.
+        let mut next = CursorMut {
+            map: &mut *self.map,
+            path: self.path.clone(),
+        };
+        next.advance();
+        let next = next.path.last().copied();
+
+        let len = self.path.len();
+        let par = len.checked_sub(2).map(|i| self.path[i]);
+        let grp = len.checked_sub(3).map(|i| self.path[i]);
+        let par_right = par.map(|p| self.map.table[p].right() == Some(idx)) == Some(true);
+        let grp_right = match (grp, par) {
+            (Some(g), Some(p)) => self.map.table[g].right() == Some(p),
+            _ => false,
+        };
+        let (value, _) = self.map._remove_node(idx, par, par_right, grp, grp_right);
+
+        // the next node has a value, so it cannot have been removed from the tree.
+        self.path = next.map(|n| self.map.path_to(n)).unwrap_or_default();
+        value
+    }
+
+    /// Get the next entry in lexicographic order without moving the cursor. If the cursor points
+    /// to the ghost position, this returns the first entry. If it points to the last entry, this
+    /// returns `None`.
+    pub fn peek_next(&mut self) -> Option<(&P, &mut T)> {
+        let path = self.path.clone();
+        self.move_next();
+        let next = core::mem::replace(&mut self.path, path).last().copied();
+        self.map.table[next?].prefix_value_mut()
+    }
+
+    /// Get the previous entry in lexicographic order without moving the cursor. If the cursor
+    /// points to the ghost position, this returns the last entry. If it points to the first entry,
+    /// this returns `None`.
+    pub fn peek_prev(&mut self) -> Option<(&P, &mut T)> {
+        let path = self.path.clone();
+        self.move_prev();
+        let prev = core::mem::replace(&mut self.path, path).last().copied();
+        self.map.table[prev?].prefix_value_mut()
+    }
+
+    /// Move the cursor to the longest entry that contains the current entry (excluding the entry
+    /// itself). If no such entry exists, or if the cursor points to the ghost position, the cursor
+    /// moves to the ghost position.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/24".parse()?, 2);
+    /// pm.insert("192.168.0.0/25".parse()?, 3);
+    /// let mut cursor = pm.cursor_mut(&"192.168.0.0/25".parse()?);
+    /// cursor.move_to_parent();
+    /// assert_eq!(cursor.key(), Some(&"192.168.0.0/24".parse()?));
+    /// cursor.move_to_parent();
+    /// assert_eq!(cursor.key(), Some(&"192.168.0.0/22".parse()?));
+    /// cursor.move_to_parent();
+    /// assert_eq!(cursor.key(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn move_to_parent(&mut self) {
+        self.path.pop();
+        while !self.path.is_empty() && !self.at_entry() {
+            self.path.pop();
+        }
+    }
+
+    /// Remove all entries that are contained within the current entry (excluding the entry
+    /// itself). The cursor keeps pointing to the current entry. If the cursor points to the ghost
+    /// position, nothing is removed.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/24".parse()?, 2);
+    /// pm.insert("192.168.1.0/24".parse()?, 3);
+    /// pm.insert("192.168.4.0/24".parse()?, 4);
+    /// let mut cursor = pm.cursor_mut(&"192.168.0.0/22".parse()?);
+    /// cursor.remove_children();
+    /// assert_eq!(cursor.key(), Some(&"192.168.0.0/22".parse()?));
+    /// cursor.move_next();
+    /// assert_eq!(cursor.key(), Some(&"192.168.4.0/24".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove_children(&mut self) {
+        let Some(&idx) = self.path.last() else {
+            return;
+        };
+        for right in [false, true] {
+            if self.map.get_child(idx, right).is_some() {
+                self.map._do_remove_children(idx, right);
+            }
+        }
+    }
+
+    /// Move the cursor to the first entry that is larger or equal to `prefix`, in lexicographic
+    /// order. If no such entry exists, the cursor points to the ghost position.
+    pub fn seek(&mut self, prefix: &P) {
+        self.path = vec![0];
+        let mut idx = 0;
+        // whether the current position is larger or equal to the prefix
+        let at_or_after = loop {
+            match self.map.get_direction(idx, prefix) {
+                Direction::Reached => break true,
+                Direction::Enter { next, .. } => {
+                    self.path.push(next);
+                    idx = next;
+                }
+                Direction::Missing => {
+                    let right = to_right(&self.map.table[idx].prefix, prefix);
+                    match self.map.get_child(idx, right) {
+                        Some(child) => {
+                            self.path.push(child);
+                            let child_p = &self.map.table[child].prefix;
+                            if prefix.contains(child_p) || child_p.mask() > prefix.mask() {
+                                break true;
+                            }
+                            // the entire subtree of `child` is smaller than `prefix`.
+                            self.descend_last();
+                            break false;
+                        }
+                        None if right => {
+                            // the entire subtree of `idx` is smaller than `prefix`.
+                            self.descend_last();
+                            break false;
+                        }
+                        // all nodes after `idx` are larger than `prefix`.
+                        None => break false,
+                    }
+                }
+            }
+        };
+        if !(at_or_after && self.at_entry()) {
+            self.advance();
+        }
+    }
+
+    /// Advance to the next node with a value (or to the ghost position).
+    fn advance(&mut self) {
+        while self.next_node() {
+            if self.at_entry() {
+                return;
+            }
+        }
+    }
+
+    /// Move to the next node in pre-order. Returns `false` if the end is reached (and the path is
+    /// empty).
+    fn next_node(&mut self) -> bool {
+        let Some(&cur) = self.path.last() else {
+            return false;
+        };
+        let node = &self.map.table[cur];
+        if let Some(next) = node.left().or(node.right()) {
+            self.path.push(next);
+            return true;
+        }
+        let mut cur = self.path.pop().unwrap();
+        while let Some(&par) = self.path.last() {
+            let par_node = &self.map.table[par];
+            if par_node.left() == Some(cur) {
+                if let Some(right) = par_node.right() {
+                    self.path.push(right);
+                    return true;
+                }
+            }
+            cur = self.path.pop().unwrap();
+        }
+        false
+    }
+
+    /// Move to the previous node in pre-order. Returns `false` if the beginning is reached (and
+    /// the path is empty).
+    fn prev_node(&mut self) -> bool {
+        let Some(cur) = self.path.pop() else {
+            return false;
+        };
+        let Some(&par) = self.path.last() else {
+            return false;
+        };
+        let par_node = &self.map.table[par];
+        if par_node.right() == Some(cur) {
+            if let Some(left) = par_node.left() {
+                self.path.push(left);
+                self.descend_last();
+            }
+        }
+        true
+    }
+
+    /// Descend from the current node to the last node of its subtree in pre-order.
+    fn descend_last(&mut self) {
+        while let Some(&cur) = self.path.last() {
+            let node = &self.map.table[cur];
+            match node.right().or(node.left()) {
+                Some(next) => self.path.push(next),
+                None => break,
+            }
+        }
+    }
+
+    /// Check if the cursor currently points to a node with a value.
+    fn at_entry(&self) -> bool {
+        self.current_node().map(|n| n.value.is_some()) == Some(true)
+    }
+
+    /// Get the current node, if the cursor does not point to the ghost position.
+    fn current_node(&self) -> Option<&Node<P, T>> {
+        self.path.last().map(|idx| &self.map.table[*idx])
+    }
+}
@@ -0,0 +1,129 @@
+//! Difference between two maps.
+
+use alloc::vec::Vec;
+
+use super::*;
+
+/// A single difference between two maps, as yielded by [`PrefixMap::diff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffItem<'a, P, T> {
+    /// The prefix is only present in the new map.
+    Added(&'a P, &'a T),
+    /// The prefix is only present in the old map.
+    Removed(&'a P, &'a T),
+    /// The prefix is present in both maps, but with different values. The first value is the one
+    /// of the old map, and the second one is the one of the new map.
+    Changed(&'a P, &'a T, &'a T),
+}
+
+/// An iterator over all differences between two maps in lexicographic order. See
+/// [`PrefixMap::diff`].
+pub struct Diff<'a, P, T> {
+    joint: JointIter<'a, P, T, T>,
+}
+
+impl<'a, P: Prefix, T: PartialEq> Iterator for Diff<'a, P, T> {
+    type Item = DiffItem<'a, P, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.joint
+            .by_ref()
+            .find_map(|(p, old, new)| diff_item(p, old, new))
+    }
+}
+
+/// Compare the values of `p` in the old and the new map.
+fn diff_item<'a, P, T: PartialEq>(
+    p: &'a P,
+    old: Option<&'a T>,
+    new: Option<&'a T>,
+) -> Option<DiffItem<'a, P, T>> {
+    match (old, new) {
+        (Some(old), Some(new)) if old == new => None,
+        (Some(old), Some(new)) => Some(DiffItem::Changed(p, old, new)),
+        (Some(old), None) => Some(DiffItem::Removed(p, old)),
+        (None, Some(new)) => Some(DiffItem::Added(p, new)),
+        (None, None) => unreachable!("Each prefix is present in at least one map"),
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+    T: PartialEq,
+{
+    /// Compute the difference from `self` (the old map) to `other` (the new map) in a single pass
+    /// over both maps. The iterator yields all prefixes that were added, removed, or whose value
+    /// has changed, in lexicographic order. Prefixes with equal values in both maps are skipped.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::DiffItem;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut old: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// old.insert("10.0.0.0/8".parse()?, 1);
+    /// old.insert("172.16.0.0/12".parse()?, 2);
+    /// old.insert("192.168.0.0/16".parse()?, 3);
+    /// let mut new = old.clone();
+    /// new.insert("10.0.0.0/8".parse()?, 4);
+    /// new.remove(&"172.16.0.0/12".parse()?);
+    /// new.insert("192.168.1.0/24".parse()?, 5);
+    /// assert_eq!(
+    ///     old.diff(&new).collect::<Vec<_>>(),
+    ///     vec![
+    ///         DiffItem::Changed(&"10.0.0.0/8".parse()?, &1, &4),
+    ///         DiffItem::Removed(&"172.16.0.0/12".parse()?, &2),
+    ///         DiffItem::Added(&"192.168.1.0/24".parse()?, &5),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn diff<'a>(&'a self, other: &'a PrefixMap<P, T>) -> Diff<'a, P, T> {
+        Diff {
+            joint: self.joint_iter(other),
+        }
+    }
+
+    /// Collect the difference from `self` to `other` like [`PrefixMap::diff`], but stop early if
+    /// the operation gets cancelled. The function `cancelled` is called once for each prefix that
+    /// is present in any of the two maps, including those with equal values. As soon as it returns
+    /// `true`, the comparison is aborted and `None` is returned. In contrast, a single call to
+    /// `next` on [`Diff`] may skip over arbitrarily many equal entries.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::DiffItem;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut old: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// old.insert("10.0.0.0/8".parse()?, 1);
+    /// let mut new = old.clone();
+    /// new.insert("192.168.0.0/16".parse()?, 2);
+    /// assert_eq!(
+    ///     old.try_diff(&new, || false),
+    ///     Some(vec![DiffItem::Added(&"192.168.0.0/16".parse()?, &2)])
+    /// );
+    /// assert_eq!(old.try_diff(&new, || true), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_diff<'a, C>(
+        &'a self,
+        other: &'a PrefixMap<P, T>,
+        mut cancelled: C,
+    ) -> Option<Vec<DiffItem<'a, P, T>>>
+    where
+        C: FnMut() -> bool,
+    {
+        let mut items = Vec::new();
+        for (p, old, new) in self.joint_iter(other) {
+            if cancelled() {
+                return None;
+            }
+            items.extend(diff_item(p, old, new));
+        }
+        Some(items)
+    }
+}
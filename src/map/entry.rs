@@ -25,6 +25,39 @@ pub struct OccupiedEntry<'a, P, T> {
     pub(super) node: &'a mut Node<P, T>,
 }
 
+/// The error returned by [`PrefixMap::try_insert`] if the key is already present. It contains the
+/// occupied entry and the value that was not inserted.
+pub struct OccupiedError<'a, P, T> {
+    /// The entry in the map that was already occupied.
+    pub entry: OccupiedEntry<'a, P, T>,
+    /// The value that was not inserted.
+    pub value: T,
+}
+
+impl<P: core::fmt::Debug, T: core::fmt::Debug> core::fmt::Debug for OccupiedError<'_, P, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OccupiedError")
+            .field("key", self.entry.key())
+            .field("old_value", self.entry.get())
+            .field("new_value", &self.value)
+            .finish()
+    }
+}
+
+impl<P: core::fmt::Debug, T: core::fmt::Debug> core::fmt::Display for OccupiedError<'_, P, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "failed to insert {:?}, key {:?} already exists with value {:?}",
+            self.value,
+            self.entry.key(),
+            self.entry.get(),
+        )
+    }
+}
+
+impl<P: core::fmt::Debug, T: core::fmt::Debug> core::error::Error for OccupiedError<'_, P, T> {}
+
 impl<'a, P, T> Entry<'a, P, T> {
     /// Get the value if it exists
     ///
@@ -121,6 +154,36 @@ where
         }
     }
 
+    /// Set the value of the entry and return an [`OccupiedEntry`] pointing to it. If the entry
+    /// was already occupied, the old value is overwritten.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    ///
+    /// let mut e = pm.entry("192.168.2.0/24".parse()?).insert_entry(20);
+    /// assert_eq!(e.key(), &"192.168.2.0/24".parse()?);
+    /// assert_eq!(e.remove(), 20);
+    ///
+    /// let e = pm.entry("192.168.1.0/24".parse()?).insert_entry(10);
+    /// assert_eq!(e.get(), &10);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn insert_entry(self, v: T) -> OccupiedEntry<'a, P, T> {
+        match self {
+            Entry::Vacant(e) => e.insert_entry(v),
+            Entry::Occupied(mut e) => {
+                e.insert(v);
+                e
+            }
+        }
+    }
+
     /// Ensures a value is in the entry by inserting the default if empty, and returns a mutable
     /// reference to the value in the entry.
     ///
@@ -424,6 +487,33 @@ where
         node.value.as_mut().unwrap()
     }
 
+    /// Insert the value into the vacant entry and return an [`OccupiedEntry`] pointing to the
+    /// newly created node.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// use prefix_trie::map::Entry;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, i32> = PrefixMap::new();
+    /// match pm.entry("192.168.1.0/24".parse()?) {
+    ///     Entry::Vacant(e) => {
+    ///         let mut e = e.insert_entry(10);
+    ///         *e.get_mut() += 1;
+    ///         assert_eq!(e.get(), &11);
+    ///     }
+    ///     Entry::Occupied(_) => unreachable!(),
+    /// }
+    /// assert_eq!(pm.get(&"192.168.1.0/24".parse()?), Some(&11));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_entry(self, v: T) -> OccupiedEntry<'a, P, T> {
+        OccupiedEntry {
+            node: self._insert(v),
+        }
+    }
+
     /// Get a mutable reference to the value. If the value is yet empty, set it to the return value
     /// from the given function.
     ///
@@ -0,0 +1,349 @@
+//! An immutable, compact snapshot of a prefix map.
+
+use alloc::{vec, vec::Vec};
+
+use super::*;
+
+/// The index that marks a missing child or value.
+const NONE: u32 = u32::MAX;
+
+/// The links of a node in a [`FrozenPrefixMap`].
+#[derive(Clone, Copy)]
+struct FrozenNode {
+    left: u32,
+    right: u32,
+    value: u32,
+}
+
+/// An immutable snapshot of a [`PrefixMap`] that only supports lookups and iteration. See
+/// [`PrefixMap::freeze`].
+///
+/// The nodes are stored in pre-order, such that lookups mostly move forward in memory. The
+/// prefixes, the links between nodes, and the values are kept in separate vectors, such that a
+/// lookup only touches the values once it found the result. Branch nodes without a value that have
+/// only a single child are removed when freezing the map. Since values are stored in
+/// lexicographic order of their prefixes, iterating over the map is a linear scan.
+#[derive(Clone)]
+pub struct FrozenPrefixMap<P, T> {
+    prefixes: Vec<P>,
+    nodes: Vec<FrozenNode>,
+    values: Vec<T>,
+    /// The node of each value.
+    keys: Vec<u32>,
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Compile the map into an immutable [`FrozenPrefixMap`], which is faster to query and to
+    /// iterate over. This is useful if a map is built once and then queried many times.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map has more than `u32::MAX - 1` nodes.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// let frozen = pm.freeze();
+    /// assert_eq!(frozen.get(&"192.168.1.0/24".parse()?), Some(&1));
+    /// assert_eq!(
+    ///     frozen.get_lpm(&"192.168.1.1/32".parse()?),
+    ///     Some((&"192.168.1.0/24".parse()?, &1))
+    /// );
+    /// assert_eq!(frozen.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn freeze(self) -> FrozenPrefixMap<P, T> {
+        assert!(
+            self.table.len() < NONE as usize,
+            "too many nodes to freeze the map"
+        );
+        let table = self.table.into_iter().map(Some).collect();
+        // never cancelled
+        FrozenPrefixMap::from_table(table, &mut || false).unwrap()
+    }
+
+    /// Compile a copy of the map into a [`FrozenPrefixMap`] like [`PrefixMap::freeze`], but stop
+    /// early if the operation gets cancelled. The function `cancelled` is called regularly (about
+    /// twice per node of the map). As soon as it returns `true`, freezing is aborted and `None` is
+    /// returned. In contrast to [`PrefixMap::freeze`], the map is kept, and its prefixes and
+    /// values are cloned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map has more than `u32::MAX - 1` nodes.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// let frozen = pm.try_freeze(|| false).unwrap();
+    /// assert_eq!(frozen.get(&"192.168.1.0/24".parse()?), Some(&1));
+    /// assert!(pm.try_freeze(|| true).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_freeze<C>(&self, mut cancelled: C) -> Option<FrozenPrefixMap<P, T>>
+    where
+        P: Clone,
+        T: Clone,
+        C: FnMut() -> bool,
+    {
+        assert!(
+            self.table.len() < NONE as usize,
+            "too many nodes to freeze the map"
+        );
+        let table = self
+            .table
+            .iter()
+            .map(|node| (!cancelled()).then(|| Some(node.clone())))
+            .collect::<Option<_>>()?;
+        FrozenPrefixMap::from_table(table, &mut cancelled)
+    }
+}
+
+/// Mark all nodes whose subtree contains at least one value, and return whether `idx` is marked.
+fn mark_values<P, T>(table: &[Option<Node<P, T>>], idx: usize, has_values: &mut [bool]) -> bool {
+    let node = table[idx].as_ref().unwrap();
+    let mut has_value = node.value.is_some();
+    for child in [node.left(), node.right()].into_iter().flatten() {
+        has_value |= mark_values(table, child, has_values);
+    }
+    has_values[idx] = has_value;
+    has_value
+}
+
+impl<P, T> FrozenPrefixMap<P, T> {
+    /// Build the frozen map from the nodes of a [`PrefixMap`], or return `None` if `cancelled`
+    /// returns `true`.
+    fn from_table<C>(mut table: Vec<Option<Node<P, T>>>, cancelled: &mut C) -> Option<Self>
+    where
+        C: FnMut() -> bool,
+    {
+        let mut frozen = FrozenPrefixMap {
+            prefixes: Vec::new(),
+            nodes: Vec::new(),
+            values: Vec::new(),
+            keys: Vec::new(),
+        };
+        let mut has_values = vec![false; table.len()];
+        mark_values(&table, 0, &mut has_values);
+        frozen.build(&mut table, &has_values, 0, true, cancelled)?;
+        Some(frozen)
+    }
+
+    /// Move the subtree of `idx` into the frozen map in pre-order, and return the index of its
+    /// root, or `NONE` if the subtree has no values. The root is always kept. Returns `None` if
+    /// `cancelled` returns `true`.
+    fn build<C>(
+        &mut self,
+        table: &mut [Option<Node<P, T>>],
+        has_values: &[bool],
+        idx: usize,
+        is_root: bool,
+        cancelled: &mut C,
+    ) -> Option<u32>
+    where
+        C: FnMut() -> bool,
+    {
+        if cancelled() {
+            return None;
+        }
+        let node = table[idx].take().unwrap();
+        let left = node.left().filter(|c| has_values[*c]);
+        let right = node.right().filter(|c| has_values[*c]);
+        if node.value.is_none() && !is_root {
+            // skip branch nodes without a value that do not actually branch.
+            match (left, right) {
+                (None, None) => return Some(NONE),
+                (Some(child), None) | (None, Some(child)) => {
+                    return self.build(table, has_values, child, false, cancelled)
+                }
+                (Some(_), Some(_)) => {}
+            }
+        }
+        let pos = self.nodes.len();
+        let value = match node.value {
+            Some(v) => {
+                self.values.push(v);
+                self.keys.push(pos as u32);
+                (self.values.len() - 1) as u32
+            }
+            None => NONE,
+        };
+        self.prefixes.push(node.prefix);
+        self.nodes.push(FrozenNode {
+            left: NONE,
+            right: NONE,
+            value,
+        });
+        if let Some(left) = left {
+            self.nodes[pos].left = self.build(table, has_values, left, false, cancelled)?;
+        }
+        if let Some(right) = right {
+            self.nodes[pos].right = self.build(table, has_values, right, false, cancelled)?;
+        }
+        Some(pos as u32)
+    }
+
+    /// Get the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Check whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Iterate over all entries in the map in lexicographic order.
+    pub fn iter(&self) -> FrozenIter<'_, P, T> {
+        FrozenIter {
+            map: self,
+            front: 0,
+            back: self.values.len(),
+        }
+    }
+
+    /// Get the value of the node at `idx`.
+    fn prefix_value(&self, idx: usize) -> Option<(&P, &T)> {
+        let value = self.nodes[idx].value;
+        (value != NONE).then(|| (&self.prefixes[idx], &self.values[value as usize]))
+    }
+}
+
+impl<P, T> FrozenPrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Get the directions from some node `idx` to get to `prefix`.
+    #[inline(always)]
+    fn get_direction(&self, cur: usize, prefix: &P) -> Direction {
+        let cur_p = &self.prefixes[cur];
+        if cur_p.eq(prefix) {
+            Direction::Reached
+        } else {
+            let node = self.nodes[cur];
+            let right = to_right(cur_p, prefix);
+            let child = if right { node.right } else { node.left };
+            if child != NONE && self.prefixes[child as usize].contains(prefix) {
+                Direction::Enter {
+                    next: child as usize,
+                    right,
+                }
+            } else {
+                Direction::Missing
+            }
+        }
+    }
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        self.get_key_value(prefix).map(|(_, t)| t)
+    }
+
+    /// Get the key and the value of an element by matching exactly on the prefix.
+    pub fn get_key_value(&self, prefix: &P) -> Option<(&P, &T)> {
+        let mut idx = 0;
+        loop {
+            match self.get_direction(idx, prefix) {
+                Direction::Reached => return self.prefix_value(idx),
+                Direction::Enter { next, .. } => idx = next,
+                Direction::Missing => return None,
+            }
+        }
+    }
+
+    /// Check if a key is present in the map.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get_key_value(prefix).is_some()
+    }
+
+    /// Get the value of the longest prefix in the map that contains `prefix`.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        let mut idx = 0;
+        let mut best_match = NONE as usize;
+        loop {
+            if self.nodes[idx].value != NONE {
+                best_match = idx;
+            }
+            match self.get_direction(idx, prefix) {
+                Direction::Enter { next, .. } => idx = next,
+                _ => break,
+            }
+        }
+        if best_match == NONE as usize {
+            None
+        } else {
+            self.prefix_value(best_match)
+        }
+    }
+}
+
+impl<P: core::fmt::Debug, T: core::fmt::Debug> core::fmt::Debug for FrozenPrefixMap<P, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over all entries of a [`FrozenPrefixMap`] in lexicographic order.
+#[derive(Clone)]
+pub struct FrozenIter<'a, P, T> {
+    map: &'a FrozenPrefixMap<P, T>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a, P, T> Iterator for FrozenIter<'a, P, T> {
+    type Item = (&'a P, &'a T);
+
+    fn next(&mut self) -> Option<(&'a P, &'a T)> {
+        if self.front == self.back {
+            return None;
+        }
+        let i = self.front;
+        self.front += 1;
+        Some((
+            &self.map.prefixes[self.map.keys[i] as usize],
+            &self.map.values[i],
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.back - self.front, Some(self.back - self.front))
+    }
+}
+
+impl<'a, P, T> DoubleEndedIterator for FrozenIter<'a, P, T> {
+    fn next_back(&mut self) -> Option<(&'a P, &'a T)> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        let i = self.back;
+        Some((
+            &self.map.prefixes[self.map.keys[i] as usize],
+            &self.map.values[i],
+        ))
+    }
+}
+
+impl<P, T> ExactSizeIterator for FrozenIter<'_, P, T> {}
+
+impl<'a, P, T> IntoIterator for &'a FrozenPrefixMap<P, T> {
+    type Item = (&'a P, &'a T);
+    type IntoIter = FrozenIter<'a, P, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -0,0 +1,108 @@
+//! Consistency checks of the internal tree structure, meant for testing.
+
+use alloc::{vec, vec::Vec};
+
+use num_traits::{PrimInt, Zero};
+
+use crate::prefix::mask_from_prefix_len;
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix + core::fmt::Debug,
+{
+    /// Check that the internal tree structure of the map is consistent, and panic with a
+    /// description of the first violation otherwise. This checks that:
+    ///
+    /// - the root is stored at index 0 and has prefix length zero,
+    /// - every prefix is in canonical form, i.e., its length does not exceed the number of bits,
+    ///   [`Prefix::mask`] is the representation with all host bits cleared, and the
+    ///   representation has no host bits set (so a map with a key like `10.0.0.1/24` fails this
+    ///   check),
+    /// - every child is strictly contained in its parent and stored on the side given by the first
+    ///   bit after the parent prefix,
+    /// - every slot of the node table is either reachable from the root exactly once, or free to
+    ///   be reused, without a value or children.
+    ///
+    /// This traverses the entire tree and is meant for tests and fuzzing harnesses.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// pm.insert("192.168.1.0/24".parse()?, 2);
+    /// pm.remove(&"192.168.0.0/23".parse()?);
+    /// pm.assert_invariants();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[track_caller]
+    pub fn assert_invariants(&self) {
+        let bits = P::R::zero().count_zeros() as u8;
+        let mut seen = vec![false; self.table.len()];
+        assert!(!self.table.is_empty(), "the node table has no root");
+        let root = &self.table[0].prefix;
+        assert_eq!(
+            root.prefix_len(),
+            0,
+            "the root {root:?} has a non-zero length"
+        );
+
+        let mut stack: Vec<usize> = vec![0];
+        seen[0] = true;
+        while let Some(idx) = stack.pop() {
+            let node = &self.table[idx];
+            let prefix = &node.prefix;
+            let len = prefix.prefix_len();
+            assert!(len <= bits, "{prefix:?} is longer than {bits} bits");
+            assert!(
+                prefix.mask() == prefix.repr() & mask_from_prefix_len(len),
+                "{prefix:?} has an inconsistent mask"
+            );
+            assert!(
+                prefix.repr() == prefix.mask(),
+                "{prefix:?} has host bits set"
+            );
+            for (child, right) in [(node.left(), false), (node.right(), true)] {
+                let Some(child) = child else { continue };
+                assert!(
+                    child < self.table.len(),
+                    "{prefix:?} has a child at index {child} out of bounds"
+                );
+                assert!(
+                    !seen[child],
+                    "the node at index {child} is reachable more than once"
+                );
+                seen[child] = true;
+                let child_p = &self.table[child].prefix;
+                assert!(
+                    child_p.prefix_len() > len && prefix.contains(child_p),
+                    "{child_p:?} is a child of {prefix:?}, but not strictly contained in it"
+                );
+                assert_eq!(
+                    to_right(prefix, child_p),
+                    right,
+                    "{child_p:?} is stored on the wrong side of {prefix:?}"
+                );
+                stack.push(child);
+            }
+        }
+
+        for &idx in &self.free {
+            assert!(idx < self.table.len(), "free slot {idx} is out of bounds");
+            assert!(!seen[idx], "free slot {idx} is reachable or listed twice");
+            seen[idx] = true;
+            let node = &self.table[idx];
+            assert!(
+                node.value.is_none() && node.left().is_none() && node.right().is_none(),
+                "free slot {idx} still stores a value or children"
+            );
+        }
+        if let Some(idx) = seen.iter().position(|x| !x) {
+            panic!("the node at index {idx} is neither reachable nor free");
+        }
+    }
+}
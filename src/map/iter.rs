@@ -1,33 +1,126 @@
 //! Module that contains the implementation for the iterators
 
+use alloc::{vec, vec::Vec};
+use core::{
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+};
+
 use crate::*;
 
 use super::Node;
 
+/// Double-ended traversal over all nodes with a value in a subtree, in lexicographic order.
+///
+/// The front is traversed in pre-order (node, left, right), while the back is traversed in the
+/// mirrored post-order (right, left, node). Both ends remember the last node they yielded, such
+/// that they stop as soon as they meet.
+#[derive(Clone)]
+struct Traversal {
+    front: Vec<usize>,
+    back: Vec<(usize, bool)>,
+    last_front: Option<usize>,
+    last_back: Option<usize>,
+}
+
+impl Traversal {
+    /// Create a traversal over the subtree of `root`.
+    fn new(root: Option<usize>) -> Self {
+        Self {
+            front: root.into_iter().collect(),
+            back: root.into_iter().map(|r| (r, false)).collect(),
+            last_front: None,
+            last_back: None,
+        }
+    }
+
+    /// Create a traversal from pre-computed stacks for the front and the back.
+    fn with_stacks(front: Vec<usize>, back: Vec<(usize, bool)>) -> Self {
+        Self {
+            front,
+            back,
+            last_front: None,
+            last_back: None,
+        }
+    }
+
+    /// Stop the traversal on both ends.
+    fn finish(&mut self) {
+        self.front.clear();
+        self.back.clear();
+    }
+
+    /// Get the index of the next node with a value.
+    fn next<P, T>(&mut self, table: &[Node<P, T>]) -> Option<usize> {
+        while let Some(cur) = self.front.pop() {
+            let node = &table[cur];
+            if let Some(right) = node.right() {
+                self.front.push(right);
+            }
+            if let Some(left) = node.left() {
+                self.front.push(left);
+            }
+            if node.value.is_some() {
+                if self.last_back == Some(cur) {
+                    self.finish();
+                    return None;
+                }
+                self.last_front = Some(cur);
+                return Some(cur);
+            }
+        }
+        None
+    }
+
+    /// Get the index of the next node with a value, starting from the back.
+    fn next_back<P, T>(&mut self, table: &[Node<P, T>]) -> Option<usize> {
+        while let Some((cur, expanded)) = self.back.pop() {
+            let node = &table[cur];
+            if !expanded {
+                self.back.push((cur, true));
+                if let Some(left) = node.left() {
+                    self.back.push((left, false));
+                }
+                if let Some(right) = node.right() {
+                    self.back.push((right, false));
+                }
+            } else if node.value.is_some() {
+                if self.last_front == Some(cur) {
+                    self.finish();
+                    return None;
+                }
+                self.last_back = Some(cur);
+                return Some(cur);
+            }
+        }
+        None
+    }
+}
+
 /// An iterator over all entries of a [`PrefixMap`] in lexicographic order.
 #[derive(Clone)]
 pub struct Iter<'a, P, T> {
     map: &'a PrefixMap<P, T>,
-    nodes: Vec<usize>,
+    nodes: Traversal,
 }
 
 impl<'a, P, T> Iterator for Iter<'a, P, T> {
     type Item = (&'a P, &'a T);
 
     fn next(&mut self) -> Option<(&'a P, &'a T)> {
-        while let Some(cur) = self.nodes.pop() {
-            let node = &self.map.table[cur];
-            if let Some(right) = node.right {
-                self.nodes.push(right);
-            }
-            if let Some(left) = node.left {
-                self.nodes.push(left);
-            }
-            if let Some(v) = &node.value {
-                return Some((&node.prefix, v));
-            }
-        }
-        None
+        let map = self.map;
+        self.nodes
+            .next(&map.table)
+            .and_then(|idx| map.table[idx].prefix_value())
+    }
+}
+
+impl<'a, P, T> DoubleEndedIterator for Iter<'a, P, T> {
+    fn next_back(&mut self) -> Option<(&'a P, &'a T)> {
+        let map = self.map;
+        self.nodes
+            .next_back(&map.table)
+            .and_then(|idx| map.table[idx].prefix_value())
     }
 }
 
@@ -35,26 +128,75 @@ impl<'a, P, T> Iterator for Iter<'a, P, T> {
 #[derive(Clone)]
 pub struct Keys<'a, P, T> {
     map: &'a PrefixMap<P, T>,
-    nodes: Vec<usize>,
+    nodes: Traversal,
 }
 
 impl<'a, P, T> Iterator for Keys<'a, P, T> {
     type Item = &'a P;
 
     fn next(&mut self) -> Option<&'a P> {
-        while let Some(cur) = self.nodes.pop() {
-            let node = &self.map.table[cur];
-            if let Some(right) = node.right {
-                self.nodes.push(right);
-            }
-            if let Some(left) = node.left {
-                self.nodes.push(left);
-            }
-            if node.value.is_some() {
-                return Some(&node.prefix);
-            }
-        }
-        None
+        let map = self.map;
+        self.nodes
+            .next(&map.table)
+            .map(|idx| &map.table[idx].prefix)
+    }
+}
+
+impl<'a, P, T> DoubleEndedIterator for Keys<'a, P, T> {
+    fn next_back(&mut self) -> Option<&'a P> {
+        let map = self.map;
+        self.nodes
+            .next_back(&map.table)
+            .map(|idx| &map.table[idx].prefix)
+    }
+}
+
+/// Convert a prefix into another prefix type with the same representation.
+fn convert<P: Prefix, Q: Prefix<R = P::R>>(p: &P) -> Q {
+    Q::from_repr_len(p.mask(), p.prefix_len())
+}
+
+/// An iterator over all prefixes of a [`PrefixMap`] in lexicographic order, converted into another
+/// prefix type `Q`. See [`PrefixMap::keys_mapped`].
+#[derive(Clone)]
+pub struct KeysMapped<'a, P, T, Q> {
+    keys: Keys<'a, P, T>,
+    _prefix: PhantomData<Q>,
+}
+
+impl<'a, P: Prefix, T, Q: Prefix<R = P::R>> Iterator for KeysMapped<'a, P, T, Q> {
+    type Item = Q;
+
+    fn next(&mut self) -> Option<Q> {
+        self.keys.next().map(convert)
+    }
+}
+
+impl<'a, P: Prefix, T, Q: Prefix<R = P::R>> DoubleEndedIterator for KeysMapped<'a, P, T, Q> {
+    fn next_back(&mut self) -> Option<Q> {
+        self.keys.next_back().map(convert)
+    }
+}
+
+/// An iterator over all entries of a [`PrefixMap`] in lexicographic order, with the prefixes
+/// converted into another prefix type `Q`. See [`PrefixMap::iter_mapped`].
+#[derive(Clone)]
+pub struct IterMapped<'a, P, T, Q> {
+    iter: Iter<'a, P, T>,
+    _prefix: PhantomData<Q>,
+}
+
+impl<'a, P: Prefix, T, Q: Prefix<R = P::R>> Iterator for IterMapped<'a, P, T, Q> {
+    type Item = (Q, &'a T);
+
+    fn next(&mut self) -> Option<(Q, &'a T)> {
+        self.iter.next().map(|(p, t)| (convert(p), t))
+    }
+}
+
+impl<'a, P: Prefix, T, Q: Prefix<R = P::R>> DoubleEndedIterator for IterMapped<'a, P, T, Q> {
+    fn next_back(&mut self) -> Option<(Q, &'a T)> {
+        self.iter.next_back().map(|(p, t)| (convert(p), t))
     }
 }
 
@@ -63,26 +205,26 @@ impl<'a, P, T> Iterator for Keys<'a, P, T> {
 #[derive(Clone)]
 pub struct Values<'a, P, T> {
     map: &'a PrefixMap<P, T>,
-    nodes: Vec<usize>,
+    nodes: Traversal,
 }
 
 impl<'a, P, T> Iterator for Values<'a, P, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<&'a T> {
-        while let Some(cur) = self.nodes.pop() {
-            let node = &self.map.table[cur];
-            if let Some(right) = node.right {
-                self.nodes.push(right);
-            }
-            if let Some(left) = node.left {
-                self.nodes.push(left);
-            }
-            if let Some(v) = node.value.as_ref() {
-                return Some(v);
-            }
-        }
-        None
+        let map = self.map;
+        self.nodes
+            .next(&map.table)
+            .and_then(|idx| map.table[idx].value.as_ref())
+    }
+}
+
+impl<'a, P, T> DoubleEndedIterator for Values<'a, P, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        let map = self.map;
+        self.nodes
+            .next_back(&map.table)
+            .and_then(|idx| map.table[idx].value.as_ref())
     }
 }
 
@@ -99,14 +241,14 @@ impl<P: Prefix, T> Iterator for IntoIter<P, T> {
     fn next(&mut self) -> Option<(P, T)> {
         while let Some(cur) = self.nodes.pop() {
             let node = &mut self.map.table[cur];
-            if let Some(right) = node.right {
+            if let Some(right) = node.right() {
                 self.nodes.push(right);
             }
-            if let Some(left) = node.left {
+            if let Some(left) = node.left() {
                 self.nodes.push(left);
             }
             if let Some(v) = node.value.take() {
-                return Some((std::mem::replace(&mut node.prefix, P::zero()), v));
+                return Some((core::mem::replace(&mut node.prefix, P::zero()), v));
             }
         }
         None
@@ -126,14 +268,14 @@ impl<P: Prefix, T> Iterator for IntoKeys<P, T> {
     fn next(&mut self) -> Option<P> {
         while let Some(cur) = self.nodes.pop() {
             let node = &mut self.map.table[cur];
-            if let Some(right) = node.right {
+            if let Some(right) = node.right() {
                 self.nodes.push(right);
             }
-            if let Some(left) = node.left {
+            if let Some(left) = node.left() {
                 self.nodes.push(left);
             }
             if node.value.is_some() {
-                return Some(std::mem::replace(&mut node.prefix, P::zero()));
+                return Some(core::mem::replace(&mut node.prefix, P::zero()));
             }
         }
         None
@@ -154,10 +296,10 @@ impl<P, T> Iterator for IntoValues<P, T> {
     fn next(&mut self) -> Option<T> {
         while let Some(cur) = self.nodes.pop() {
             let node = &mut self.map.table[cur];
-            if let Some(right) = node.right {
+            if let Some(right) = node.right() {
                 self.nodes.push(right);
             }
-            if let Some(left) = node.left {
+            if let Some(left) = node.left() {
                 self.nodes.push(left);
             }
             if let Some(v) = node.value.take() {
@@ -168,6 +310,168 @@ impl<P, T> Iterator for IntoValues<P, T> {
     }
 }
 
+/// An item yielded by [`PrefixMap::children_up_to_len`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundedEntry<'a, P, T> {
+    /// An entry whose prefix length is at most the maximum length.
+    Entry(&'a P, &'a T),
+    /// A branch longer than the maximum length that was not entered.
+    Truncated {
+        /// The prefix of the topmost node of the branch that was skipped.
+        prefix: &'a P,
+        /// The number of entries within that branch.
+        count: usize,
+    },
+}
+
+/// An iterator over all entries of a [`PrefixMap`] up to a maximum prefix length, in lexicographic
+/// order. See [`PrefixMap::children_up_to_len`].
+#[derive(Clone)]
+pub struct ChildrenUpToLen<'a, P, T> {
+    map: &'a PrefixMap<P, T>,
+    nodes: Vec<usize>,
+    max_len: u8,
+}
+
+impl<'a, P: Prefix, T> Iterator for ChildrenUpToLen<'a, P, T> {
+    type Item = BoundedEntry<'a, P, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(cur) = self.nodes.pop() {
+            let node = &self.map.table[cur];
+            if node.prefix.prefix_len() > self.max_len {
+                let count = Values {
+                    map: self.map,
+                    nodes: Traversal::new(Some(cur)),
+                }
+                .count();
+                if count > 0 {
+                    return Some(BoundedEntry::Truncated {
+                        prefix: &node.prefix,
+                        count,
+                    });
+                }
+                continue;
+            }
+            if let Some(right) = node.right() {
+                self.nodes.push(right);
+            }
+            if let Some(left) = node.left() {
+                self.nodes.push(left);
+            }
+            if let Some(v) = &node.value {
+                return Some(BoundedEntry::Entry(&node.prefix, v));
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over all entries of a [`PrefixMap`] that do not contain any other entry, in
+/// lexicographic order. See [`PrefixMap::iter_leaves`].
+#[derive(Clone)]
+pub struct Leaves<'a, P, T> {
+    iter: Iter<'a, P, T>,
+    /// The last entry, which is yielded unless the next entry is contained within it.
+    pending: Option<(&'a P, &'a T)>,
+}
+
+impl<'a, P: Prefix, T> Iterator for Leaves<'a, P, T> {
+    type Item = (&'a P, &'a T);
+
+    fn next(&mut self) -> Option<(&'a P, &'a T)> {
+        // In lexicographic order, all entries contained within some prefix directly follow it.
+        for (p, t) in self.iter.by_ref() {
+            match self.pending.replace((p, t)) {
+                Some((last, _)) if last.contains(p) => {}
+                Some(leaf) => return Some(leaf),
+                None => {}
+            }
+        }
+        self.pending.take()
+    }
+}
+
+/// An iterator over all entries of a [`PrefixMap`] that are contained within a prefix without any
+/// other entry in between, in lexicographic order. See [`PrefixMap::direct_children`].
+#[derive(Clone)]
+pub struct DirectChildren<'a, P, T> {
+    map: &'a PrefixMap<P, T>,
+    nodes: Vec<usize>,
+}
+
+impl<'a, P, T> Iterator for DirectChildren<'a, P, T> {
+    type Item = (&'a P, &'a T);
+
+    fn next(&mut self) -> Option<(&'a P, &'a T)> {
+        while let Some(cur) = self.nodes.pop() {
+            let node = &self.map.table[cur];
+            if let Some(v) = &node.value {
+                // do not descend into the subtree of an entry.
+                return Some((&node.prefix, v));
+            }
+            self.nodes.extend(node.right());
+            self.nodes.extend(node.left());
+        }
+        None
+    }
+}
+
+/// An iterator over all entries of a [`PrefixMap`] within a range of prefixes, in lexicographic
+/// order. See [`PrefixMap::range`].
+#[derive(Clone)]
+pub struct Range<'a, P: Prefix, T> {
+    map: &'a PrefixMap<P, T>,
+    nodes: Traversal,
+    start: Bound<(P::R, u8)>,
+    end: Bound<(P::R, u8)>,
+}
+
+/// Get the key of a prefix by which the lexicographic order is determined.
+fn order_key<P: Prefix>(prefix: &P) -> (P::R, u8) {
+    (prefix.mask(), prefix.prefix_len())
+}
+
+impl<'a, P: Prefix, T> Iterator for Range<'a, P, T> {
+    type Item = (&'a P, &'a T);
+
+    fn next(&mut self) -> Option<(&'a P, &'a T)> {
+        let map = self.map;
+        let idx = self.nodes.next(&map.table)?;
+        let key = order_key(&map.table[idx].prefix);
+        let in_range = match self.end {
+            Bound::Included(end) => key <= end,
+            Bound::Excluded(end) => key < end,
+            Bound::Unbounded => true,
+        };
+        if in_range {
+            map.table[idx].prefix_value()
+        } else {
+            self.nodes.finish();
+            None
+        }
+    }
+}
+
+impl<'a, P: Prefix, T> DoubleEndedIterator for Range<'a, P, T> {
+    fn next_back(&mut self) -> Option<(&'a P, &'a T)> {
+        let map = self.map;
+        let idx = self.nodes.next_back(&map.table)?;
+        let key = order_key(&map.table[idx].prefix);
+        let in_range = match self.start {
+            Bound::Included(start) => key >= start,
+            Bound::Excluded(start) => key > start,
+            Bound::Unbounded => true,
+        };
+        if in_range {
+            map.table[idx].prefix_value()
+        } else {
+            self.nodes.finish();
+            None
+        }
+    }
+}
+
 impl<P: Prefix, T> IntoIterator for PrefixMap<P, T> {
     type Item = (P, T);
 
@@ -189,7 +493,7 @@ impl<'a, P, T> IntoIterator for &'a PrefixMap<P, T> {
     fn into_iter(self) -> Self::IntoIter {
         Iter {
             map: self,
-            nodes: vec![0],
+            nodes: Traversal::new(Some(0)),
         }
     }
 }
@@ -206,7 +510,7 @@ impl<'a, P, T> Iterator for IterMut<'a, P, T> {
         let mut idx = 0;
         while idx < self.table.len() {
             if self.table[idx].value.is_some() {
-                let table = std::mem::take(&mut self.table);
+                let table = core::mem::take(&mut self.table);
                 let (first, second) = table.split_at_mut(idx + 1);
                 self.table = second;
                 return first.get_mut(idx).unwrap().prefix_value_mut();
@@ -230,7 +534,7 @@ impl<'a, P, T> Iterator for ValuesMut<'a, P, T> {
         let mut idx = 0;
         while idx < self.table.len() {
             if self.table[idx].value.is_some() {
-                let table = std::mem::take(&mut self.table);
+                let table = core::mem::take(&mut self.table);
                 let (first, second) = table.split_at_mut(idx + 1);
                 self.table = second;
                 return first.get_mut(idx).unwrap().value.as_mut();
@@ -244,7 +548,8 @@ impl<'a, P, T> Iterator for ValuesMut<'a, P, T> {
 
 impl<P, T> PrefixMap<P, T> {
     /// An iterator visiting all key-value pairs in lexicographic order. The iterator element type
-    /// is `(&P, &T)`.
+    /// is `(&P, &T)`. The iterator is double-ended, so it can also traverse the map starting from
+    /// the largest prefix using [`Iterator::rev`].
     ///
     /// ```
     /// # use prefix_trie::*;
@@ -311,7 +616,7 @@ impl<P, T> PrefixMap<P, T> {
     pub fn keys(&self) -> Keys<'_, P, T> {
         Keys {
             map: self,
-            nodes: vec![0],
+            nodes: Traversal::new(Some(0)),
         }
     }
 
@@ -338,6 +643,7 @@ impl<P, T> PrefixMap<P, T> {
     /// pm.insert("192.168.0.0/24".parse()?, 4);
     /// pm.insert("192.168.2.0/24".parse()?, 5);
     /// assert_eq!(pm.values().collect::<Vec<_>>(), vec![&1, &2, &4, &3, &5]);
+    /// assert_eq!(pm.values().rev().collect::<Vec<_>>(), vec![&5, &3, &4, &2, &1]);
     /// # Ok(())
     /// # }
     /// ```
@@ -345,7 +651,7 @@ impl<P, T> PrefixMap<P, T> {
     pub fn values(&self) -> Values<'_, P, T> {
         Values {
             map: self,
-            nodes: vec![0],
+            nodes: Traversal::new(Some(0)),
         }
     }
 
@@ -372,9 +678,95 @@ impl<P, T> PrefixMap<P, T>
 where
     P: Prefix,
 {
+    /// An iterator visiting all keys in lexicographic order, converted into another prefix type `Q`
+    /// with the same representation. The keys are converted on the fly, without collecting them
+    /// first. The iterator element type is `Q`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<(u32, u8), _> = PrefixMap::new();
+    /// pm.insert((0xc0a8_0000, 22), 1);
+    /// pm.insert((0xc0a8_0000, 23), 2);
+    /// assert_eq!(
+    ///     pm.keys_mapped::<Ipv4Net>().collect::<Vec<_>>(),
+    ///     vec!["192.168.0.0/22".parse()?, "192.168.0.0/23".parse()?]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn keys_mapped<Q: Prefix<R = P::R>>(&self) -> KeysMapped<'_, P, T, Q> {
+        KeysMapped {
+            keys: self.keys(),
+            _prefix: PhantomData,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs in lexicographic order, with the keys converted
+    /// into another prefix type `Q` with the same representation. The iterator element type is
+    /// `(Q, &T)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<(u32, u8), _> = PrefixMap::new();
+    /// pm.insert((0xc0a8_0000, 22), 1);
+    /// pm.insert((0xc0a8_0000, 23), 2);
+    /// assert_eq!(
+    ///     pm.iter_mapped::<Ipv4Net>().collect::<Vec<_>>(),
+    ///     vec![("192.168.0.0/22".parse()?, &1), ("192.168.0.0/23".parse()?, &2)]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_mapped<Q: Prefix<R = P::R>>(&self) -> IterMapped<'_, P, T, Q> {
+        IterMapped {
+            iter: self.iter(),
+            _prefix: PhantomData,
+        }
+    }
+
+    /// An iterator visiting only the most specific entries, i.e., all entries that do not contain
+    /// any other entry of the map. The iterator element type is `(&'a P, &'a T)`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("192.168.2.0/23".parse()?, 3);
+    /// pm.insert("192.168.0.0/24".parse()?, 4);
+    /// pm.insert("10.0.0.0/8".parse()?, 5);
+    /// assert_eq!(
+    ///     pm.iter_leaves().collect::<Vec<_>>(),
+    ///     vec![
+    ///         (&"10.0.0.0/8".parse()?, &5),
+    ///         (&"192.168.0.0/24".parse()?, &4),
+    ///         (&"192.168.2.0/23".parse()?, &3),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_leaves(&self) -> Leaves<'_, P, T> {
+        Leaves {
+            iter: self.iter(),
+            pending: None,
+        }
+    }
+
     /// Get an iterator over the node itself and all children with a value. All elements returned
     /// have a prefix that is contained within `prefix` itself (or are the same).
     ///
+    /// Counting the children with this iterator takes time linear in their number. Instead, an
+    /// [`AugmentedPrefixMap`](crate::map::AugmentedPrefixMap) maintains the number of entries in
+    /// each subtree, and [`count_under`](crate::map::AugmentedPrefixMap::count_under) counts them
+    /// in time linear in the prefix length.
+    ///
     /// ```
     /// # use prefix_trie::*;
     /// # use ipnet::Ipv4Net;
@@ -396,32 +788,48 @@ where
     /// # }
     /// ```
     pub fn children(&self, prefix: &P) -> Iter<'_, P, T> {
-        // first, find the longest prefix containing `prefix`.
-        let mut idx = 0;
-        let mut cur_p = &self.table[idx].prefix;
-        let nodes = loop {
-            if cur_p.eq(prefix) {
-                break vec![idx];
-            }
-            let right = to_right(cur_p, prefix);
-            match self.get_child(idx, right) {
-                Some(c) => {
-                    cur_p = &self.table[c].prefix;
-                    if cur_p.contains(prefix) {
-                        // continue traversal
-                        idx = c;
-                    } else if prefix.contains(cur_p) {
-                        break vec![c];
-                    } else {
-                        break vec![];
-                    }
-                }
-                None => break vec![],
-            }
-        };
+        let nodes = Traversal::new(self.children_root(prefix));
         Iter { map: self, nodes }
     }
 
+    /// Get an iterator over all entries that are strictly contained within `prefix`, but not
+    /// within any other entry that is strictly contained within `prefix`. In other words, these are
+    /// the entries one level below `prefix` in the hierarchy of the map. The `prefix` itself does
+    /// not need to be present in the map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("192.168.0.0/24".parse()?, 3);
+    /// pm.insert("192.168.3.0/24".parse()?, 4);
+    /// assert_eq!(
+    ///     pm.direct_children(&"192.168.0.0/22".parse()?).collect::<Vec<_>>(),
+    ///     vec![
+    ///         (&"192.168.0.0/23".parse()?, &2),
+    ///         (&"192.168.3.0/24".parse()?, &4),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn direct_children(&self, prefix: &P) -> DirectChildren<'_, P, T> {
+        let mut nodes = Vec::new();
+        if let Some(root) = self.children_root(prefix) {
+            let node = &self.table[root];
+            if node.prefix.eq(prefix) {
+                nodes.extend(node.right());
+                nodes.extend(node.left());
+            } else {
+                nodes.push(root);
+            }
+        }
+        DirectChildren { map: self, nodes }
+    }
+
     /// Get an iterator over the node itself and all children with a value. All elements returned
     /// have a prefix that is contained within `prefix` itself (or are the same). This function will
     /// consume `self`, returning an iterator over all owned children.
@@ -447,12 +855,199 @@ where
     /// # }
     /// ```
     pub fn into_children(self, prefix: &P) -> IntoIter<P, T> {
+        let nodes = self.children_root(prefix).into_iter().collect();
+        IntoIter { map: self, nodes }
+    }
+
+    /// Get an iterator over the node itself and all children with a value, but never descend into
+    /// nodes whose prefix length is larger than `max_len`. Instead, each such branch is reported
+    /// as a single [`BoundedEntry::Truncated`] item, containing the prefix of the branch and the
+    /// number of entries that were skipped within it. Branches without any entries are not
+    /// reported. Items are yielded in lexicographic order.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// use prefix_trie::map::BoundedEntry;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("192.168.2.0/23".parse()?, 3);
+    /// pm.insert("192.168.0.0/24".parse()?, 4);
+    /// pm.insert("192.168.0.0/25".parse()?, 5);
+    /// pm.insert("192.168.2.0/24".parse()?, 6);
+    /// assert_eq!(
+    ///     pm.children_up_to_len(&"192.168.0.0/22".parse()?, 23).collect::<Vec<_>>(),
+    ///     vec![
+    ///         BoundedEntry::Entry(&"192.168.0.0/22".parse()?, &1),
+    ///         BoundedEntry::Entry(&"192.168.0.0/23".parse()?, &2),
+    ///         BoundedEntry::Truncated { prefix: &"192.168.0.0/24".parse()?, count: 2 },
+    ///         BoundedEntry::Entry(&"192.168.2.0/23".parse()?, &3),
+    ///         BoundedEntry::Truncated { prefix: &"192.168.2.0/24".parse()?, count: 1 },
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn children_up_to_len(&self, prefix: &P, max_len: u8) -> ChildrenUpToLen<'_, P, T> {
+        let nodes = self.children_root(prefix).into_iter().collect();
+        ChildrenUpToLen {
+            map: self,
+            nodes,
+            max_len,
+        }
+    }
+
+    /// Get an iterator over all entries that are larger or equal to `start` in lexicographic
+    /// order. This allows resuming the iteration of a large map from a given prefix. The iterator
+    /// is the same as `self.iter()` after skipping all elements smaller than `start`, but it does
+    /// not need to visit any of the skipped elements.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("192.168.2.0/23".parse()?, 3);
+    /// pm.insert("192.168.0.0/24".parse()?, 4);
+    /// pm.insert("192.168.2.0/24".parse()?, 5);
+    /// assert_eq!(
+    ///     pm.iter_from(&"192.168.1.0/24".parse()?).collect::<Vec<_>>(),
+    ///     vec![
+    ///         (&"192.168.2.0/23".parse()?, &3),
+    ///         (&"192.168.2.0/24".parse()?, &5),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_from(&self, start: &P) -> Iter<'_, P, T> {
+        let front = self.lower_bound(start, true);
+        let back = front.iter().rev().map(|idx| (*idx, false)).collect();
+        Iter {
+            map: self,
+            nodes: Traversal::with_stacks(front, back),
+        }
+    }
+
+    /// Get an iterator over all entries whose prefix lies within `range` in lexicographic order.
+    /// Similar to [`PrefixMap::iter_from`], the iterator does not need to visit any element before
+    /// the range and stops as soon as it reaches the end of the range.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("192.168.2.0/23".parse()?, 3);
+    /// pm.insert("192.168.0.0/24".parse()?, 4);
+    /// pm.insert("192.168.2.0/24".parse()?, 5);
+    /// let start: Ipv4Net = "192.168.0.0/23".parse()?;
+    /// let end: Ipv4Net = "192.168.2.0/24".parse()?;
+    /// assert_eq!(
+    ///     pm.range(start..end).collect::<Vec<_>>(),
+    ///     vec![
+    ///         (&"192.168.0.0/23".parse()?, &2),
+    ///         (&"192.168.0.0/24".parse()?, &4),
+    ///         (&"192.168.2.0/23".parse()?, &3),
+    ///     ]
+    /// );
+    /// assert_eq!(
+    ///     pm.range(..=start).rev().collect::<Vec<_>>(),
+    ///     vec![
+    ///         (&"192.168.0.0/23".parse()?, &2),
+    ///         (&"192.168.0.0/22".parse()?, &1),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn range<R: RangeBounds<P>>(&self, range: R) -> Range<'_, P, T> {
+        let front = match range.start_bound() {
+            Bound::Included(start) => self.lower_bound(start, true),
+            Bound::Excluded(start) => self.lower_bound(start, false),
+            Bound::Unbounded => vec![0],
+        };
+        let back = match range.end_bound() {
+            Bound::Included(end) => self.upper_bound(end, true),
+            Bound::Excluded(end) => self.upper_bound(end, false),
+            Bound::Unbounded => vec![(0, false)],
+        };
+        Range {
+            map: self,
+            nodes: Traversal::with_stacks(front, back),
+            start: range.start_bound().map(order_key),
+            end: range.end_bound().map(order_key),
+        }
+    }
+
+    /// Get the smallest prefix in the map that is lexicographically larger than `prefix`. The
+    /// `prefix` itself does not need to be present in the map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/24".parse()?, 2);
+    /// pm.insert("192.168.2.0/23".parse()?, 3);
+    /// assert_eq!(pm.next_key(&"192.168.0.0/22".parse()?), Some(&"192.168.0.0/24".parse()?));
+    /// assert_eq!(pm.next_key(&"192.168.1.0/24".parse()?), Some(&"192.168.2.0/23".parse()?));
+    /// assert_eq!(pm.next_key(&"192.168.2.0/23".parse()?), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn next_key(&self, prefix: &P) -> Option<&P> {
+        self.range((Bound::Excluded(prefix), Bound::Unbounded))
+            .next()
+            .map(|(p, _)| p)
+    }
+
+    /// Get the largest prefix in the map that is lexicographically smaller than `prefix`. The
+    /// `prefix` itself does not need to be present in the map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/24".parse()?, 2);
+    /// pm.insert("192.168.2.0/23".parse()?, 3);
+    /// assert_eq!(pm.prev_key(&"192.168.2.0/23".parse()?), Some(&"192.168.0.0/24".parse()?));
+    /// assert_eq!(pm.prev_key(&"192.168.0.0/23".parse()?), Some(&"192.168.0.0/22".parse()?));
+    /// assert_eq!(pm.prev_key(&"192.168.0.0/22".parse()?), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prev_key(&self, prefix: &P) -> Option<&P> {
+        self.range((Bound::Unbounded, Bound::Excluded(prefix)))
+            .next_back()
+            .map(|(p, _)| p)
+    }
+
+    /// Get an iterator over all entries in the subtree of `root`.
+    pub(super) fn iter_subtree(&self, root: Option<usize>) -> Iter<'_, P, T> {
+        Iter {
+            map: self,
+            nodes: Traversal::new(root),
+        }
+    }
+
+    /// Find the node with the shortest prefix that is contained within `prefix` (or the same).
+    pub(super) fn children_root(&self, prefix: &P) -> Option<usize> {
         // first, find the longest prefix containing `prefix`.
         let mut idx = 0;
         let mut cur_p = &self.table[idx].prefix;
-        let nodes = loop {
+        loop {
             if cur_p.eq(prefix) {
-                break vec![idx];
+                break Some(idx);
             }
             let right = to_right(cur_p, prefix);
             match self.get_child(idx, right) {
@@ -462,15 +1057,91 @@ where
                         // continue traversal
                         idx = c;
                     } else if prefix.contains(cur_p) {
-                        break vec![c];
+                        break Some(c);
                     } else {
-                        break vec![];
+                        break None;
                     }
                 }
-                None => break vec![],
+                None => break None,
             }
-        };
-        IntoIter { map: self, nodes }
+        }
+    }
+
+    /// Compute the traversal stack of all nodes that are larger than `start` (or equal if
+    /// `inclusive`) in lexicographic order. The stack contains the roots of the subtrees that are
+    /// entirely within the bound, where the smallest subtree is at the top.
+    fn lower_bound(&self, start: &P, inclusive: bool) -> Vec<usize> {
+        let mut stack = Vec::new();
+        let mut idx = 0;
+        loop {
+            let node = &self.table[idx];
+            if node.prefix.eq(start) {
+                if inclusive {
+                    stack.push(idx);
+                } else {
+                    stack.extend(node.right());
+                    stack.extend(node.left());
+                }
+                break;
+            }
+            // `node` contains `start`, so it is smaller itself.
+            let right = to_right(&node.prefix, start);
+            if !right {
+                stack.extend(node.right());
+            }
+            let Some(child) = self.get_child(idx, right) else {
+                break;
+            };
+            let child_p = &self.table[child].prefix;
+            if child_p.contains(start) {
+                idx = child;
+                continue;
+            }
+            // the entire subtree of `child` is either smaller or larger than `start`.
+            if start.contains(child_p) || child_p.mask() > start.mask() {
+                stack.push(child);
+            }
+            break;
+        }
+        stack
+    }
+
+    /// Compute the reverse traversal stack of all nodes that are smaller than `end` (or equal if
+    /// `inclusive`) in lexicographic order. The stack contains both the roots of subtrees that are
+    /// entirely within the bound (not expanded) and individual nodes (expanded), where the largest
+    /// element is at the top.
+    fn upper_bound(&self, end: &P, inclusive: bool) -> Vec<(usize, bool)> {
+        let mut stack = Vec::new();
+        let mut idx = 0;
+        loop {
+            let node = &self.table[idx];
+            if node.prefix.eq(end) {
+                if inclusive {
+                    stack.push((idx, true));
+                }
+                break;
+            }
+            // `node` contains `end`, so it is smaller itself, but its children might not be.
+            stack.push((idx, true));
+            let right = to_right(&node.prefix, end);
+            if right {
+                stack.extend(node.left().map(|left| (left, false)));
+            }
+            let Some(child) = self.get_child(idx, right) else {
+                break;
+            };
+            let child_p = &self.table[child].prefix;
+            if child_p.contains(end) {
+                idx = child;
+                continue;
+            }
+            // the entire subtree of `child` is either smaller or larger than `end`.
+            if !(end.contains(child_p) || child_p.mask() > end.mask()) {
+                stack.push((child, false));
+            }
+            break;
+        }
+        stack
     }
 }
 
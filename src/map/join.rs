@@ -0,0 +1,115 @@
+//! Simultaneous traversal of two maps.
+
+use core::{cmp::Ordering, iter::Peekable};
+
+use super::*;
+
+/// An iterator that walks two maps in lockstep, yielding each distinct prefix of both maps in
+/// lexicographic order together with the values in both maps. See [`PrefixMap::joint_iter`].
+pub struct JointIter<'a, P, T, U> {
+    a: Peekable<Iter<'a, P, T>>,
+    b: Peekable<Iter<'a, P, U>>,
+}
+
+impl<'a, P: Prefix, T, U> Iterator for JointIter<'a, P, T, U> {
+    type Item = (&'a P, Option<&'a T>, Option<&'a U>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.a.peek(), self.b.peek()) {
+            (Some((pa, _)), Some((pb, _))) => cmp(*pa, *pb),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        match order {
+            Ordering::Less => self.a.next().map(|(p, t)| (p, Some(t), None)),
+            Ordering::Greater => self.b.next().map(|(p, u)| (p, None, Some(u))),
+            Ordering::Equal => {
+                let (p, t) = self.a.next()?;
+                let (_, u) = self.b.next()?;
+                Some((p, Some(t), Some(u)))
+            }
+        }
+    }
+}
+
+/// Compare two prefixes in lexicographic order.
+fn cmp<P: Prefix>(a: &P, b: &P) -> Ordering {
+    (a.mask(), a.prefix_len()).cmp(&(b.mask(), b.prefix_len()))
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Iterate over both `self` and `other` in lockstep. The iterator yields each prefix that is
+    /// present in `self` or `other` exactly once in lexicographic order, together with its value
+    /// in `self` and in `other` (or `None` if it is missing). Neither map is collected or copied,
+    /// and the traversal takes linear time in the size of both maps.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut old: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// old.insert("10.0.0.0/8".parse()?, 1);
+    /// old.insert("192.168.0.0/16".parse()?, 2);
+    /// let mut new: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// new.insert("10.0.0.0/8".parse()?, 3);
+    /// new.insert("172.16.0.0/12".parse()?, 4);
+    /// assert_eq!(
+    ///     old.joint_iter(&new).collect::<Vec<_>>(),
+    ///     vec![
+    ///         (&"10.0.0.0/8".parse()?, Some(&1), Some(&3)),
+    ///         (&"172.16.0.0/12".parse()?, None, Some(&4)),
+    ///         (&"192.168.0.0/16".parse()?, Some(&2), None),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn joint_iter<'a, U>(&'a self, other: &'a PrefixMap<P, U>) -> JointIter<'a, P, T, U> {
+        JointIter {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
+        }
+    }
+
+    /// Create a new map from all prefixes in `self` or `other` (an outer join). For each prefix,
+    /// the function `f` is called with the values of both maps (or `None` if the prefix is missing
+    /// in one of them), and the returned value is stored in the new map. If `f` returns `None`,
+    /// the prefix is not part of the new map. Both maps are traversed simultaneously, so this
+    /// takes linear time in the size of both maps.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut peer_a: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// peer_a.insert("10.0.0.0/8".parse()?, 100);
+    /// peer_a.insert("192.168.0.0/16".parse()?, 200);
+    /// let mut peer_b: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// peer_b.insert("10.0.0.0/8".parse()?, "b");
+    /// peer_b.insert("172.16.0.0/12".parse()?, "b");
+    /// let combined = peer_a.union_with(&peer_b, |_, a, b| Some((a.copied(), b.is_some())));
+    /// assert_eq!(
+    ///     combined.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("10.0.0.0/8".parse()?, (Some(100), true)),
+    ///         ("172.16.0.0/12".parse()?, (None, true)),
+    ///         ("192.168.0.0/16".parse()?, (Some(200), false)),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn union_with<U, V, F>(&self, other: &PrefixMap<P, U>, mut f: F) -> PrefixMap<P, V>
+    where
+        P: Clone,
+        F: FnMut(&P, Option<&T>, Option<&U>) -> Option<V>,
+    {
+        self.joint_iter(other)
+            .filter_map(|(p, t, u)| f(p, t, u).map(|v| (p.clone(), v)))
+            .collect()
+    }
+}
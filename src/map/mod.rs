@@ -1,14 +1,104 @@
 //! Implementation of the Prefix Map.
 
-use crate::{to_right, Prefix};
+use alloc::{vec, vec::Vec};
 
+use core::{num::NonZeroU32, ops::RangeInclusive};
+
+use crate::{prefix::prefixes_in_range, to_right, Prefix};
+
+mod aggregate;
+mod append;
+mod augmented;
+mod budgeted;
+#[cfg(feature = "checked")]
+mod checked;
+mod classify;
+mod coverage;
+mod cursor;
+mod diff;
 mod entry;
+mod frozen;
+#[cfg(any(test, feature = "test-utils"))]
+mod invariants;
 mod iter;
+mod join;
+mod overlap;
+#[cfg(feature = "rayon")]
+mod par_iter;
+mod query;
+mod rebase;
+#[cfg(feature = "rand")]
+mod sample;
+mod shard;
+mod stride;
+mod summary;
+#[cfg(feature = "std")]
+mod text;
+mod view;
+mod wildcard;
 
+pub use augmented::{AugmentedPrefixMap, Max, Monoid, Sum};
+pub use budgeted::{Budgeted, ResumeToken};
+pub use classify::LpmAddresses;
+pub use coverage::Gaps;
+pub use cursor::*;
+pub use diff::{Diff, DiffItem};
 pub use entry::*;
+pub use frozen::{FrozenIter, FrozenPrefixMap};
 pub use iter::*;
+pub use join::JointIter;
+pub use overlap::OverlapError;
+#[cfg(feature = "rayon")]
+pub use par_iter::*;
+pub use shard::shard_of;
+pub use stride::StrideIndex;
+#[cfg(feature = "ipnet")]
+pub use summary::dual_stack_summary;
+pub use summary::{DualStackSummary, Stats, Summary};
+#[cfg(feature = "std")]
+pub use text::FromReaderError;
+pub use view::{OutOfViewError, TrieView, TrieViewMut};
+pub use wildcard::WildcardPrefixes;
+
+pub use crate::fmt::{DisplayCsv, DisplayTree};
 
 /// Prefix map implemented as a prefix tree.
+///
+/// Cloning a map copies all of its nodes. Use a
+/// [`PersistentPrefixMap`](crate::persistent::PersistentPrefixMap) for cheap copy-on-write
+/// snapshots that can be read while the map is modified.
+///
+/// # Dropping
+///
+/// All nodes are stored in a single vector, so dropping a map (or calling [`PrefixMap::clear`])
+/// drops every prefix and value once without traversing the tree or recursing into it. The
+/// remaining cost is that of dropping the values themselves, so there is no faster way to clear
+/// a map (the `teardown` benchmark compares both). This can still cause a noticeable pause for
+/// maps with millions of entries whose values own memory. To avoid this pause, e.g.,
+/// when replacing a routing table, move the old map to a different thread and drop it there:
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use ipnet::Ipv4Net;
+/// let mut table: PrefixMap<Ipv4Net, Vec<u32>> = PrefixMap::new();
+/// let new_table = PrefixMap::new();
+/// let old_table = std::mem::replace(&mut table, new_table);
+/// std::thread::spawn(move || drop(old_table));
+/// ```
+///
+/// The map does not implement `Drop` itself, so the drop check treats it like the `Vec` that
+/// stores its nodes, which is marked `#[may_dangle]`. Hence, the map may still hold references
+/// to data that is dropped before the map itself, as long as the values do not access them in
+/// their own `Drop` implementation:
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use ipnet::Ipv4Net;
+/// let mut map: PrefixMap<Ipv4Net, &String> = PrefixMap::new();
+/// let name = String::from("default");
+/// // `name` is dropped before `map`.
+/// map.insert("0.0.0.0/0".parse().unwrap(), &name);
+/// ```
 #[derive(Clone)]
 pub struct PrefixMap<P, T> {
     pub(crate) table: Vec<Node<P, T>>,
@@ -21,12 +111,7 @@ where
 {
     fn default() -> Self {
         Self {
-            table: vec![Node {
-                prefix: P::zero(),
-                value: None,
-                left: None,
-                right: None,
-            }],
+            table: vec![Node::new(P::zero(), None)],
             free: Vec::new(),
         }
     }
@@ -41,6 +126,25 @@ where
         Self::default()
     }
 
+    /// Create an empty prefix map with space for at least `capacity` nodes. A map with `n` entries
+    /// uses at most `2n + 1` nodes (including the root), as each insertion adds at most one
+    /// branch node.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// let pm: PrefixMap<Ipv4Net, u32> = PrefixMap::with_capacity(1000);
+    /// assert!(pm.capacity() >= 1000);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut table = Vec::with_capacity(capacity.max(1));
+        table.push(Node::new(P::zero(), None));
+        Self {
+            table,
+            free: Vec::new(),
+        }
+    }
+
     /// Get the value of an element by matching exactly on the prefix.
     ///
     /// ```
@@ -57,14 +161,8 @@ where
     /// # }
     /// ```
     pub fn get(&self, prefix: &P) -> Option<&T> {
-        let mut idx = 0;
-        loop {
-            match self.get_direction(idx, prefix) {
-                Direction::Reached => return self.table[idx].value.as_ref(),
-                Direction::Enter { next, .. } => idx = next,
-                Direction::Missing => return None,
-            }
-        }
+        let idx = self.find_exact(|map, idx| map.get_direction(idx, prefix))?;
+        self.table[idx].value.as_ref()
     }
 
     /// Get a mutable reference to a value of an element by matching exactly on the prefix.
@@ -83,14 +181,8 @@ where
     /// # }
     /// ```
     pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
-        let mut idx = 0;
-        loop {
-            match self.get_direction(idx, prefix) {
-                Direction::Reached => return self.table[idx].value.as_mut(),
-                Direction::Enter { next, .. } => idx = next,
-                Direction::Missing => return None,
-            }
-        }
+        let idx = self.find_exact(|map, idx| map.get_direction(idx, prefix))?;
+        self.table[idx].value.as_mut()
     }
 
     /// Get the value of an element by matching exactly on the prefix.
@@ -107,14 +199,8 @@ where
     /// # }
     /// ```
     pub fn get_key_value(&self, prefix: &P) -> Option<(&P, &T)> {
-        let mut idx = 0;
-        loop {
-            match self.get_direction(idx, prefix) {
-                Direction::Reached => return self.table[idx].prefix_value(),
-                Direction::Enter { next, .. } => idx = next,
-                Direction::Missing => return None,
-            }
-        }
+        let idx = self.find_exact(|map, idx| map.get_direction(idx, prefix))?;
+        self.table[idx].prefix_value()
     }
 
     /// Get a value of an element by using longest prefix matching
@@ -134,15 +220,8 @@ where
     /// # }
     /// ```
     pub fn get_lpm<'a>(&'a self, prefix: &P) -> Option<(&'a P, &'a T)> {
-        let mut idx = 0;
-        let mut best_match: Option<(&P, &T)> = None;
-        loop {
-            best_match = self.table[idx].prefix_value().or(best_match);
-            match self.get_direction(idx, prefix) {
-                Direction::Enter { next, .. } => idx = next,
-                _ => return best_match,
-            }
-        }
+        let idx = self.find_lpm(|map, idx| map.get_direction(idx, prefix))?;
+        self.table[idx].prefix_value()
     }
 
     /// Get a mutable reference to a value of an element by using longest prefix matching
@@ -181,7 +260,9 @@ where
         }
     }
 
-    /// Check if a key is present in the datastructure.
+    /// Get the longest prefix in the datastructure that strictly contains `prefix`. In contrast to
+    /// [`Self::get_lpm`], this never returns `prefix` itself, even if it is present. This is the
+    /// entry that would match `prefix` if it was removed.
     ///
     /// ```
     /// # use prefix_trie::*;
@@ -189,24 +270,80 @@ where
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
     /// pm.insert("192.168.1.0/24".parse()?, 1);
-    /// assert!(pm.contains_key(&"192.168.1.0/24".parse()?));
-    /// assert!(!pm.contains_key(&"192.168.2.0/24".parse()?));
-    /// assert!(!pm.contains_key(&"192.168.0.0/23".parse()?));
-    /// assert!(!pm.contains_key(&"192.168.1.128/25".parse()?));
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// assert_eq!(pm.get_parent(&"192.168.1.0/24".parse()?), Some((&"192.168.0.0/23".parse()?, &2)));
+    /// assert_eq!(pm.get_parent(&"192.168.1.1/32".parse()?), Some((&"192.168.1.0/24".parse()?, &1)));
+    /// assert_eq!(pm.get_parent(&"192.168.0.0/23".parse()?), None);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn contains_key(&self, prefix: &P) -> bool {
+    pub fn get_parent<'a>(&'a self, prefix: &P) -> Option<(&'a P, &'a T)> {
+        let mut idx = 0;
+        let mut best_match: Option<(&P, &T)> = None;
+        loop {
+            match self.get_direction(idx, prefix) {
+                Direction::Enter { next, .. } => {
+                    best_match = self.table[idx].prefix_value().or(best_match);
+                    idx = next;
+                }
+                Direction::Missing => return self.table[idx].prefix_value().or(best_match),
+                Direction::Reached => return best_match,
+            }
+        }
+    }
+
+    /// Get the longest prefix in the datastructure that contains `prefix` and whose prefix length
+    /// is at most `max_len`. Longer matches are ignored.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// pm.insert("192.168.1.0/25".parse()?, 2);
+    /// let query = "192.168.1.1/32".parse()?;
+    /// assert_eq!(pm.get_lpm_bounded(&query, 32), Some((&"192.168.1.0/25".parse()?, &2)));
+    /// assert_eq!(pm.get_lpm_bounded(&query, 24), Some((&"192.168.1.0/24".parse()?, &1)));
+    /// assert_eq!(pm.get_lpm_bounded(&query, 23), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_lpm_bounded<'a>(&'a self, prefix: &P, max_len: u8) -> Option<(&'a P, &'a T)> {
         let mut idx = 0;
+        let mut best_match: Option<(&P, &T)> = None;
         loop {
+            // prefix lengths only increase along the path.
+            if self.table[idx].prefix.prefix_len() > max_len {
+                return best_match;
+            }
+            best_match = self.table[idx].prefix_value().or(best_match);
             match self.get_direction(idx, prefix) {
-                Direction::Reached => return self.table[idx].value.is_some(),
                 Direction::Enter { next, .. } => idx = next,
-                Direction::Missing => return false,
+                _ => return best_match,
             }
         }
     }
 
+    /// Check if a key is present in the datastructure.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// assert!(pm.contains_key(&"192.168.1.0/24".parse()?));
+    /// assert!(!pm.contains_key(&"192.168.2.0/24".parse()?));
+    /// assert!(!pm.contains_key(&"192.168.0.0/23".parse()?));
+    /// assert!(!pm.contains_key(&"192.168.1.128/25".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get(prefix).is_some()
+    }
+
     /// Get the longest prefix in the datastructure that matches the given `prefix`.
     ///
     /// ```
@@ -254,6 +391,10 @@ where
     /// # Ok(())
     /// # }
     pub fn get_spm<'a>(&'a self, prefix: &P) -> Option<(&'a P, &'a T)> {
+        // the root node contains everything, so check it first.
+        if let Some(x) = self.table[0].prefix_value() {
+            return Some(x);
+        }
         let mut idx = 0;
         loop {
             match self.get_direction(idx, prefix) {
@@ -289,6 +430,90 @@ where
         self.get_spm(prefix).map(|(p, _)| p)
     }
 
+    /// Get the lexicographically smallest entry of the map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("10.0.0.0/8".parse()?, 3);
+    /// assert_eq!(pm.first(), Some((&"10.0.0.0/8".parse()?, &3)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn first(&self) -> Option<(&P, &T)> {
+        self.iter().next()
+    }
+
+    /// Get the lexicographically largest entry of the map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("10.0.0.0/8".parse()?, 3);
+    /// assert_eq!(pm.last(), Some((&"192.168.1.0/24".parse()?, &1)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn last(&self) -> Option<(&P, &T)> {
+        self.iter().next_back()
+    }
+
+    /// Remove and return the lexicographically smallest entry of the map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// pm.insert("10.0.0.0/8".parse()?, 2);
+    /// assert_eq!(pm.pop_first(), Some(("10.0.0.0/8".parse()?, 2)));
+    /// assert_eq!(pm.pop_first(), Some(("192.168.0.0/23".parse()?, 1)));
+    /// assert_eq!(pm.pop_first(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pop_first(&mut self) -> Option<(P, T)>
+    where
+        P: Clone,
+    {
+        let prefix = self.first()?.0.clone();
+        let value = self.remove(&prefix)?;
+        Some((prefix, value))
+    }
+
+    /// Remove and return the lexicographically largest entry of the map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// pm.insert("10.0.0.0/8".parse()?, 2);
+    /// assert_eq!(pm.pop_last(), Some(("192.168.0.0/23".parse()?, 1)));
+    /// assert_eq!(pm.pop_last(), Some(("10.0.0.0/8".parse()?, 2)));
+    /// assert_eq!(pm.pop_last(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pop_last(&mut self) -> Option<(P, T)>
+    where
+        P: Clone,
+    {
+        let prefix = self.last()?.0.clone();
+        let value = self.remove(&prefix)?;
+        Some((prefix, value))
+    }
+
     /// Insert a new item into the prefix-map. This function may return any value that existed
     /// before.
     ///
@@ -336,6 +561,44 @@ where
         }
     }
 
+    /// Insert all addresses of the inclusive `range` with the given `value`. The range is
+    /// decomposed into the smallest list of prefixes that cover it exactly, and each of them is
+    /// inserted with a clone of `value`, replacing any existing value. Nothing is inserted if the
+    /// range is empty.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use std::net::Ipv4Addr;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// let start: Ipv4Addr = "192.168.0.128".parse()?;
+    /// let end: Ipv4Addr = "192.168.2.255".parse()?;
+    /// pm.insert_range(start.into()..=end.into(), "AU");
+    /// assert_eq!(
+    ///     pm.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("192.168.0.128/25".parse()?, "AU"),
+    ///         ("192.168.1.0/24".parse()?, "AU"),
+    ///         ("192.168.2.0/24".parse()?, "AU"),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_range(&mut self, range: RangeInclusive<P::R>, value: T)
+    where
+        T: Clone,
+    {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        for prefix in prefixes_in_range(start, end) {
+            self.insert(prefix, value.clone());
+        }
+    }
+
     /// Gets the given key’s corresponding entry in the map for in-place manipulation.
     ///
     /// ```
@@ -373,6 +636,128 @@ where
         }
     }
 
+    /// Get a mutable reference to the value of `prefix`, inserting the result of `default` first
+    /// if the prefix is not yet present. This is equivalent to
+    /// `self.entry(prefix).or_insert_with(default)`, but it requires only a single traversal and
+    /// does not construct an [`Entry`]. If the prefix is already present, `prefix` is dropped and
+    /// `default` is not called.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, Vec<u32>> = PrefixMap::new();
+    /// pm.get_or_insert_with("192.168.0.0/23".parse()?, Vec::new).push(1);
+    /// pm.get_or_insert_with("192.168.0.0/23".parse()?, Vec::new).push(2);
+    /// assert_eq!(pm.get(&"192.168.0.0/23".parse()?), Some(&vec![1, 2]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_or_insert_with<F: FnOnce() -> T>(&mut self, prefix: P, default: F) -> &mut T {
+        let mut idx = 0;
+        let new = loop {
+            match self.get_direction_for_insert(idx, &prefix) {
+                DirectionForInsert::Enter { next, .. } => idx = next,
+                DirectionForInsert::Reached => {
+                    return self.table[idx].value.get_or_insert_with(default)
+                }
+                DirectionForInsert::NewLeaf { right } => {
+                    let new = self.new_node(prefix, Some(default()));
+                    self.set_child(idx, new, right);
+                    break new;
+                }
+                DirectionForInsert::NewChild { right, child_right } => {
+                    let new = self.new_node(prefix, Some(default()));
+                    let child = self.set_child(idx, new, right).unwrap();
+                    self.set_child(new, child, child_right);
+                    break new;
+                }
+                DirectionForInsert::NewBranch {
+                    branch_prefix,
+                    right,
+                    prefix_right,
+                } => {
+                    let branch = self.new_node(branch_prefix, None);
+                    let new = self.new_node(prefix, Some(default()));
+                    let child = self.set_child(idx, branch, right).unwrap();
+                    self.set_child(branch, new, prefix_right);
+                    self.set_child(branch, child, !prefix_right);
+                    break new;
+                }
+            }
+        };
+        self.table[new].value.as_mut().unwrap()
+    }
+
+    /// Insert a new item into the prefix-map, but only if the key is not yet present. On success,
+    /// return a mutable reference to the inserted value. Otherwise, return an [`OccupiedError`]
+    /// containing the occupied entry and the value, leaving the map unchanged.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// assert_eq!(pm.try_insert("192.168.0.0/23".parse()?, 1).ok(), Some(&mut 1));
+    /// let err = pm.try_insert("192.168.0.0/23".parse()?, 2).unwrap_err();
+    /// assert_eq!(err.entry.get(), &1);
+    /// assert_eq!(err.value, 2);
+    /// assert_eq!(pm.get(&"192.168.0.0/23".parse()?), Some(&1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_insert(&mut self, prefix: P, value: T) -> Result<&mut T, OccupiedError<'_, P, T>> {
+        match self.entry(prefix) {
+            Entry::Vacant(e) => Ok(e.insert(value)),
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+        }
+    }
+
+    /// Gets the entry of the longest prefix match for the given key. If any element in the map
+    /// contains `prefix`, then the returned entry is occupied and points to the longest such
+    /// element. Otherwise, the entry is vacant and points to `prefix` itself. This allows you to
+    /// update a covering element or create a more specific element with a single traversal.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// pm.entry_lpm("192.168.1.0/24".parse()?).and_modify(|x| *x += 1).or_insert(10);
+    /// pm.entry_lpm("10.0.0.0/8".parse()?).and_modify(|x| *x += 1).or_insert(20);
+    /// assert_eq!(pm.get(&"192.168.0.0/23".parse()?), Some(&2));
+    /// assert_eq!(pm.get(&"192.168.1.0/24".parse()?), None);
+    /// assert_eq!(pm.get(&"10.0.0.0/8".parse()?), Some(&20));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn entry_lpm(&mut self, prefix: P) -> Entry<'_, P, T> {
+        let mut idx = 0;
+        let mut best_match: Option<usize> = None;
+        let direction = loop {
+            if self.table[idx].value.is_some() {
+                best_match = Some(idx);
+            }
+            match self.get_direction_for_insert(idx, &prefix) {
+                DirectionForInsert::Enter { next, .. } => idx = next,
+                direction => break direction,
+            }
+        };
+        if let Some(idx) = best_match {
+            Entry::Occupied(OccupiedEntry {
+                node: &mut self.table[idx],
+            })
+        } else {
+            Entry::Vacant(VacantEntry {
+                map: self,
+                prefix,
+                idx,
+                direction,
+            })
+        }
+    }
+
     /// Removes a key from the map, returning the value at the key if the key was previously in the
     /// map. In contrast to [`Self::remove_keep_tree`], this operation will modify the tree
     /// structure. As a result, this operation takes longer than `remove_keep_tree`, as does
@@ -393,27 +778,7 @@ where
     /// # }
     /// ```
     pub fn remove(&mut self, prefix: &P) -> Option<T> {
-        let mut idx = 0;
-        let mut grandparent = None;
-        let mut grandparent_right = false;
-        let mut parent = None;
-        let mut parent_right = false;
-        // first, search for the element
-        loop {
-            match self.get_direction(idx, prefix) {
-                Direction::Reached => break,
-                Direction::Enter { next, right } => {
-                    grandparent_right = parent_right;
-                    parent_right = right;
-                    grandparent = parent;
-                    parent = Some(idx);
-                    idx = next;
-                }
-                Direction::Missing => return None,
-            }
-        }
-        self._remove_node(idx, parent, parent_right, grandparent, grandparent_right)
-            .0
+        self.remove_exact(|map, idx| map.get_direction(idx, prefix))
     }
 
     /// Removes a key from the map, returning the value at the key if the key was previously in the
@@ -495,6 +860,134 @@ where
         }
     }
 
+    /// Remove all entries that are contained within `prefix` like [`PrefixMap::remove_children`],
+    /// but stop early if the operation gets cancelled. The function `cancelled` is called once
+    /// before removing each entry. As soon as it returns `true`, the removal is aborted and `None`
+    /// is returned. The entries removed up to that point stay removed, while all other entries
+    /// remain in the map, and the map stays valid. Calling this function again resumes the
+    /// removal. In contrast to [`PrefixMap::remove_children`], each entry is removed separately.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/23".parse()?, 1);
+    /// pm.insert("192.168.0.0/24".parse()?, 2);
+    /// pm.insert("192.168.1.0/24".parse()?, 3);
+    /// pm.insert("192.168.2.0/24".parse()?, 4);
+    /// let mut budget = 2;
+    /// let result = pm.try_remove_children(&"192.168.0.0/23".parse()?, || {
+    ///     budget -= 1;
+    ///     budget < 0
+    /// });
+    /// assert_eq!(result, None);
+    /// assert_eq!(pm.iter().count(), 2);
+    /// assert_eq!(pm.try_remove_children(&"192.168.0.0/23".parse()?, || false), Some(()));
+    /// assert_eq!(pm.into_iter().collect::<Vec<_>>(), vec![("192.168.2.0/24".parse()?, 4)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_remove_children<C>(&mut self, prefix: &P, mut cancelled: C) -> Option<()>
+    where
+        C: FnMut() -> bool,
+    {
+        let (mut par, mut par_right) = (None, false);
+        let (mut grp, mut grp_right) = (None, false);
+        let mut idx = 0;
+        loop {
+            match self.get_direction_for_insert(idx, prefix) {
+                DirectionForInsert::Reached => break,
+                DirectionForInsert::Enter { next, right } => {
+                    (grp, grp_right) = (par, par_right);
+                    (par, par_right) = (Some(idx), right);
+                    idx = next
+                }
+                DirectionForInsert::NewLeaf { .. } | DirectionForInsert::NewBranch { .. } => {
+                    return Some(())
+                }
+                DirectionForInsert::NewChild { right, .. } => {
+                    (grp, grp_right) = (par, par_right);
+                    (par, par_right) = (Some(idx), right);
+                    idx = self.get_child(idx, right).unwrap();
+                    break;
+                }
+            }
+        }
+        let mut aborted = false;
+        let _ = self._retain(idx, par, par_right, grp, grp_right, |_, _| {
+            aborted = aborted || cancelled();
+            aborted
+        });
+        (!aborted).then_some(())
+    }
+
+    /// Remove all entries that are contained within `prefix` (including `prefix` itself), and
+    /// return them as a new map. Instead of cloning and removing each entry, the detached subtree
+    /// is moved into the new map node by node, so this operation is `O(k)` for `k` moved nodes.
+    /// Just like [`PrefixMap::remove_children`], this will change the tree structure.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// pm.insert("11.0.0.0/8".parse()?, 3);
+    /// let ten = pm.split_off(&"10.0.0.0/8".parse()?);
+    /// assert_eq!(
+    ///     ten.into_iter().collect::<Vec<_>>(),
+    ///     vec![("10.0.0.0/8".parse()?, 1), ("10.1.0.0/16".parse()?, 2)]
+    /// );
+    /// assert_eq!(pm.into_iter().collect::<Vec<_>>(), vec![("11.0.0.0/8".parse()?, 3)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn split_off(&mut self, prefix: &P) -> PrefixMap<P, T> {
+        if prefix.prefix_len() == 0 {
+            return core::mem::take(self);
+        }
+        let mut parent_right = false;
+        let mut parent = 0;
+        let mut idx = 0;
+        let (parent, right) = loop {
+            match self.get_direction_for_insert(idx, prefix) {
+                DirectionForInsert::Reached => break (parent, parent_right),
+                DirectionForInsert::Enter { next, right } => {
+                    parent_right = right;
+                    parent = idx;
+                    idx = next
+                }
+                DirectionForInsert::NewLeaf { .. } | DirectionForInsert::NewBranch { .. } => {
+                    return PrefixMap::new()
+                }
+                DirectionForInsert::NewChild { right, .. } => break (idx, right),
+            }
+        };
+        let root = self.clear_child(parent, right).unwrap();
+
+        // collect all nodes of the subtree, such that their slots can be reused afterwards.
+        let mut nodes = vec![root];
+        let mut i = 0;
+        while let Some(idx) = nodes.get(i).copied() {
+            nodes.extend(self.table[idx].left());
+            nodes.extend(self.table[idx].right());
+            i += 1;
+        }
+
+        let mut map = PrefixMap::new();
+        let new_root = map.move_subtree(&mut self.table, root);
+        let right = to_right(&map.table[0].prefix, &map.table[new_root].prefix);
+        map.set_child(0, new_root, right);
+        for idx in nodes {
+            self.table[idx].left = None;
+            self.table[idx].right = None;
+            self.free.push(idx);
+        }
+        map
+    }
+
     /// Clear the map but keep the allocated memory.
     ///
     /// ```
@@ -513,12 +1006,123 @@ where
     pub fn clear(&mut self) {
         self.table.clear();
         self.free.clear();
-        self.table.push(Node {
-            prefix: P::zero(),
-            value: None,
-            left: None,
-            right: None,
-        });
+        self.table.push(Node::new(P::zero(), None));
+    }
+
+    /// Get the number of nodes the map can hold without reallocating, including the root.
+    pub fn capacity(&self) -> usize {
+        self.table.capacity()
+    }
+
+    /// Reserve space for at least `additional` more nodes, beyond the ones that are currently
+    /// allocated or free to be reused. See [`PrefixMap::with_capacity`] for how many nodes are
+    /// needed per entry.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// let mut pm: PrefixMap<Ipv4Net, u32> = PrefixMap::new();
+    /// pm.reserve(1000);
+    /// assert!(pm.capacity() >= 1001);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.table
+            .reserve(additional.saturating_sub(self.free.len()));
+    }
+
+    /// Shrink the allocated memory as much as possible. Free slots at the end of the node table
+    /// are released, while free slots in between are kept for reuse. Use [`PrefixMap::compact`]
+    /// to release all free slots.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::with_capacity(1000);
+    /// pm.insert("192.168.0.0/24".parse()?, 1);
+    /// pm.insert("192.168.1.0/24".parse()?, 2);
+    /// pm.remove(&"192.168.1.0/24".parse()?);
+    /// pm.shrink_to_fit();
+    /// assert!(pm.capacity() < 1000);
+    /// assert_eq!(pm.get(&"192.168.0.0/24".parse()?), Some(&1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.free.sort_unstable();
+        while self.free.last() == Some(&(self.table.len() - 1)) {
+            self.free.pop();
+            self.table.pop();
+        }
+        self.table.shrink_to_fit();
+        self.free.shrink_to_fit();
+    }
+
+    /// Rebuild the node table, such that it only contains the nodes that are needed to store the
+    /// current entries, and release all remaining memory. This removes free slots left behind by
+    /// previous removals, and branch nodes without a value that do not actually branch, e.g., those
+    /// kept by [`PrefixMap::remove_keep_tree`]. All entries are preserved. This operation is `O(n)`
+    /// and is useful for long-lived maps after many insertions and removals.
+    ///
+    /// Compacting moves nodes to new positions in the table. The map does not expose node indices
+    /// or handles, and cursors and views borrow the map, so they cannot be kept across a call to
+    /// `compact`. The only place where node indices are visible is the output of `to_dot`, whose
+    /// node names change. A [`ResumeToken`] stores a prefix rather than a position, so it stays
+    /// valid.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// for i in 0..=255 {
+    ///     pm.insert(format!("10.{i}.0.0/16").parse()?, i);
+    /// }
+    /// for i in 1..=255 {
+    ///     pm.remove_keep_tree(&format!("10.{i}.0.0/16").parse()?);
+    /// }
+    /// pm.compact();
+    /// assert!(pm.capacity() < 16);
+    /// assert_eq!(pm.iter().collect::<Vec<_>>(), vec![(&"10.0.0.0/16".parse()?, &0)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compact(&mut self) {
+        let mut old = core::mem::take(&mut self.table);
+        self.table = Vec::with_capacity(old.len() - self.free.len());
+        self.free = Vec::new();
+        self.table.push(Node::new(P::zero(), old[0].value.take()));
+        for (child, right) in [(old[0].left(), false), (old[0].right(), true)] {
+            if let Some(child) = child.and_then(|c| self.compact_subtree(&mut old, c)) {
+                self.set_child(0, child, right);
+            }
+        }
+        self.table.shrink_to_fit();
+    }
+
+    /// Take all elements out of the map, leaving it empty, and return an owned iterator over them
+    /// in lexicographic order. The iterator owns the detached tree and does not borrow the map, so
+    /// the map can be refilled immediately while the drained elements are processed elsewhere,
+    /// e.g., on a different thread. Unlike [`PrefixMap::clear`], the allocated memory moves into
+    /// the iterator.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/24".parse()?, 1);
+    /// pm.insert("192.168.1.0/24".parse()?, 2);
+    /// let drained = pm.take_all();
+    /// pm.insert("10.0.0.0/8".parse()?, 3);
+    /// let handle = std::thread::spawn(move || drained.map(|(_, t)| t).sum::<i32>());
+    /// assert_eq!(handle.join().unwrap(), 3);
+    /// assert_eq!(pm.iter().collect::<Vec<_>>(), vec![(&"10.0.0.0/8".parse()?, &3)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_all(&mut self) -> IntoIter<P, T> {
+        core::mem::take(self).into_iter()
     }
 
     /// Keep only the elements in the map that satisfy the given condition `f`.
@@ -546,6 +1150,76 @@ where
     {
         self._retain(0, None, false, None, false, f);
     }
+
+    /// Keep only the elements in the map that satisfy the given condition `f`, like
+    /// [`PrefixMap::retain`], but stop early if the operation gets cancelled. The function
+    /// `cancelled` is called once before visiting each entry. As soon as it returns `true`, the
+    /// operation is aborted and `None` is returned. The entries visited up to that point are
+    /// removed or kept according to `f`, while all other entries remain in the map without calling
+    /// `f` on them.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/24".parse()?, 1);
+    /// pm.insert("192.168.1.0/24".parse()?, 2);
+    /// pm.insert("192.168.2.0/24".parse()?, 3);
+    /// assert_eq!(pm.try_retain(|_, t| *t % 2 == 0, || true), None);
+    /// assert_eq!(pm.iter().count(), 3);
+    /// assert_eq!(pm.try_retain(|_, t| *t % 2 == 0, || false), Some(()));
+    /// assert_eq!(pm.into_iter().collect::<Vec<_>>(), vec![("192.168.1.0/24".parse()?, 2)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_retain<F, C>(&mut self, mut f: F, mut cancelled: C) -> Option<()>
+    where
+        F: FnMut(&P, &T) -> bool,
+        C: FnMut() -> bool,
+    {
+        let mut aborted = false;
+        let _ = self._retain(0, None, false, None, false, |p, t| {
+            aborted = aborted || cancelled();
+            aborted || f(p, t)
+        });
+        (!aborted).then_some(())
+    }
+
+    /// Replace every value in the map by `f(prefix, value)`, passing the old value by value. This
+    /// keeps the structure of the tree, so no node is removed or inserted. The order in which the
+    /// elements are visited is arbitrary (and **not** in lexicographic order).
+    ///
+    /// If `f` panics, the element that is currently being transformed is removed from the map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use std::sync::Arc;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/24".parse()?, Arc::new(vec![1]));
+    /// pm.insert("192.168.1.0/24".parse()?, Arc::new(vec![2]));
+    /// pm.transform_values(|p, v| {
+    ///     let mut v = Arc::unwrap_or_clone(v);
+    ///     v.push(p.prefix_len());
+    ///     Arc::new(v)
+    /// });
+    /// assert_eq!(pm.get(&"192.168.0.0/24".parse()?), Some(&Arc::new(vec![1, 24])));
+    /// assert_eq!(pm.get(&"192.168.1.0/24".parse()?), Some(&Arc::new(vec![2, 24])));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn transform_values<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&P, T) -> T,
+    {
+        for node in self.table.iter_mut() {
+            if let Some(value) = node.value.take() {
+                node.value = Some(f(&node.prefix, value));
+            }
+        }
+    }
 }
 
 /// Private function implementations
@@ -553,6 +1227,73 @@ impl<P, T> PrefixMap<P, T>
 where
     P: Prefix,
 {
+    /// Walk down the tree by calling `direction` on each node, and return the node that is
+    /// reached. Lookups by key and by [`PrefixQuery`](crate::PrefixQuery) only differ in
+    /// `direction`.
+    #[inline(always)]
+    fn find_exact<D>(&self, mut direction: D) -> Option<usize>
+    where
+        D: FnMut(&Self, usize) -> Direction,
+    {
+        let mut idx = 0;
+        loop {
+            match direction(self, idx) {
+                Direction::Reached => return Some(idx),
+                Direction::Enter { next, .. } => idx = next,
+                Direction::Missing => return None,
+            }
+        }
+    }
+
+    /// Walk down the tree like [`Self::find_exact`], and return the last node with a value on the
+    /// way, which is the longest prefix match.
+    #[inline(always)]
+    fn find_lpm<D>(&self, mut direction: D) -> Option<usize>
+    where
+        D: FnMut(&Self, usize) -> Direction,
+    {
+        let mut idx = 0;
+        let mut best_match = None;
+        loop {
+            if self.table[idx].value.is_some() {
+                best_match = Some(idx);
+            }
+            match direction(self, idx) {
+                Direction::Enter { next, .. } => idx = next,
+                _ => return best_match,
+            }
+        }
+    }
+
+    /// Walk down the tree like [`Self::find_exact`], and remove the node that is reached,
+    /// returning its value.
+    fn remove_exact<D>(&mut self, mut direction: D) -> Option<T>
+    where
+        D: FnMut(&Self, usize) -> Direction,
+    {
+        let mut idx = 0;
+        let mut grandparent = None;
+        let mut grandparent_right = false;
+        let mut parent = None;
+        let mut parent_right = false;
+        // first, search for the element
+        loop {
+            match direction(self, idx) {
+                Direction::Reached => break,
+                Direction::Enter { next, right } => {
+                    grandparent_right = parent_right;
+                    parent_right = right;
+                    grandparent = parent;
+                    parent = Some(idx);
+                    idx = next;
+                }
+                Direction::Missing => return None,
+            }
+        }
+        self._remove_node(idx, parent, parent_right, grandparent, grandparent_right)
+            .0
+    }
+
     /// remove all elements from that point onwards.
     fn _do_remove_children(&mut self, idx: usize, right: bool) {
         let mut to_free = vec![self.get_child(idx, right).unwrap()];
@@ -560,10 +1301,10 @@ where
         while let Some(idx) = to_free.pop() {
             let node = &mut self.table[idx];
             let _ = node.value.take();
-            if let Some(left) = node.left.take() {
+            if let Some(left) = node.set_left(None) {
                 to_free.push(left)
             }
-            if let Some(right) = node.right.take() {
+            if let Some(right) = node.set_right(None) {
                 to_free.push(right)
             }
             self.free.push(idx);
@@ -574,9 +1315,9 @@ where
     #[inline(always)]
     fn get_child(&self, idx: usize, right: bool) -> Option<usize> {
         if right {
-            self.table[idx].right
+            self.table[idx].right()
         } else {
-            self.table[idx].left
+            self.table[idx].left()
         }
     }
 
@@ -584,9 +1325,9 @@ where
     #[inline(always)]
     fn set_child(&mut self, idx: usize, child: usize, right: bool) -> Option<usize> {
         if right {
-            self.table[idx].right.replace(child)
+            self.table[idx].set_right(Some(child))
         } else {
-            self.table[idx].left.replace(child)
+            self.table[idx].set_left(Some(child))
         }
     }
 
@@ -594,9 +1335,37 @@ where
     #[inline(always)]
     fn clear_child(&mut self, idx: usize, right: bool) -> Option<usize> {
         if right {
-            self.table[idx].right.take()
+            self.table[idx].set_right(None)
         } else {
-            self.table[idx].left.take()
+            self.table[idx].set_left(None)
+        }
+    }
+
+    /// Move the subtree of `node` from `old` into `self`, skipping all nodes that are not needed,
+    /// and return the index of the new root of the subtree, if it contains any values.
+    fn compact_subtree(&mut self, old: &mut [Node<P, T>], node: usize) -> Option<usize> {
+        let prefix = core::mem::replace(&mut old[node].prefix, P::zero());
+        if let Some(value) = old[node].value.take() {
+            let new = self.new_node(prefix, Some(value));
+            if let Some(left) = old[node].left().and_then(|c| self.compact_subtree(old, c)) {
+                self.set_child(new, left, false);
+            }
+            if let Some(right) = old[node].right().and_then(|c| self.compact_subtree(old, c)) {
+                self.set_child(new, right, true);
+            }
+            return Some(new);
+        }
+        let left = old[node].left().and_then(|c| self.compact_subtree(old, c));
+        let right = old[node].right().and_then(|c| self.compact_subtree(old, c));
+        match (left, right) {
+            (Some(left), Some(right)) => {
+                let new = self.new_node(prefix, None);
+                self.set_child(new, left, false);
+                self.set_child(new, right, true);
+                Some(new)
+            }
+            (Some(child), None) | (None, Some(child)) => Some(child),
+            (None, None) => None,
         }
     }
 
@@ -612,12 +1381,11 @@ where
             idx
         } else {
             let idx = self.table.len();
-            self.table.push(Node {
-                prefix,
-                value,
-                left: None,
-                right: None,
-            });
+            assert!(
+                idx <= u32::MAX as usize,
+                "a PrefixMap cannot have more than 2^32 nodes"
+            );
+            self.table.push(Node::new(prefix, value));
             idx
         }
     }
@@ -635,8 +1403,8 @@ where
         // and `parent_right` stores the direction of `idx` at `parent`.
         let node = &mut self.table[idx];
         let value = node.value.take();
-        let has_left = node.left.is_some();
-        let has_right = node.right.is_some();
+        let has_left = node.left().is_some();
+        let has_right = node.right().is_some();
 
         if has_left && has_right {
             // if the node has both left and right set, then it must remain in the tree.
@@ -649,7 +1417,9 @@ where
                 // current node. but only do that if the grandparent is something.
                 if let Some(grp) = grp {
                     if self.table[par].value.is_none() {
-                        if let Some(sibling) = self.get_child(par, !par_right) {
+                        let sibling = self.clear_child(par, !par_right);
+                        self.free.push(par);
+                        if let Some(sibling) = sibling {
                             self.set_child(grp, sibling, grp_right);
                             return (value, true);
                         } else {
@@ -686,10 +1456,12 @@ where
         // first, do the recursion
         let mut idx_removed = false;
         let mut par_removed = false;
-        if let Some(left) = self.table[idx].left {
+        // removing the left child may also remove `idx`, so remember the right child beforehand.
+        let right = self.table[idx].right();
+        if let Some(left) = self.table[idx].left() {
             (f, idx_removed) = self._retain(left, Some(idx), false, par, par_right, f);
         }
-        if let Some(right) = self.table[idx].right {
+        if let Some(right) = right {
             if idx_removed {
                 (f, par_removed) = self._retain(right, par, par_right, grp, grp_right, f);
             } else {
@@ -763,7 +1535,7 @@ where
     T: PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.iter().zip(other.iter()).all(|(a, b)| a == b)
+        self.iter().eq(other.iter())
     }
 }
 
@@ -774,16 +1546,107 @@ where
 {
 }
 
+/// The hash only depends on the entries of the map (in lexicographic order), not on the order of
+/// insertion or the structure of the tree, such that it is consistent with [`PartialEq`].
+impl<P, T> core::hash::Hash for PrefixMap<P, T>
+where
+    P: Prefix + core::hash::Hash,
+    T: core::hash::Hash,
+{
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        let mut len = 0;
+        for (p, t) in self.iter() {
+            p.hash(state);
+            t.hash(state);
+            len += 1;
+        }
+        state.write_usize(len);
+    }
+}
+
+/// Get the value of an element by matching exactly on the prefix.
+///
+/// # Panics
+///
+/// Panics if the prefix is not present in the map.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+/// let prefix = "192.168.1.0/24".parse()?;
+/// pm.insert(prefix, 1);
+/// assert_eq!(pm[&prefix], 1);
+/// # Ok(())
+/// # }
+/// ```
+impl<P, T> core::ops::Index<&P> for PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    type Output = T;
+
+    fn index(&self, prefix: &P) -> &T {
+        self.get(prefix).expect("prefix not found in the map")
+    }
+}
+
 /// Node kind that can either be a leaf, a node, or a forward node that summarizes multiple branches.
 #[derive(Clone)]
 pub(crate) struct Node<P, T> {
     pub(crate) prefix: P,
     pub(crate) value: Option<T>,
-    pub(crate) left: Option<usize>,
-    pub(crate) right: Option<usize>,
+    left: Option<NonZeroU32>,
+    right: Option<NonZeroU32>,
+}
+
+/// Convert the index of a child node into its compact representation. The root at index 0 is never
+/// a child of another node, such that `Option<NonZeroU32>` takes only four bytes.
+#[inline(always)]
+fn child_idx(idx: usize) -> NonZeroU32 {
+    u32::try_from(idx)
+        .ok()
+        .and_then(NonZeroU32::new)
+        .expect("invalid index of a child node")
 }
 
 impl<P, T> Node<P, T> {
+    /// Create a new node without any children.
+    #[inline(always)]
+    pub(crate) fn new(prefix: P, value: Option<T>) -> Self {
+        Self {
+            prefix,
+            value,
+            left: None,
+            right: None,
+        }
+    }
+
+    /// Get the index of the left child.
+    #[inline(always)]
+    pub(crate) fn left(&self) -> Option<usize> {
+        self.left.map(|idx| idx.get() as usize)
+    }
+
+    /// Get the index of the right child.
+    #[inline(always)]
+    pub(crate) fn right(&self) -> Option<usize> {
+        self.right.map(|idx| idx.get() as usize)
+    }
+
+    /// Set the left child, and return the index of the old one.
+    #[inline(always)]
+    pub(crate) fn set_left(&mut self, child: Option<usize>) -> Option<usize> {
+        core::mem::replace(&mut self.left, child.map(child_idx)).map(|idx| idx.get() as usize)
+    }
+
+    /// Set the right child, and return the index of the old one.
+    #[inline(always)]
+    pub(crate) fn set_right(&mut self, child: Option<usize>) -> Option<usize> {
+        core::mem::replace(&mut self.right, child.map(child_idx)).map(|idx| idx.get() as usize)
+    }
+
     /// get the tuple of prefix and value.
     fn prefix_value(&self) -> Option<(&P, &T)> {
         self.value.as_ref().map(|v| (&self.prefix, v))
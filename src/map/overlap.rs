@@ -0,0 +1,117 @@
+//! Insertion that rejects overlapping prefixes.
+
+use super::*;
+
+/// The error returned by [`PrefixMap::insert_non_overlapping`] if the new prefix overlaps with an
+/// entry that already exists in the map. It contains the prefix and value that were not inserted.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OverlapError<P, T> {
+    /// The prefix of the existing entry that overlaps with `prefix`. It either contains `prefix`,
+    /// is contained within `prefix`, or is the same as `prefix`.
+    pub existing: P,
+    /// The prefix that was not inserted.
+    pub prefix: P,
+    /// The value that was not inserted.
+    pub value: T,
+}
+
+impl<P: core::fmt::Display, T> core::fmt::Display for OverlapError<P, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Prefix {} overlaps with the existing entry {}",
+            self.prefix, self.existing
+        )
+    }
+}
+
+impl<P, T> core::error::Error for OverlapError<P, T>
+where
+    P: core::fmt::Debug + core::fmt::Display,
+    T: core::fmt::Debug,
+{
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Insert a new item into the map, but only if `prefix` neither contains nor is contained
+    /// within any prefix that already exists in the map. Otherwise, the map is not modified, and
+    /// an [`OverlapError`] is returned that contains the prefix and value, together with the
+    /// overlapping entry. Inserting a prefix that already exists is also rejected.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::OverlapError;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert_non_overlapping("192.168.0.0/23".parse()?, 1)?;
+    /// pm.insert_non_overlapping("192.168.2.0/24".parse()?, 2)?;
+    /// assert_eq!(
+    ///     pm.insert_non_overlapping("192.168.1.0/24".parse()?, 3),
+    ///     Err(OverlapError {
+    ///         existing: "192.168.0.0/23".parse()?,
+    ///         prefix: "192.168.1.0/24".parse()?,
+    ///         value: 3,
+    ///     })
+    /// );
+    /// assert_eq!(
+    ///     pm.insert_non_overlapping("192.168.0.0/22".parse()?, 4),
+    ///     Err(OverlapError {
+    ///         existing: "192.168.0.0/23".parse()?,
+    ///         prefix: "192.168.0.0/22".parse()?,
+    ///         value: 4,
+    ///     })
+    /// );
+    /// assert_eq!(pm.get(&"192.168.1.0/24".parse()?), None);
+    /// assert_eq!(pm.get(&"192.168.0.0/22".parse()?), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_non_overlapping(&mut self, prefix: P, value: T) -> Result<(), OverlapError<P, T>>
+    where
+        P: Clone,
+    {
+        match self.find_overlap(&prefix) {
+            Some(idx) => Err(OverlapError {
+                existing: self.table[idx].prefix.clone(),
+                prefix,
+                value,
+            }),
+            None => {
+                self.insert(prefix, value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Find the index of a node with a value whose prefix either contains or is contained within
+    /// `prefix`. Nodes that contain `prefix` are preferred, and otherwise, the first contained node
+    /// in lexicographic order is returned.
+    fn find_overlap(&self, prefix: &P) -> Option<usize> {
+        // first, search all nodes that contain `prefix`.
+        let mut idx = 0;
+        loop {
+            if self.table[idx].value.is_some() {
+                return Some(idx);
+            }
+            match self.get_direction(idx, prefix) {
+                Direction::Enter { next, .. } => idx = next,
+                _ => break,
+            }
+        }
+        // then, search the subtree of nodes that are contained within `prefix`.
+        let mut nodes = vec![self.children_root(prefix)?];
+        while let Some(cur) = nodes.pop() {
+            let node = &self.table[cur];
+            if node.value.is_some() {
+                return Some(cur);
+            }
+            nodes.extend(node.right());
+            nodes.extend(node.left());
+        }
+        None
+    }
+}
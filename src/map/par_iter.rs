@@ -0,0 +1,207 @@
+//! Parallel iterators over the [`PrefixMap`] using `rayon`.
+
+use alloc::{vec, vec::Vec};
+
+use rayon::iter::{
+    plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer},
+    IntoParallelRefMutIterator, ParallelIterator,
+};
+
+use super::*;
+
+/// A parallel iterator over all entries of a [`PrefixMap`]. The work is split along the subtrees
+/// of the map, so collecting the iterator yields the elements in lexicographic order. See
+/// [`PrefixMap::par_iter`].
+pub struct ParIter<'a, P, T> {
+    table: &'a [Node<P, T>],
+}
+
+impl<'a, P: Sync, T: Sync> ParallelIterator for ParIter<'a, P, T> {
+    type Item = (&'a P, &'a T);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let producer = SubtreeProducer {
+            table: self.table,
+            nodes: vec![(0, false)],
+        };
+        bridge_unindexed(producer, consumer)
+    }
+}
+
+/// A parallel iterator over all values of a [`PrefixMap`]. The work is split along the subtrees
+/// of the map, so collecting the iterator yields the values in lexicographic order of their
+/// prefixes. See [`PrefixMap::par_values`].
+pub struct ParValues<'a, P, T> {
+    table: &'a [Node<P, T>],
+}
+
+impl<'a, P: Sync, T: Sync> ParallelIterator for ParValues<'a, P, T> {
+    type Item = &'a T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        ParIter { table: self.table }
+            .map(|(_, t)| t)
+            .drive_unindexed(consumer)
+    }
+}
+
+/// A parallel iterator over all entries of a [`PrefixMap`] with mutable references to the values.
+/// The order of this iterator is arbitrary (and **not** in lexicographic order). See
+/// [`PrefixMap::par_iter_mut`].
+pub struct ParIterMut<'a, P, T> {
+    table: &'a mut [Node<P, T>],
+}
+
+impl<'a, P: Sync + Send, T: Send> ParallelIterator for ParIterMut<'a, P, T> {
+    type Item = (&'a P, &'a mut T);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.table
+            .par_iter_mut()
+            .filter_map(|node| node.prefix_value_mut())
+            .drive_unindexed(consumer)
+    }
+}
+
+/// Producer that traverses a set of subtrees. Each element of `nodes` is either the root of a
+/// subtree that is not yet traversed, or a single node (if `expanded`) whose children are already
+/// part of the stack. The last element is the next one in lexicographic order.
+struct SubtreeProducer<'a, P, T> {
+    table: &'a [Node<P, T>],
+    nodes: Vec<(usize, bool)>,
+}
+
+impl<'a, P: Sync, T: Sync> UnindexedProducer for SubtreeProducer<'a, P, T> {
+    type Item = (&'a P, &'a T);
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        // expand the subtree until there are at least two elements that can be distributed.
+        while self.nodes.len() < 2 {
+            match self.nodes.pop() {
+                Some((idx, false)) => {
+                    let node = &self.table[idx];
+                    self.nodes.extend(node.right().map(|r| (r, false)));
+                    self.nodes.extend(node.left().map(|l| (l, false)));
+                    if node.value.is_some() {
+                        self.nodes.push((idx, true));
+                    }
+                }
+                Some(elem) => {
+                    self.nodes.push(elem);
+                    return (self, None);
+                }
+                None => return (self, None),
+            }
+        }
+        // the last half of the stack comes first in lexicographic order.
+        let first = self.nodes.split_off(self.nodes.len() / 2);
+        let first = Self {
+            table: self.table,
+            nodes: first,
+        };
+        (first, Some(self))
+    }
+
+    fn fold_with<F>(mut self, mut folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        while let Some((idx, expanded)) = self.nodes.pop() {
+            let node = &self.table[idx];
+            if !expanded {
+                self.nodes.extend(node.right().map(|r| (r, false)));
+                self.nodes.extend(node.left().map(|l| (l, false)));
+            }
+            if let Some(elem) = node.prefix_value() {
+                folder = folder.consume(elem);
+                if folder.full() {
+                    break;
+                }
+            }
+        }
+        folder
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Get a parallel iterator over all key-value pairs. The work is split along the subtrees of
+    /// the map. The iterator preserves the lexicographic order, e.g., when collecting it into a
+    /// `Vec`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use rayon::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("192.168.2.0/23".parse()?, 3);
+    /// assert_eq!(pm.par_iter().map(|(_, t)| *t).sum::<i32>(), 6);
+    /// assert_eq!(
+    ///     pm.par_iter().collect::<Vec<_>>(),
+    ///     pm.iter().collect::<Vec<_>>(),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn par_iter(&self) -> ParIter<'_, P, T> {
+        ParIter { table: &self.table }
+    }
+
+    /// Get a parallel iterator over all values. The work is split along the subtrees of the map.
+    /// The iterator preserves the lexicographic order of the prefixes, e.g., when collecting it
+    /// into a `Vec`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use rayon::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.insert("192.168.2.0/23".parse()?, 3);
+    /// assert_eq!(pm.par_values().collect::<Vec<_>>(), vec![&1, &2, &3]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn par_values(&self) -> ParValues<'_, P, T> {
+        ParValues { table: &self.table }
+    }
+
+    /// Get a parallel iterator over all key-value pairs with mutable references to the values.
+    /// The order of this iterator is arbitrary (and **not** in lexicographic order).
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use rayon::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/22".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// pm.par_iter_mut().for_each(|(p, t)| *t += p.prefix_len() as i32);
+    /// assert_eq!(pm.get(&"192.168.0.0/22".parse()?), Some(&23));
+    /// assert_eq!(pm.get(&"192.168.0.0/23".parse()?), Some(&25));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn par_iter_mut(&mut self) -> ParIterMut<'_, P, T> {
+        ParIterMut {
+            table: &mut self.table,
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Lookups using a borrowed query instead of a key.
+
+use num_traits::{CheckedShr, PrimInt, Zero};
+
+use super::*;
+use crate::prefix::mask_from_prefix_len;
+use crate::PrefixQuery;
+
+/// A query, already masked with its prefix length.
+#[derive(Clone, Copy)]
+struct Query<R> {
+    mask: R,
+    len: u8,
+}
+
+impl<R: PrimInt + Zero + CheckedShr> Query<R> {
+    fn new<Q: PrefixQuery<R> + ?Sized>(query: &Q) -> Self {
+        let len = query.query_prefix_len();
+        Self {
+            mask: query.query_repr() & mask_from_prefix_len(len),
+            len,
+        }
+    }
+
+    /// Check if the bit at position `bit` (counted from the left) is set.
+    fn is_bit_set(&self, bit: u8) -> bool {
+        let mask = (!R::zero()).checked_shr(bit as u32).unwrap_or_else(R::zero)
+            ^ (!R::zero())
+                .checked_shr(1u32 + bit as u32)
+                .unwrap_or_else(R::zero);
+        mask & self.mask != R::zero()
+    }
+
+    /// Check if `prefix` contains the query.
+    fn is_contained_in<P: Prefix<R = R>>(&self, prefix: &P) -> bool {
+        prefix.prefix_len() <= self.len
+            && self.mask & mask_from_prefix_len(prefix.prefix_len()) == prefix.mask()
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Get the directions from some node `idx` to get to `query`.
+    fn get_direction_query(&self, cur: usize, query: Query<P::R>) -> Direction {
+        let cur_p = &self.table[cur].prefix;
+        if cur_p.prefix_len() == query.len && cur_p.mask() == query.mask {
+            Direction::Reached
+        } else {
+            let right = query.is_bit_set(cur_p.prefix_len());
+            match self.get_child(cur, right) {
+                Some(child) if query.is_contained_in(&self.table[child].prefix) => {
+                    Direction::Enter { next: child, right }
+                }
+                _ => Direction::Missing,
+            }
+        }
+    }
+
+    /// Get the value of an element by matching exactly on the query. In contrast to
+    /// [`Self::get`], this does not require constructing a key of type `P`. See [`PrefixQuery`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use std::net::Ipv4Addr;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// assert_eq!(pm.get_borrowed(&(Ipv4Addr::new(192, 168, 1, 0), 24)), Some(&1));
+    /// assert_eq!(pm.get_borrowed(&(0xc0a8_0100u32, 24)), Some(&1));
+    /// assert_eq!(pm.get_borrowed(&(0xc0a8_0100u32, 23)), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_borrowed<Q>(&self, query: &Q) -> Option<&T>
+    where
+        Q: PrefixQuery<P::R> + ?Sized,
+    {
+        let query = Query::new(query);
+        let idx = self.find_exact(|map, idx| map.get_direction_query(idx, query))?;
+        self.table[idx].value.as_ref()
+    }
+
+    /// Get the stored key and the value of the element matching the query. Since host bits of the
+    /// query are ignored, this can be used to get the canonical key for an un-normalized query.
+    /// See [`Self::get_key_value`] and [`PrefixQuery`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use std::net::Ipv4Addr;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// assert_eq!(
+    ///     pm.get_key_value_borrowed(&(Ipv4Addr::new(192, 168, 1, 5), 24)),
+    ///     Some((&"192.168.1.0/24".parse()?, &1))
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_key_value_borrowed<Q>(&self, query: &Q) -> Option<(&P, &T)>
+    where
+        Q: PrefixQuery<P::R> + ?Sized,
+    {
+        let query = Query::new(query);
+        let idx = self.find_exact(|map, idx| map.get_direction_query(idx, query))?;
+        self.table[idx].prefix_value()
+    }
+
+    /// Check if a key matching the query is present in the datastructure. See
+    /// [`Self::contains_key`] and [`PrefixQuery`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// assert!(pm.contains_key_borrowed(&(0xc0a8_0100u32, 24)));
+    /// assert!(!pm.contains_key_borrowed(&(0xc0a8_0000u32, 23)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn contains_key_borrowed<Q>(&self, query: &Q) -> bool
+    where
+        Q: PrefixQuery<P::R> + ?Sized,
+    {
+        self.get_borrowed(query).is_some()
+    }
+
+    /// Get the value of the longest prefix that contains the query. See [`Self::get_lpm`] and
+    /// [`PrefixQuery`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// pm.insert("192.168.0.0/23".parse()?, 2);
+    /// assert_eq!(
+    ///     pm.get_lpm_borrowed(&(0xc0a8_0101u32, 32)),
+    ///     Some((&"192.168.1.0/24".parse()?, &1))
+    /// );
+    /// assert_eq!(pm.get_lpm_borrowed(&(0xc0a8_0201u32, 32)), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_lpm_borrowed<Q>(&self, query: &Q) -> Option<(&P, &T)>
+    where
+        Q: PrefixQuery<P::R> + ?Sized,
+    {
+        let query = Query::new(query);
+        let idx = self.find_lpm(|map, idx| map.get_direction_query(idx, query))?;
+        self.table[idx].prefix_value()
+    }
+
+    /// Remove the element matching the query from the map, returning its value. See
+    /// [`Self::remove`] and [`PrefixQuery`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.1.0/24".parse()?, 1);
+    /// assert_eq!(pm.remove_borrowed(&(0xc0a8_0100u32, 24)), Some(1));
+    /// assert_eq!(pm.get(&"192.168.1.0/24".parse()?), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove_borrowed<Q>(&mut self, query: &Q) -> Option<T>
+    where
+        Q: PrefixQuery<P::R> + ?Sized,
+    {
+        let query = Query::new(query);
+        self.remove_exact(|map, idx| map.get_direction_query(idx, query))
+    }
+}
@@ -0,0 +1,64 @@
+//! Translate the entries of a [`PrefixMap`] into a different address range.
+
+use crate::prefix::last_address;
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Create a new map that contains every entry under `from` (including `from` itself), shifted
+    /// to the corresponding position under `to`. Each translated prefix keeps both its address
+    /// offset relative to the start of `from` and its prefix length. This is useful to map an
+    /// address space onto a different one, e.g., for NAT-style mappings or to replay a scenario in
+    /// documentation address ranges.
+    ///
+    /// If `to` is longer than `from`, entries that do not fit into `to` after the translation are
+    /// skipped.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, 1);
+    /// pm.insert("10.0.0.64/26".parse()?, 2);
+    /// pm.insert("10.0.0.128/25".parse()?, 3);
+    /// pm.insert("172.16.0.0/12".parse()?, 4);
+    /// let rebased = pm.rebase(&"10.0.0.0/24".parse()?, &"192.0.2.0/24".parse()?);
+    /// assert_eq!(
+    ///     rebased.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("192.0.2.0/24".parse()?, 1),
+    ///         ("192.0.2.64/26".parse()?, 2),
+    ///         ("192.0.2.128/25".parse()?, 3),
+    ///     ]
+    /// );
+    /// let rebased = pm.rebase(&"10.0.0.0/24".parse()?, &"192.0.2.0/25".parse()?);
+    /// assert_eq!(
+    ///     rebased.into_iter().collect::<Vec<_>>(),
+    ///     vec![("192.0.2.64/26".parse()?, 2)]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rebase(&self, from: &P, to: &P) -> PrefixMap<P, T>
+    where
+        T: Clone,
+    {
+        let to_size = last_address(to) - to.mask();
+        self.children(from)
+            .filter(|(p, _)| p.prefix_len() >= to.prefix_len())
+            .filter_map(|(p, t)| {
+                let offset = p.mask() - from.mask();
+                (offset <= to_size).then(|| {
+                    (
+                        P::from_repr_len(to.mask() + offset, p.prefix_len()),
+                        t.clone(),
+                    )
+                })
+            })
+            .collect()
+    }
+}
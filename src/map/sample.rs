@@ -0,0 +1,70 @@
+//! Random sampling of entries and covered addresses.
+
+use num_traits::{NumCast, ToPrimitive};
+use rand::seq::IteratorRandom;
+use rand::Rng;
+
+use crate::prefix::CoveredRanges;
+
+use super::*;
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Pick an entry of the map uniformly at random, or return `None` if the map is empty. This
+    /// iterates over all entries of the map, but does not allocate.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("192.168.0.0/16".parse()?, 2);
+    /// let (_, value) = pm.sample(&mut rand::thread_rng()).unwrap();
+    /// assert!(*value == 1 || *value == 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(&P, &T)> {
+        self.iter().choose(rng)
+    }
+
+    /// Pick an address uniformly at random from all addresses that are covered by the entries of
+    /// the map, or return `None` if the map is empty. Hence, each entry is picked with a
+    /// probability proportional to its size, and addresses covered by multiple entries are not
+    /// more likely to be picked. The address is returned as its representation `P::R`.
+    ///
+    /// If the map covers the entire address space of `u128` (e.g., it contains `::/0`), the last
+    /// address is never picked.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use std::net::Ipv4Addr;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, 1);
+    /// pm.insert("10.0.0.0/25".parse()?, 2);
+    /// let addr = Ipv4Addr::from(pm.sample_addr(&mut rand::thread_rng()).unwrap());
+    /// assert!("10.0.0.0/24".parse::<Ipv4Net>()?.contains(&addr));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sample_addr<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<P::R> {
+        let total = self.coverage_size();
+        if total == 0 {
+            return None;
+        }
+        let mut offset = rng.gen_range(0..total);
+        for (start, end) in CoveredRanges::new(self.keys()) {
+            let size = (end - start).to_u128().unwrap().saturating_add(1);
+            if offset < size {
+                return Some(start + <P::R as NumCast>::from(offset).unwrap());
+            }
+            offset -= size;
+        }
+        unreachable!("the offset is smaller than the number of covered addresses")
+    }
+}
@@ -0,0 +1,161 @@
+//! Deterministic assignment of prefixes to shards.
+
+use num_traits::{NumCast, PrimInt, Zero};
+
+use super::*;
+
+/// Compute the shard (in `0..n_shards`) of a prefix. The address space is split into `n_shards`
+/// contiguous ranges of equal size, and each prefix is assigned to the range that contains its
+/// first address. The assignment only depends on the leading bits of the prefix, so it is the same
+/// for any map and can be used to route prefixes to shards without looking at any entries. In
+/// contrast, [`PrefixMap::partition`] balances the shards by their number of entries.
+///
+/// Prefixes that span multiple shards (for instance `0.0.0.0/0`) are assigned to the shard of their
+/// first address. Hence, a longest-prefix match on a single shard may miss such short prefixes.
+///
+/// ```
+/// # use prefix_trie::map::shard_of;
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let p1: Ipv4Net = "10.0.0.0/8".parse()?;
+/// let p2: Ipv4Net = "192.168.0.0/16".parse()?;
+/// let p3: Ipv4Net = "255.255.255.0/24".parse()?;
+/// assert_eq!(shard_of(&p1, 4), 0);
+/// assert_eq!(shard_of(&p2, 4), 3);
+/// assert_eq!(shard_of(&p3, 4), 3);
+/// assert_eq!(shard_of(&p3, 5), 4);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Panics
+/// This function panics if `n_shards` is zero.
+pub fn shard_of<P: Prefix>(prefix: &P, n_shards: usize) -> usize {
+    assert!(n_shards > 0, "The number of shards must be positive");
+    let num_bits = P::R::zero().count_zeros();
+    let addr = <u128 as NumCast>::from(prefix.mask()).unwrap();
+    // take the 64 most significant bits of the address
+    let top = (addr << (128 - num_bits)) >> 64;
+    ((top * n_shards as u128) >> 64) as usize
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Split the map into `n` disjoint maps with (roughly) the same number of entries. Each
+    /// resulting map holds a contiguous range of entries in lexicographic order, so the shards are
+    /// split by leading bits, and shard `i` only contains prefixes that are lexicographically
+    /// smaller than those in shard `i + 1`. The split points are chosen using the number of
+    /// entries in each subtree: A subtree that fits into the current shard is moved there as a
+    /// whole, without inserting each of its entries separately, and it is split into its children
+    /// otherwise. The sizes of any two shards differ by at most one entry.
+    ///
+    /// In contrast to [`shard_of`], the split points depend on the entries of the map. To find
+    /// the shard responsible for a prefix, compare it with the first entry of each shard. A
+    /// prefix and its more-specific prefixes may end up in different shards, so a longest-prefix
+    /// match on a single shard may miss a covering prefix that is stored in a previous shard.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// pm.insert("10.2.0.0/16".parse()?, 3);
+    /// pm.insert("192.168.0.0/16".parse()?, 4);
+    /// let shards = pm.partition(2);
+    /// assert_eq!(
+    ///     shards[0].iter().collect::<Vec<_>>(),
+    ///     vec![(&"10.0.0.0/8".parse()?, &1), (&"10.1.0.0/16".parse()?, &2)],
+    /// );
+    /// assert_eq!(
+    ///     shards[1].iter().collect::<Vec<_>>(),
+    ///     vec![(&"10.2.0.0/16".parse()?, &3), (&"192.168.0.0/16".parse()?, &4)],
+    /// );
+    /// // 10.2.0.0/16 is covered by 10.0.0.0/8, which is stored in the first shard.
+    /// assert_eq!(shards[1].get_lpm(&"10.2.0.0/24".parse()?).map(|(_, t)| *t), Some(3));
+    /// assert_eq!(shards[1].get_lpm(&"10.3.0.0/24".parse()?), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    /// This function panics if `n` is zero.
+    pub fn partition(self, n: usize) -> Vec<PrefixMap<P, T>> {
+        assert!(n > 0, "The number of shards must be positive");
+        let mut counts = vec![0; self.table.len()];
+        let len = self.count_subtree(0, &mut counts);
+        let mut split = Split {
+            counts,
+            shards: (0..n).map(|_| PrefixMap::new()).collect(),
+            shard: 0,
+            taken: 0,
+            len,
+        };
+        let mut table = self.table;
+        split.visit(&mut table, 0);
+        split.shards
+    }
+
+    /// Store the number of entries in the subtree of each node in `counts`, and return the number
+    /// of entries in the subtree of `idx`.
+    fn count_subtree(&self, idx: usize, counts: &mut [usize]) -> usize {
+        let node = &self.table[idx];
+        let count = node.value.is_some() as usize
+            + node.left().map_or(0, |c| self.count_subtree(c, counts))
+            + node.right().map_or(0, |c| self.count_subtree(c, counts));
+        counts[idx] = count;
+        count
+    }
+}
+
+/// State for moving the entries of a map into shards, in lexicographic order.
+struct Split<P, T> {
+    /// The number of entries in the subtree of each node.
+    counts: Vec<usize>,
+    /// The shards that are being built.
+    shards: Vec<PrefixMap<P, T>>,
+    /// The current shard.
+    shard: usize,
+    /// The number of entries moved into the current or any previous shard.
+    taken: usize,
+    /// The number of entries in the map.
+    len: usize,
+}
+
+impl<P: Prefix, T> Split<P, T> {
+    /// The number of entries that should be moved into all shards up to (and including) `shard`.
+    fn bound(&self, shard: usize) -> usize {
+        (shard + 1) * self.len / self.shards.len()
+    }
+
+    /// Move all entries in the subtree of `table[idx]` into the shards, in lexicographic order.
+    fn visit(&mut self, table: &mut [Node<P, T>], idx: usize) {
+        while self.shard + 1 < self.shards.len() && self.taken >= self.bound(self.shard) {
+            self.shard += 1;
+        }
+        let count = self.counts[idx];
+        if count == 0 {
+            return;
+        }
+        if self.taken + count <= self.bound(self.shard) {
+            // the entire subtree fits into the current shard, and the shards are disjoint.
+            self.shards[self.shard].append_subtree(0, table, idx, &mut |_, _, t| t);
+            self.taken += count;
+            return;
+        }
+        if let Some(value) = table[idx].value.take() {
+            let prefix = core::mem::replace(&mut table[idx].prefix, P::zero());
+            self.shards[self.shard].insert(prefix, value);
+            self.taken += 1;
+        }
+        for child in [table[idx].left(), table[idx].right()]
+            .into_iter()
+            .flatten()
+        {
+            self.visit(table, child);
+        }
+    }
+}
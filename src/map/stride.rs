@@ -0,0 +1,118 @@
+//! A multi-bit index that accelerates longest prefix match lookups.
+
+use alloc::vec::Vec;
+
+use num_traits::{NumCast, PrimInt, ToPrimitive, Zero};
+
+use super::*;
+
+/// The largest number of bits that can be resolved by a [`StrideIndex`].
+const MAX_STRIDE: u8 = 24;
+
+/// An index over the first bits of all prefixes in a [`PrefixMap`], which accelerates longest
+/// prefix match lookups. For each combination of the first `stride` bits, the index stores the
+/// deepest node that contains all prefixes starting with these bits, together with the longest
+/// match up to that node. A lookup of a prefix that is at least `stride` bits long starts directly
+/// at that node instead of at the root, skipping the first levels of the tree. Shorter prefixes
+/// are looked up in the map without using the index. Since the index borrows the map, the map
+/// cannot be modified while the index exists. See [`PrefixMap::stride_index`].
+pub struct StrideIndex<'a, P, T> {
+    map: &'a PrefixMap<P, T>,
+    stride: u8,
+    /// The node to start from, and the node of the best match up to that node.
+    slots: Vec<(usize, Option<usize>)>,
+}
+
+impl<'a, P: Prefix, T> StrideIndex<'a, P, T> {
+    fn new(map: &'a PrefixMap<P, T>, stride: u8) -> Self {
+        let bits = P::R::zero().count_zeros() as u8;
+        let stride = stride.min(bits).min(MAX_STRIDE);
+        let slots = (0..1usize << stride)
+            .map(|slot| {
+                let repr = if stride == 0 {
+                    P::R::zero()
+                } else {
+                    <P::R as NumCast>::from(slot).unwrap() << (bits - stride) as usize
+                };
+                let prefix = P::from_repr_len(repr, stride);
+                let mut idx = 0;
+                let mut best_match = map.table[0].value.as_ref().map(|_| 0);
+                while let Direction::Enter { next, .. } = map.get_direction(idx, &prefix) {
+                    idx = next;
+                    if map.table[idx].value.is_some() {
+                        best_match = Some(idx);
+                    }
+                }
+                (idx, best_match)
+            })
+            .collect();
+        Self { map, stride, slots }
+    }
+
+    /// Perform a longest prefix match of `prefix`. The result is the same as
+    /// [`PrefixMap::get_lpm`].
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&'a P, &'a T)> {
+        if prefix.prefix_len() < self.stride {
+            return self.map.get_lpm(prefix);
+        }
+        let bits = P::R::zero().count_zeros() as u8;
+        let slot = if self.stride == 0 {
+            0
+        } else {
+            (prefix.mask() >> (bits - self.stride) as usize)
+                .to_usize()
+                .unwrap()
+        };
+        let table = &self.map.table;
+        let (mut idx, best_match) = self.slots[slot];
+        let mut best_match = best_match.and_then(|i| table[i].prefix_value());
+        loop {
+            best_match = table[idx].prefix_value().or(best_match);
+            match self.map.get_direction(idx, prefix) {
+                Direction::Enter { next, .. } => idx = next,
+                _ => return best_match,
+            }
+        }
+    }
+
+    /// Get the number of bits that are resolved by the index.
+    pub fn stride(&self) -> u8 {
+        self.stride
+    }
+
+    /// Get the map on which the lookups are performed.
+    pub fn map(&self) -> &'a PrefixMap<P, T> {
+        self.map
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Build an index over the first `stride` bits of the map to accelerate longest prefix match
+    /// lookups. The index has `2^stride` slots, and `stride` is limited to the number of bits in
+    /// `P::R` and to 24. Building the index requires one traversal per slot, so it pays off only
+    /// if the map is queried many times without being modified, e.g., for classifying large
+    /// streams of addresses. A `stride` of 16 is a good choice for IPv4 routing tables.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.1.0.0/16".parse()?, 2);
+    /// pm.insert("10.1.1.0/24".parse()?, 3);
+    /// let index = pm.stride_index(12);
+    /// assert_eq!(index.get_lpm(&"10.1.1.1/32".parse()?), Some((&"10.1.1.0/24".parse()?, &3)));
+    /// assert_eq!(index.get_lpm(&"10.1.2.1/32".parse()?), Some((&"10.1.0.0/16".parse()?, &2)));
+    /// assert_eq!(index.get_lpm(&"10.0.0.0/8".parse()?), Some((&"10.0.0.0/8".parse()?, &1)));
+    /// assert_eq!(index.get_lpm(&"11.0.0.0/8".parse()?), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stride_index(&self, stride: u8) -> StrideIndex<'_, P, T> {
+        StrideIndex::new(self, stride)
+    }
+}
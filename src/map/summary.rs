@@ -0,0 +1,181 @@
+//! Summary statistics of a [`PrefixMap`], and of a pair of IPv4 and IPv6 maps.
+
+use alloc::{vec, vec::Vec};
+
+#[cfg(feature = "ipnet")]
+use ipnet::{Ipv4Net, Ipv6Net};
+use num_traits::{PrimInt, Zero};
+
+use super::*;
+
+/// Summary statistics of a [`PrefixMap`]. See [`PrefixMap::summary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    /// The number of entries in the map.
+    pub entries: usize,
+    /// The number of individual addresses covered by the entries, counting overlapping entries
+    /// only once. See [`PrefixMap::coverage_size`].
+    pub covered_addresses: u128,
+    /// The number of entries per prefix length, where index `i` stores the number of entries with
+    /// prefix length `i`. The vector has one element per possible length, including zero.
+    pub length_histogram: Vec<usize>,
+    /// The approximate number of bytes allocated by the map. This does not include memory
+    /// allocated by the values themselves (e.g., the contents of a `Vec`).
+    pub memory_bytes: usize,
+}
+
+/// Summary statistics of a pair of maps, one for each address family. See [`dual_stack_summary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DualStackSummary {
+    /// Statistics of the IPv4 map.
+    pub ipv4: Summary,
+    /// Statistics of the IPv6 map.
+    pub ipv6: Summary,
+}
+
+/// Statistics about the tree structure and the memory of a [`PrefixMap`]. See
+/// [`PrefixMap::stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of nodes that store a value, i.e., the number of entries in the map.
+    pub value_nodes: usize,
+    /// The number of nodes without a value that are part of the tree, including the root.
+    pub branch_nodes: usize,
+    /// The number of slots in the node table, including free slots.
+    pub slots: usize,
+    /// The number of slots that are not part of the tree and are free to be reused. Use
+    /// [`PrefixMap::compact`] to release them.
+    pub free_slots: usize,
+    /// The approximate number of bytes allocated by the map. This does not include memory
+    /// allocated by the values themselves (e.g., the contents of a `Vec`).
+    pub memory_bytes: usize,
+    /// The largest number of edges on a path from the root to any node.
+    pub max_depth: usize,
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Compute the summary statistics of the map in a single call. This iterates over all
+    /// entries of the map.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, 1);
+    /// pm.insert("10.0.0.0/25".parse()?, 2);
+    /// pm.insert("10.0.1.0/24".parse()?, 3);
+    /// let summary = pm.summary();
+    /// assert_eq!(summary.entries, 3);
+    /// assert_eq!(summary.covered_addresses, 512);
+    /// assert_eq!(summary.length_histogram.len(), 33);
+    /// assert_eq!(summary.length_histogram[24], 2);
+    /// assert_eq!(summary.length_histogram[25], 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn summary(&self) -> Summary {
+        let max_len = P::R::zero().count_zeros() as usize;
+        let mut length_histogram = vec![0; max_len + 1];
+        let mut entries = 0;
+        for p in self.keys() {
+            length_histogram[p.prefix_len() as usize] += 1;
+            entries += 1;
+        }
+        Summary {
+            entries,
+            covered_addresses: self.coverage_size(),
+            length_histogram,
+            memory_bytes: self.memory_bytes(),
+        }
+    }
+
+    /// Compute statistics about the tree structure and the memory of the map. This traverses all
+    /// nodes of the tree, but does not look at the values.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/24".parse()?, 1);
+    /// pm.insert("10.0.1.0/24".parse()?, 2);
+    /// pm.remove(&"10.0.1.0/24".parse()?);
+    /// let stats = pm.stats();
+    /// assert_eq!(stats.value_nodes, 1);
+    /// assert_eq!(stats.branch_nodes, 1);
+    /// assert_eq!(stats.free_slots, 2);
+    /// assert_eq!(stats.slots, 4);
+    /// assert_eq!(stats.max_depth, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&self) -> Stats {
+        let mut value_nodes = 0;
+        let mut branch_nodes = 0;
+        let mut max_depth = 0;
+        let mut stack = vec![(0, 0)];
+        while let Some((idx, depth)) = stack.pop() {
+            let node = &self.table[idx];
+            if node.value.is_some() {
+                value_nodes += 1;
+            } else {
+                branch_nodes += 1;
+            }
+            max_depth = max_depth.max(depth);
+            stack.extend(node.left().map(|c| (c, depth + 1)));
+            stack.extend(node.right().map(|c| (c, depth + 1)));
+        }
+        Stats {
+            value_nodes,
+            branch_nodes,
+            slots: self.table.len(),
+            free_slots: self.table.len() - value_nodes - branch_nodes,
+            memory_bytes: self.memory_bytes(),
+            max_depth,
+        }
+    }
+
+    /// The approximate number of bytes allocated by the map, without the memory allocated by the
+    /// values themselves.
+    fn memory_bytes(&self) -> usize {
+        core::mem::size_of::<Self>()
+            + self.table.capacity() * core::mem::size_of::<Node<P, T>>()
+            + self.free.capacity() * core::mem::size_of::<usize>()
+    }
+}
+
+/// Compute the summary statistics of an IPv4 and an IPv6 map in a single call. See
+/// [`PrefixMap::summary`].
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::dual_stack_summary;
+/// # use ipnet::{Ipv4Net, Ipv6Net};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut v4: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+/// v4.insert("10.0.0.0/24".parse()?, 1);
+/// let mut v6: PrefixMap<Ipv6Net, _> = PrefixMap::new();
+/// v6.insert("2001:db8::/32".parse()?, 2);
+/// v6.insert("2001:db8::/48".parse()?, 3);
+/// let summary = dual_stack_summary(&v4, &v6);
+/// assert_eq!(summary.ipv4.entries, 1);
+/// assert_eq!(summary.ipv6.entries, 2);
+/// assert_eq!(summary.ipv6.covered_addresses, 1 << 96);
+/// assert_eq!(summary.ipv6.length_histogram[48], 1);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "ipnet")]
+pub fn dual_stack_summary<T, U>(
+    ipv4: &PrefixMap<Ipv4Net, T>,
+    ipv6: &PrefixMap<Ipv6Net, U>,
+) -> DualStackSummary {
+    DualStackSummary {
+        ipv4: ipv4.summary(),
+        ipv6: ipv6.summary(),
+    }
+}
@@ -0,0 +1,151 @@
+//! Import and export of maps in a plain-text format with one `prefix<TAB>value` per line.
+
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use super::*;
+
+/// The error returned by [`PrefixMap::from_reader`].
+#[derive(Debug)]
+pub enum FromReaderError<PE, TE> {
+    /// Reading from the underlying reader failed.
+    Io(std::io::Error),
+    /// The line does not contain a tab that separates the prefix from the value.
+    MissingValue {
+        /// The line number (starting at 1).
+        line: usize,
+    },
+    /// The prefix could not be parsed.
+    Prefix {
+        /// The line number (starting at 1).
+        line: usize,
+        /// The error returned when parsing the prefix.
+        error: PE,
+    },
+    /// The value could not be parsed.
+    Value {
+        /// The line number (starting at 1).
+        line: usize,
+        /// The error returned when parsing the value.
+        error: TE,
+    },
+}
+
+impl<PE: core::fmt::Display, TE: core::fmt::Display> core::fmt::Display
+    for FromReaderError<PE, TE>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FromReaderError::Io(e) => write!(f, "Cannot read the input: {e}"),
+            FromReaderError::MissingValue { line } => {
+                write!(f, "Line {line}: missing tab-separated value")
+            }
+            FromReaderError::Prefix { line, error } => {
+                write!(f, "Line {line}: invalid prefix: {error}")
+            }
+            FromReaderError::Value { line, error } => {
+                write!(f, "Line {line}: invalid value: {error}")
+            }
+        }
+    }
+}
+
+impl<PE, TE> std::error::Error for FromReaderError<PE, TE>
+where
+    PE: core::fmt::Debug + core::fmt::Display,
+    TE: core::fmt::Debug + core::fmt::Display,
+{
+}
+
+impl<PE, TE> From<std::io::Error> for FromReaderError<PE, TE> {
+    fn from(e: std::io::Error) -> Self {
+        FromReaderError::Io(e)
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Write all entries into `writer`, one `prefix<TAB>value` per line in lexicographic order.
+    /// Both the prefix and the value are formatted using [`Display`](core::fmt::Display). The
+    /// output can be read back with [`PrefixMap::from_reader`], as long as the formatted values
+    /// contain neither a tab nor a line break. Nothing is escaped, so such values do not round-trip.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("192.168.0.0/16".parse()?, 65001);
+    /// pm.insert("10.0.0.0/8".parse()?, 65000);
+    /// let mut out = Vec::new();
+    /// pm.to_writer(&mut out)?;
+    /// assert_eq!(out, b"10.0.0.0/8\t65000\n192.168.0.0/16\t65001\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_writer<W>(&self, mut writer: W) -> std::io::Result<()>
+    where
+        W: Write,
+        P: core::fmt::Display,
+        T: core::fmt::Display,
+    {
+        for (p, t) in self.iter() {
+            writeln!(writer, "{p}\t{t}")?;
+        }
+        Ok(())
+    }
+
+    /// Read a map from `reader`, which contains one `prefix<TAB>value` per line. Both the prefix
+    /// and the value are parsed using [`FromStr`]. Empty lines are skipped, and if a prefix
+    /// appears multiple times, the last value is kept. Parsing stops at the first invalid line.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::FromReaderError;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let input = "10.0.0.0/8\t65000\n\n192.168.0.0/16\t65001\n";
+    /// let pm: PrefixMap<Ipv4Net, u32> = PrefixMap::from_reader(input.as_bytes())?;
+    /// assert_eq!(
+    ///     pm.into_iter().collect::<Vec<_>>(),
+    ///     vec![("10.0.0.0/8".parse()?, 65000), ("192.168.0.0/16".parse()?, 65001)]
+    /// );
+    ///
+    /// let input = "10.0.0.0/8\t65000\n192.168.0.0/16\n";
+    /// let res: Result<PrefixMap<Ipv4Net, u32>, _> = PrefixMap::from_reader(input.as_bytes());
+    /// assert!(matches!(res, Err(FromReaderError::MissingValue { line: 2 })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_reader<R>(reader: R) -> Result<Self, FromReaderError<P::Err, T::Err>>
+    where
+        R: BufRead,
+        P: FromStr,
+        T: FromStr,
+    {
+        let mut map = PrefixMap::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+            let line_nr = i + 1;
+            let (p, t) = line
+                .split_once('\t')
+                .ok_or(FromReaderError::MissingValue { line: line_nr })?;
+            let p = p.parse().map_err(|error| FromReaderError::Prefix {
+                line: line_nr,
+                error,
+            })?;
+            let t = t.parse().map_err(|error| FromReaderError::Value {
+                line: line_nr,
+                error,
+            })?;
+            map.insert(p, t);
+        }
+        Ok(map)
+    }
+}
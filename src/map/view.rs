@@ -0,0 +1,298 @@
+//! Views on a subtree of a map.
+
+use super::*;
+
+/// A read-only view on all entries of a [`PrefixMap`] that are contained within a prefix. All
+/// lookups and iterators of the view are restricted to that subtree. See [`PrefixMap::view_at`].
+pub struct TrieView<'a, P, T> {
+    map: &'a PrefixMap<P, T>,
+    /// The root of the subtree, or `None` if the view is empty.
+    root: Option<usize>,
+}
+
+/// A mutable view on all entries of a [`PrefixMap`] that are contained within a prefix. Lookups
+/// are restricted to that subtree, and only prefixes within the view can be inserted or removed.
+/// See [`PrefixMap::view_at_mut`].
+pub struct TrieViewMut<'a, P, T> {
+    map: &'a mut PrefixMap<P, T>,
+    prefix: P,
+}
+
+/// The error returned by [`TrieViewMut::insert`] if the prefix is not contained within the view.
+/// It contains the prefix and value that were not inserted.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OutOfViewError<P, T> {
+    /// The root prefix of the view.
+    pub view: P,
+    /// The prefix that was not inserted.
+    pub prefix: P,
+    /// The value that was not inserted.
+    pub value: T,
+}
+
+impl<P: core::fmt::Display, T> core::fmt::Display for OutOfViewError<P, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Prefix {} is outside of the view at {}",
+            self.prefix, self.view
+        )
+    }
+}
+
+impl<P, T> core::error::Error for OutOfViewError<P, T>
+where
+    P: core::fmt::Debug + core::fmt::Display,
+    T: core::fmt::Debug,
+{
+}
+
+impl<P, T> Clone for TrieView<'_, P, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P, T> Copy for TrieView<'_, P, T> {}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Get a read-only view on all entries that are contained within `prefix` (including
+    /// `prefix` itself). Looking up a prefix outside of the view always returns `None`, even if
+    /// the map contains a matching entry elsewhere.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("0.0.0.0/0".parse()?, 0);
+    /// pm.insert("10.0.0.0/16".parse()?, 1);
+    /// pm.insert("10.0.1.0/24".parse()?, 2);
+    /// pm.insert("10.1.0.0/16".parse()?, 3);
+    /// let view = pm.view_at(&"10.0.0.0/16".parse()?);
+    /// assert_eq!(view.len(), 2);
+    /// assert_eq!(view.get(&"10.0.1.0/24".parse()?), Some(&2));
+    /// assert_eq!(view.get(&"10.1.0.0/16".parse()?), None);
+    /// assert_eq!(view.get_lpm(&"10.0.1.1/32".parse()?), Some((&"10.0.1.0/24".parse()?, &2)));
+    /// assert_eq!(view.get_lpm(&"10.0.2.1/32".parse()?), Some((&"10.0.0.0/16".parse()?, &1)));
+    /// assert_eq!(view.get_lpm(&"10.1.0.1/32".parse()?), None);
+    /// assert_eq!(
+    ///     view.iter().map(|(_, t)| *t).collect::<Vec<_>>(),
+    ///     vec![1, 2],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn view_at(&self, prefix: &P) -> TrieView<'_, P, T> {
+        TrieView {
+            map: self,
+            root: self.children_root(prefix),
+        }
+    }
+
+    /// Get a mutable view on all entries that are contained within `prefix` (including `prefix`
+    /// itself). Looking up a prefix outside of the view always returns `None`, and inserting a
+    /// prefix outside of the view is rejected with an [`OutOfViewError`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::map::OutOfViewError;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("0.0.0.0/0".parse()?, 0);
+    /// pm.insert("10.0.0.0/16".parse()?, 1);
+    /// let mut view = pm.view_at_mut(&"10.0.0.0/16".parse()?);
+    /// assert_eq!(view.insert("10.0.1.0/24".parse()?, 2), Ok(None));
+    /// assert_eq!(
+    ///     view.insert("10.1.0.0/16".parse()?, 3),
+    ///     Err(OutOfViewError {
+    ///         view: "10.0.0.0/16".parse()?,
+    ///         prefix: "10.1.0.0/16".parse()?,
+    ///         value: 3,
+    ///     })
+    /// );
+    /// *view.get_mut(&"10.0.0.0/16".parse()?).unwrap() += 10;
+    /// assert_eq!(view.remove(&"0.0.0.0/0".parse()?), None);
+    /// assert_eq!(
+    ///     pm.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("0.0.0.0/0".parse()?, 0),
+    ///         ("10.0.0.0/16".parse()?, 11),
+    ///         ("10.0.1.0/24".parse()?, 2),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn view_at_mut(&mut self, prefix: &P) -> TrieViewMut<'_, P, T>
+    where
+        P: Clone,
+    {
+        TrieViewMut {
+            map: self,
+            prefix: prefix.clone(),
+        }
+    }
+}
+
+impl<'a, P, T> TrieView<'a, P, T>
+where
+    P: Prefix,
+{
+    /// Find the node of `prefix` within the view.
+    fn find(&self, prefix: &P) -> Option<usize> {
+        let mut idx = self.root?;
+        if !self.map.table[idx].prefix.contains(prefix) {
+            return None;
+        }
+        loop {
+            match self.map.get_direction(idx, prefix) {
+                Direction::Reached => return Some(idx),
+                Direction::Enter { next, .. } => idx = next,
+                Direction::Missing => return None,
+            }
+        }
+    }
+
+    /// Get the value of an element in the view by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&'a T> {
+        let idx = self.find(prefix)?;
+        self.map.table[idx].value.as_ref()
+    }
+
+    /// Get the key and the value of an element in the view by matching exactly on the prefix.
+    pub fn get_key_value(&self, prefix: &P) -> Option<(&'a P, &'a T)> {
+        let idx = self.find(prefix)?;
+        self.map.table[idx].prefix_value()
+    }
+
+    /// Check if a key is present in the view.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get(prefix).is_some()
+    }
+
+    /// Get the longest matching entry of `prefix` within the view. Entries outside of the view
+    /// that contain `prefix` are ignored.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&'a P, &'a T)> {
+        let mut idx = self.root?;
+        if !self.map.table[idx].prefix.contains(prefix) {
+            return None;
+        }
+        let mut best_match = None;
+        loop {
+            best_match = self.map.table[idx].prefix_value().or(best_match);
+            match self.map.get_direction(idx, prefix) {
+                Direction::Enter { next, .. } => idx = next,
+                _ => return best_match,
+            }
+        }
+    }
+
+    /// Iterate over all entries of the view in lexicographic order.
+    pub fn iter(&self) -> Iter<'a, P, T> {
+        self.map.iter_subtree(self.root)
+    }
+
+    /// Get the number of entries in the view. This operation is `O(n)` for `n` nodes in the view.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Check if the view contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+impl<'a, P: Prefix, T> IntoIterator for TrieView<'a, P, T> {
+    type Item = (&'a P, &'a T);
+    type IntoIter = Iter<'a, P, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, P, T> TrieViewMut<'a, P, T>
+where
+    P: Prefix,
+{
+    /// Get the root prefix of the view.
+    pub fn prefix(&self) -> &P {
+        &self.prefix
+    }
+
+    /// Get a read-only view on the same subtree.
+    pub fn as_view(&self) -> TrieView<'_, P, T> {
+        self.map.view_at(&self.prefix)
+    }
+
+    /// Get the value of an element in the view by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        self.prefix
+            .contains(prefix)
+            .then(|| self.map.get(prefix))
+            .flatten()
+    }
+
+    /// Get a mutable reference to the value of an element in the view by matching exactly on the
+    /// prefix.
+    pub fn get_mut(&mut self, prefix: &P) -> Option<&mut T> {
+        if self.prefix.contains(prefix) {
+            self.map.get_mut(prefix)
+        } else {
+            None
+        }
+    }
+
+    /// Check if a key is present in the view.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get(prefix).is_some()
+    }
+
+    /// Get the longest matching entry of `prefix` within the view. Entries outside of the view
+    /// that contain `prefix` are ignored.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        // any entry of the view that contains `prefix` is longer than all entries outside of it.
+        self.map
+            .get_lpm(prefix)
+            .filter(|(p, _)| self.prefix.contains(p))
+    }
+
+    /// Insert a new item into the view, returning the value that was stored before. If `prefix`
+    /// is not contained within the view, the map is not modified, and an [`OutOfViewError`] is
+    /// returned.
+    pub fn insert(&mut self, prefix: P, value: T) -> Result<Option<T>, OutOfViewError<P, T>>
+    where
+        P: Clone,
+    {
+        if self.prefix.contains(&prefix) {
+            Ok(self.map.insert(prefix, value))
+        } else {
+            Err(OutOfViewError {
+                view: self.prefix.clone(),
+                prefix,
+                value,
+            })
+        }
+    }
+
+    /// Remove an element from the view, returning its value. Nothing is removed if `prefix` is
+    /// not contained within the view.
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        if self.prefix.contains(prefix) {
+            self.map.remove(prefix)
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over all entries of the view in lexicographic order.
+    pub fn iter(&self) -> Iter<'_, P, T> {
+        self.map.children(&self.prefix)
+    }
+}
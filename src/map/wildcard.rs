@@ -0,0 +1,130 @@
+//! Queries with non-contiguous (wildcard) masks.
+
+use core::marker::PhantomData;
+
+use num_traits::{One, PrimInt, Zero};
+
+use crate::prefix::mask_from_prefix_len;
+
+use super::*;
+
+/// Iterator over the smallest set of prefixes that together contain exactly those addresses that
+/// match an address under an arbitrary (possibly non-contiguous) mask. The prefixes are yielded in
+/// lexicographic order.
+///
+/// The `mask` has a bit set for every bit of the address that must match, like a regular netmask.
+/// To use an inverse (Cisco-style) wildcard mask, pass `!wildcard` instead. The number of
+/// generated prefixes is `2^k`, where `k` is the number of unset bits in `mask` that are followed
+/// by a set bit.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::map::WildcardPrefixes;
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // match 10.x.0.0/16 for any value of x
+/// let prefixes: Vec<Ipv4Net> = WildcardPrefixes::new(0x0a00_0000, 0xff00_ff00).collect();
+/// assert_eq!(prefixes.len(), 256);
+/// assert_eq!(prefixes[0], "10.0.0.0/24".parse()?);
+/// assert_eq!(prefixes[1], "10.1.0.0/24".parse()?);
+/// assert_eq!(prefixes[255], "10.255.0.0/24".parse()?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct WildcardPrefixes<P: Prefix> {
+    base: P::R,
+    free: P::R,
+    len: u8,
+    /// The next subset of `free` to yield, or `None` if all subsets were yielded.
+    next: Option<P::R>,
+    _prefix: PhantomData<P>,
+}
+
+impl<P: Prefix> WildcardPrefixes<P> {
+    /// Expand the `addr` under the `mask` into prefixes. Bits of `addr` that are not set in `mask`
+    /// are ignored.
+    pub fn new(addr: P::R, mask: P::R) -> Self {
+        let len = (mask.count_zeros() + mask.count_ones() - mask.trailing_zeros()) as u8;
+        let prefix_mask: P::R = mask_from_prefix_len(len);
+        Self {
+            base: addr & mask,
+            free: prefix_mask & !mask,
+            len,
+            next: Some(P::R::zero()),
+            _prefix: PhantomData,
+        }
+    }
+}
+
+impl<P: Prefix> Iterator for WildcardPrefixes<P> {
+    type Item = P;
+
+    fn next(&mut self) -> Option<P> {
+        let sub = self.next?;
+        // enumerate all subsets of `free` in increasing order.
+        self.next = (sub != self.free).then(|| ((sub | !self.free) + P::R::one()) & self.free);
+        Some(P::from_repr_len(self.base | sub, self.len))
+    }
+}
+
+impl<P, T> PrefixMap<P, T>
+where
+    P: Prefix,
+{
+    /// Check if any prefix in the map contains an address that matches `addr` under the
+    /// (possibly non-contiguous) `mask`, i.e., if any entry overlaps with a prefix generated by
+    /// [`WildcardPrefixes`]. See [`WildcardPrefixes`] for the semantics of `mask`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.3.0.0/16".parse()?, 1);
+    /// pm.insert("192.168.1.0/24".parse()?, 2);
+    /// // 10.x.1.0 where x is even
+    /// assert!(!pm.contains_wildcard(0x0a00_0100, 0xff01_ffff));
+    /// // 10.x.1.0 where x is odd
+    /// assert!(pm.contains_wildcard(0x0a01_0100, 0xff01_ffff));
+    /// // 192.168.x.0/24 where x is odd
+    /// assert!(pm.contains_wildcard(0xc0a8_0100, 0xffff_0100));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn contains_wildcard(&self, addr: P::R, mask: P::R) -> bool {
+        WildcardPrefixes::new(addr, mask)
+            .any(|p| self.get_lpm(&p).is_some() || self.children(&p).next().is_some())
+    }
+
+    /// Perform a longest prefix match for each prefix generated by [`WildcardPrefixes`] from
+    /// `addr` and `mask`. The iterator yields the generated prefixes in lexicographic order,
+    /// together with the value of their longest prefix match (or `None`).
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pm: PrefixMap<Ipv4Net, _> = PrefixMap::new();
+    /// pm.insert("10.0.0.0/8".parse()?, 1);
+    /// pm.insert("10.2.0.0/16".parse()?, 2);
+    /// // 10.x.0.1 where x is between 0 and 3
+    /// assert_eq!(
+    ///     pm.lpm_wildcard(0x0a00_0001, 0xfffc_ffff).collect::<Vec<_>>(),
+    ///     vec![
+    ///         ("10.0.0.1/32".parse()?, Some(&1)),
+    ///         ("10.1.0.1/32".parse()?, Some(&1)),
+    ///         ("10.2.0.1/32".parse()?, Some(&2)),
+    ///         ("10.3.0.1/32".parse()?, Some(&1)),
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lpm_wildcard(
+        &self,
+        addr: P::R,
+        mask: P::R,
+    ) -> LpmAddresses<'_, P, T, WildcardPrefixes<P>> {
+        self.lpm_all_addresses_of(WildcardPrefixes::new(addr, mask))
+    }
+}
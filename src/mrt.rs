@@ -0,0 +1,237 @@
+//! Loader for MRT `TABLE_DUMP_V2` RIB dumps (RFC 6396).
+//!
+//! [`load_rib`] reads a RIB dump, as published by route collectors like RIPE RIS or RouteViews,
+//! and stores all RIB entries of each prefix in a [`PrefixMap`]. IPv4 and IPv6 prefixes are stored
+//! in separate maps. The BGP path attributes of each entry are kept in their raw wire format.
+//! Records of other types or subtypes (e.g., multicast RIBs or BGP4MP messages) are skipped.
+//! Compressed dumps must be decompressed before they are passed to the loader.
+
+use std::io::{ErrorKind, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use ipnet::{Ipv4Net, Ipv6Net};
+
+use crate::PrefixMap;
+
+pub(crate) const TABLE_DUMP_V2: u16 = 13;
+pub(crate) const PEER_INDEX_TABLE: u16 = 1;
+pub(crate) const RIB_IPV4_UNICAST: u16 = 2;
+pub(crate) const RIB_IPV6_UNICAST: u16 = 4;
+const RIB_IPV4_UNICAST_ADDPATH: u16 = 8;
+const RIB_IPV6_UNICAST_ADDPATH: u16 = 10;
+
+/// The error returned by [`load_rib`].
+#[derive(Debug)]
+pub enum MrtError {
+    /// Reading from the underlying reader failed.
+    Io(std::io::Error),
+    /// The input is not a valid MRT file. The message describes the problem.
+    Malformed(&'static str),
+}
+
+impl core::fmt::Display for MrtError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MrtError::Io(e) => write!(f, "Cannot read the MRT file: {e}"),
+            MrtError::Malformed(msg) => write!(f, "Malformed MRT file: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for MrtError {}
+
+impl From<std::io::Error> for MrtError {
+    fn from(e: std::io::Error) -> Self {
+        MrtError::Io(e)
+    }
+}
+
+/// A peer of the route collector, as listed in the `PEER_INDEX_TABLE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peer {
+    /// The BGP identifier of the peer.
+    pub bgp_id: Ipv4Addr,
+    /// The IP address of the peer.
+    pub addr: IpAddr,
+    /// The AS number of the peer.
+    pub asn: u32,
+}
+
+/// A single route for a prefix, as received from one peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RibEntry {
+    /// The index of the peer in [`Rib::peers`].
+    pub peer_index: u16,
+    /// The time (in seconds since the UNIX epoch) at which the route was received.
+    pub originated_time: u32,
+    /// The path identifier, if the dump uses the `ADDPATH` subtypes (RFC 8050).
+    pub path_id: Option<u32>,
+    /// The BGP path attributes in their wire format.
+    pub attributes: Vec<u8>,
+}
+
+/// The content of a RIB dump.
+#[derive(Debug, Clone, Default)]
+pub struct Rib {
+    /// All peers of the `PEER_INDEX_TABLE`. Entries refer to them using [`RibEntry::peer_index`].
+    pub peers: Vec<Peer>,
+    /// All RIB entries for IPv4 unicast prefixes.
+    pub ipv4: PrefixMap<Ipv4Net, Vec<RibEntry>>,
+    /// All RIB entries for IPv6 unicast prefixes.
+    pub ipv6: PrefixMap<Ipv6Net, Vec<RibEntry>>,
+}
+
+/// Read an MRT `TABLE_DUMP_V2` RIB dump from `reader`. Entries of the same prefix (even if they are
+/// spread over multiple records) are collected into the same vector, in the order they appear in
+/// the dump.
+///
+/// ```
+/// # use prefix_trie::mrt::load_rib;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // an empty dump contains no peers and no entries.
+/// let rib = load_rib(&[][..])?;
+/// assert!(rib.peers.is_empty());
+/// assert_eq!(rib.ipv4.iter().count(), 0);
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_rib<R: Read>(mut reader: R) -> Result<Rib, MrtError> {
+    let mut rib = Rib::default();
+    let mut header = [0; 12];
+    let mut body = Vec::new();
+    while read_header(&mut reader, &mut header)? {
+        let kind = u16::from_be_bytes([header[4], header[5]]);
+        let subtype = u16::from_be_bytes([header[6], header[7]]);
+        let len = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+        body.clear();
+        (&mut reader).take(len as u64).read_to_end(&mut body)?;
+        if body.len() != len as usize {
+            return Err(MrtError::Malformed("truncated record"));
+        }
+        if kind != TABLE_DUMP_V2 {
+            continue;
+        }
+        let mut buf = Buf(&body);
+        match subtype {
+            PEER_INDEX_TABLE => rib.peers = parse_peers(&mut buf)?,
+            RIB_IPV4_UNICAST | RIB_IPV4_UNICAST_ADDPATH => {
+                let (len, addr) = buf.prefix::<4>(32)?;
+                let prefix = Ipv4Net::new(Ipv4Addr::from(addr), len).unwrap().trunc();
+                let entries = parse_entries(&mut buf, subtype == RIB_IPV4_UNICAST_ADDPATH)?;
+                rib.ipv4.entry(prefix).or_default().extend(entries);
+            }
+            RIB_IPV6_UNICAST | RIB_IPV6_UNICAST_ADDPATH => {
+                let (len, addr) = buf.prefix::<16>(128)?;
+                let prefix = Ipv6Net::new(Ipv6Addr::from(addr), len).unwrap().trunc();
+                let entries = parse_entries(&mut buf, subtype == RIB_IPV6_UNICAST_ADDPATH)?;
+                rib.ipv6.entry(prefix).or_default().extend(entries);
+            }
+            _ => {}
+        }
+    }
+    Ok(rib)
+}
+
+/// Read the common header of the next record. Returns `false` if the reader is at its end.
+fn read_header<R: Read>(reader: &mut R, header: &mut [u8; 12]) -> Result<bool, MrtError> {
+    let mut read = 0;
+    while read < header.len() {
+        match reader.read(&mut header[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => return Err(MrtError::Malformed("truncated header")),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
+
+/// Parse the body of a `PEER_INDEX_TABLE` record.
+fn parse_peers(buf: &mut Buf<'_>) -> Result<Vec<Peer>, MrtError> {
+    let _collector_id = buf.bytes::<4>()?;
+    let view_name_len = buf.u16()?;
+    buf.skip(view_name_len as usize)?;
+    let count = buf.u16()?;
+    let mut peers = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let peer_type = buf.bytes::<1>()?[0];
+        let bgp_id = Ipv4Addr::from(buf.bytes::<4>()?);
+        let addr = if peer_type & 0x01 != 0 {
+            IpAddr::V6(Ipv6Addr::from(buf.bytes::<16>()?))
+        } else {
+            IpAddr::V4(Ipv4Addr::from(buf.bytes::<4>()?))
+        };
+        let asn = if peer_type & 0x02 != 0 {
+            buf.u32()?
+        } else {
+            buf.u16()? as u32
+        };
+        peers.push(Peer { bgp_id, addr, asn });
+    }
+    Ok(peers)
+}
+
+/// Parse the RIB entries of a `RIB_*` record, after the prefix.
+fn parse_entries(buf: &mut Buf<'_>, addpath: bool) -> Result<Vec<RibEntry>, MrtError> {
+    let count = buf.u16()?;
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let peer_index = buf.u16()?;
+        let originated_time = buf.u32()?;
+        let path_id = if addpath { Some(buf.u32()?) } else { None };
+        let attr_len = buf.u16()?;
+        let attributes = buf.take(attr_len as usize)?.to_vec();
+        entries.push(RibEntry {
+            peer_index,
+            originated_time,
+            path_id,
+            attributes,
+        });
+    }
+    Ok(entries)
+}
+
+/// A cursor over the body of a record.
+struct Buf<'a>(&'a [u8]);
+
+impl<'a> Buf<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], MrtError> {
+        if self.0.len() < n {
+            return Err(MrtError::Malformed("record too short"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn skip(&mut self, n: usize) -> Result<(), MrtError> {
+        self.take(n).map(|_| ())
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], MrtError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u16(&mut self) -> Result<u16, MrtError> {
+        self.bytes().map(u16::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, MrtError> {
+        self.bytes().map(u32::from_be_bytes)
+    }
+
+    /// Parse the sequence number, followed by a prefix length and the significant bytes of the
+    /// prefix.
+    fn prefix<const N: usize>(&mut self, max_len: u8) -> Result<(u8, [u8; N]), MrtError> {
+        let _sequence = self.u32()?;
+        let len = self.bytes::<1>()?[0];
+        if len > max_len {
+            return Err(MrtError::Malformed("invalid prefix length"));
+        }
+        let mut addr = [0; N];
+        let n = (len as usize).div_ceil(8);
+        addr[..n].copy_from_slice(self.take(n)?);
+        Ok((len, addr))
+    }
+}
@@ -0,0 +1,387 @@
+//! A persistent prefix map, where every modification creates a new version of the map.
+//!
+//! A [`PersistentPrefixMap`] is immutable. Instead of modifying the map in place, [`insert`] and
+//! [`remove`] return a new map, and the original map remains unchanged. Both maps share all
+//! subtrees that were not modified, so creating a new version only copies the nodes on the path
+//! from the root to the modified prefix, and cloning a map is `O(1)`. This makes it cheap to keep
+//! many versions of a map at the same time, e.g., historical snapshots of a routing table.
+//!
+//! The map can also be modified in place using [`insert_mut`] and [`remove_mut`], which only copy
+//! the nodes that are shared with other versions (copy-on-write). Together with [`snapshot`], this
+//! allows taking consistent point-in-time snapshots that can be read while the map continues to
+//! be modified.
+//!
+//! In contrast to [`PrefixMap`], nodes are stored individually behind an [`Arc`], so lookups are
+//! slower. Prefixes and values are cloned when the nodes that store them are copied.
+//!
+//! Snapshots are only available on a [`PersistentPrefixMap`], not on a [`PrefixMap`]. A
+//! [`PrefixMap`] stores all nodes in a single vector and links them by their index, so two
+//! versions cannot share individual subtrees. Sharing the vector itself would require copying all
+//! nodes on the first modification after a snapshot, and storing each node behind a reference
+//! count would slow down every operation on a [`PrefixMap`]. A [`PrefixMap`] can be converted
+//! into a [`PersistentPrefixMap`] using [`From`].
+//!
+//! [`insert`]: PersistentPrefixMap::insert
+//! [`remove`]: PersistentPrefixMap::remove
+//! [`insert_mut`]: PersistentPrefixMap::insert_mut
+//! [`remove_mut`]: PersistentPrefixMap::remove_mut
+//! [`snapshot`]: PersistentPrefixMap::snapshot
+
+use alloc::{sync::Arc, vec, vec::Vec};
+
+use crate::{to_right, Prefix, PrefixMap};
+
+/// A node of a [`PersistentPrefixMap`], which may be shared between multiple versions.
+struct Node<P, T> {
+    prefix: P,
+    value: Option<T>,
+    left: Option<Arc<Node<P, T>>>,
+    right: Option<Arc<Node<P, T>>>,
+}
+
+impl<P: Clone, T: Clone> Clone for Node<P, T> {
+    /// Copy the node, sharing both children.
+    fn clone(&self) -> Self {
+        Self {
+            prefix: self.prefix.clone(),
+            value: self.value.clone(),
+            left: self.left.clone(),
+            right: self.right.clone(),
+        }
+    }
+}
+
+impl<P, T> Node<P, T> {
+    fn leaf(prefix: P, value: Option<T>) -> Self {
+        Self {
+            prefix,
+            value,
+            left: None,
+            right: None,
+        }
+    }
+
+    fn child(&self, right: bool) -> Option<&Arc<Node<P, T>>> {
+        if right {
+            self.right.as_ref()
+        } else {
+            self.left.as_ref()
+        }
+    }
+
+    fn child_mut(&mut self, right: bool) -> &mut Option<Arc<Node<P, T>>> {
+        if right {
+            &mut self.right
+        } else {
+            &mut self.left
+        }
+    }
+}
+
+/// A persistent prefix map with structural sharing. See the [module documentation](self).
+///
+/// ```
+/// # use prefix_trie::persistent::PersistentPrefixMap;
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let v1: PersistentPrefixMap<Ipv4Net, u32> = PersistentPrefixMap::new();
+/// let v2 = v1.insert("10.0.0.0/8".parse()?, 1);
+/// let v3 = v2.insert("10.1.0.0/16".parse()?, 2);
+/// let v4 = v3.remove(&"10.0.0.0/8".parse()?);
+/// assert_eq!(v1.len(), 0);
+/// assert_eq!(v2.get_lpm(&"10.1.1.1/32".parse()?), Some((&"10.0.0.0/8".parse()?, &1)));
+/// assert_eq!(v3.get_lpm(&"10.1.1.1/32".parse()?), Some((&"10.1.0.0/16".parse()?, &2)));
+/// assert_eq!(v4.get_lpm(&"10.2.1.1/32".parse()?), None);
+/// # Ok(())
+/// # }
+/// ```
+pub struct PersistentPrefixMap<P, T> {
+    root: Arc<Node<P, T>>,
+    len: usize,
+}
+
+impl<P, T> Clone for PersistentPrefixMap<P, T> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<P: Prefix, T> Default for PersistentPrefixMap<P, T> {
+    fn default() -> Self {
+        Self {
+            root: Arc::new(Node::leaf(P::zero(), None)),
+            len: 0,
+        }
+    }
+}
+
+impl<P: Prefix, T> PersistentPrefixMap<P, T> {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Check whether both maps are the same version, i.e., whether they share the same root. If
+    /// this returns `false`, the maps may still contain the same entries.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.root, &other.root)
+    }
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        self.get_key_value(prefix).map(|(_, t)| t)
+    }
+
+    /// Get the key and the value of an element by matching exactly on the prefix.
+    pub fn get_key_value(&self, prefix: &P) -> Option<(&P, &T)> {
+        let mut node = &*self.root;
+        loop {
+            if node.prefix.eq(prefix) {
+                return node.value.as_ref().map(|t| (&node.prefix, t));
+            }
+            match node.child(to_right(&node.prefix, prefix)) {
+                Some(child) if child.prefix.contains(prefix) => node = child,
+                _ => return None,
+            }
+        }
+    }
+
+    /// Check if a key is present in the map.
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get_key_value(prefix).is_some()
+    }
+
+    /// Get the value of the longest prefix in the map that contains `prefix`.
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        let mut node = &*self.root;
+        let mut best_match = None;
+        loop {
+            best_match = node
+                .value
+                .as_ref()
+                .map(|t| (&node.prefix, t))
+                .or(best_match);
+            if node.prefix.eq(prefix) {
+                return best_match;
+            }
+            match node.child(to_right(&node.prefix, prefix)) {
+                Some(child) if child.prefix.contains(prefix) => node = child,
+                _ => return best_match,
+            }
+        }
+    }
+
+    /// Iterate over all entries in the map in lexicographic order.
+    pub fn iter(&self) -> Iter<'_, P, T> {
+        Iter {
+            stack: vec![&*self.root],
+        }
+    }
+}
+
+impl<P, T> PersistentPrefixMap<P, T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    /// Create a new version of the map, in which `prefix` is mapped to `value`. The new version
+    /// shares all unmodified nodes with `self`, and `self` remains unchanged.
+    pub fn insert(&self, prefix: P, value: T) -> Self {
+        let mut new = self.clone();
+        new.insert_mut(prefix, value);
+        new
+    }
+
+    /// Create a new version of the map, in which `prefix` is removed. The new version shares all
+    /// unmodified nodes with `self`, and `self` remains unchanged. If `prefix` is not present,
+    /// this returns a clone of `self`.
+    pub fn remove(&self, prefix: &P) -> Self {
+        let mut new = self.clone();
+        new.remove_mut(prefix);
+        new
+    }
+
+    /// Create a snapshot of the map in `O(1)`. The snapshot and the map share all nodes until
+    /// either of them is modified. See [`PersistentPrefixMap::insert_mut`].
+    ///
+    /// ```
+    /// # use prefix_trie::persistent::PersistentPrefixMap;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut map: PersistentPrefixMap<Ipv4Net, u32> = PersistentPrefixMap::new();
+    /// map.insert_mut("10.0.0.0/8".parse()?, 1);
+    /// let snapshot = map.snapshot();
+    /// map.insert_mut("10.1.0.0/16".parse()?, 2);
+    /// map.remove_mut(&"10.0.0.0/8".parse()?);
+    /// assert_eq!(snapshot.iter().collect::<Vec<_>>(), vec![(&"10.0.0.0/8".parse()?, &1)]);
+    /// assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&"10.1.0.0/16".parse()?, &2)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// Insert a new item into the map in place. If the key is already present, the old value is
+    /// replaced and returned. Nodes on the path to `prefix` that are shared with a different
+    /// version (e.g., a [snapshot](PersistentPrefixMap::snapshot)) are copied, while nodes that are
+    /// only owned by this map are modified without copying them.
+    pub fn insert_mut(&mut self, prefix: P, value: T) -> Option<T> {
+        let old = insert(&mut self.root, prefix, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Remove an element from the map in place, returning its value. Like
+    /// [`PersistentPrefixMap::insert_mut`], only the nodes that are shared with a different
+    /// version are copied. Nothing is copied if `prefix` is not present.
+    pub fn remove_mut(&mut self, prefix: &P) -> Option<T> {
+        if !self.contains_key(prefix) {
+            return None;
+        }
+        let old = remove(&mut self.root, prefix);
+        self.len -= 1;
+        old
+    }
+}
+
+/// Store `value` at `prefix` in the subtree of `node`, copying all shared nodes on the path, and
+/// return the old value. `node` must contain `prefix`.
+fn insert<P, T>(node: &mut Arc<Node<P, T>>, prefix: P, value: T) -> Option<T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    let node = Arc::make_mut(node);
+    if node.prefix.eq(&prefix) {
+        return node.value.replace(value);
+    }
+    let slot = node.child_mut(to_right(&node.prefix, &prefix));
+    let Some(child) = slot.take() else {
+        *slot = Some(Arc::new(Node::leaf(prefix, Some(value))));
+        return None;
+    };
+    if child.prefix.contains(&prefix) {
+        let child = slot.insert(child);
+        return insert(child, prefix, value);
+    }
+    let new = if prefix.contains(&child.prefix) {
+        let mut leaf = Node::leaf(prefix, Some(value));
+        let right = to_right(&leaf.prefix, &child.prefix);
+        *leaf.child_mut(right) = Some(child);
+        leaf
+    } else {
+        let mut branch = Node::leaf(child.prefix.longest_common_prefix(&prefix), None);
+        let leaf_right = to_right(&branch.prefix, &prefix);
+        *branch.child_mut(leaf_right) = Some(Arc::new(Node::leaf(prefix, Some(value))));
+        *branch.child_mut(!leaf_right) = Some(child);
+        branch
+    };
+    *slot = Some(Arc::new(new));
+    None
+}
+
+/// Remove `prefix` from the subtree of `node`, copying all shared nodes on the path, and return
+/// its value. Children that are no longer needed are removed. `node` must contain `prefix`.
+fn remove<P, T>(node: &mut Arc<Node<P, T>>, prefix: &P) -> Option<T>
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    let node = Arc::make_mut(node);
+    if node.prefix.eq(prefix) {
+        return node.value.take();
+    }
+    let slot = node.child_mut(to_right(&node.prefix, prefix));
+    let child = slot.as_mut().filter(|c| c.prefix.contains(prefix))?;
+    let value = remove(child, prefix)?;
+    // remove the child if it has no value and at most one child.
+    if child.value.is_none() && (child.left.is_none() || child.right.is_none()) {
+        let child = slot.take().unwrap();
+        *slot = match Arc::try_unwrap(child) {
+            Ok(child) => child.left.or(child.right),
+            Err(child) => child.left.clone().or_else(|| child.right.clone()),
+        };
+    }
+    Some(value)
+}
+
+impl<P: Prefix + core::fmt::Debug, T: core::fmt::Debug> core::fmt::Debug
+    for PersistentPrefixMap<P, T>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<P: Prefix, T: PartialEq> PartialEq for PersistentPrefixMap<P, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|((p1, t1), (p2, t2))| p1.eq(p2) && t1 == t2)
+    }
+}
+
+impl<P: Prefix, T: Eq> Eq for PersistentPrefixMap<P, T> {}
+
+impl<P: Prefix + Clone, T: Clone> FromIterator<(P, T)> for PersistentPrefixMap<P, T> {
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (p, t) in iter {
+            map.insert_mut(p, t);
+        }
+        map
+    }
+}
+
+impl<P: Prefix + Clone, T: Clone> From<PrefixMap<P, T>> for PersistentPrefixMap<P, T> {
+    fn from(map: PrefixMap<P, T>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<'a, P: Prefix, T> IntoIterator for &'a PersistentPrefixMap<P, T> {
+    type Item = (&'a P, &'a T);
+    type IntoIter = Iter<'a, P, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over all entries of a [`PersistentPrefixMap`] in lexicographic order.
+pub struct Iter<'a, P, T> {
+    stack: Vec<&'a Node<P, T>>,
+}
+
+impl<'a, P, T> Iterator for Iter<'a, P, T> {
+    type Item = (&'a P, &'a T);
+
+    fn next(&mut self) -> Option<(&'a P, &'a T)> {
+        while let Some(node) = self.stack.pop() {
+            self.stack.extend(node.right.as_deref());
+            self.stack.extend(node.left.as_deref());
+            if let Some(value) = node.value.as_ref() {
+                return Some((&node.prefix, value));
+            }
+        }
+        None
+    }
+}
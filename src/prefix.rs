@@ -1,7 +1,11 @@
 //! Description of the generic type `Prefix`.
 
+use core::marker::PhantomData;
+use core::net::{Ipv4Addr, Ipv6Addr};
+#[cfg(feature = "ipnet")]
 use ipnet::{Ipv4Net, Ipv6Net};
-use num_traits::{CheckedShr, PrimInt, Unsigned, Zero};
+
+use num_traits::{CheckedShr, One, PrimInt, Unsigned, Zero};
 
 /// Trait for defining prefixes.
 pub trait Prefix: Sized {
@@ -67,6 +71,107 @@ pub trait Prefix: Sized {
     }
 }
 
+/// A type that can be used to look up keys of a [`crate::PrefixMap`] without constructing a key
+/// of type `P`. This is useful if `P` owns heap data, but it can be compared using only its
+/// representation and its prefix length. Every [`Prefix`] can be used as a query for prefixes
+/// with the same representation.
+///
+/// A query matches a key if both have the same prefix length, and the same representation after
+/// both are masked with that prefix length.
+pub trait PrefixQuery<R> {
+    /// Get the raw representation of the query. Host bits beyond the prefix length are ignored.
+    fn query_repr(&self) -> R;
+
+    /// Get the prefix length of the query.
+    fn query_prefix_len(&self) -> u8;
+}
+
+impl<P: Prefix> PrefixQuery<P::R> for P {
+    fn query_repr(&self) -> P::R {
+        self.repr()
+    }
+
+    fn query_prefix_len(&self) -> u8 {
+        self.prefix_len()
+    }
+}
+
+/// The reason why a [`Prefix`] implementation is inconsistent. See [`check_prefix`].
+#[cfg(feature = "checked")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixError {
+    /// The prefix length is larger than the number of bits in the representation.
+    LengthOutOfRange {
+        /// The prefix length of the prefix.
+        len: u8,
+        /// The number of bits in the representation.
+        max: u8,
+    },
+    /// [`Prefix::mask`] does not return the representation with all host bits cleared.
+    InconsistentMask,
+    /// [`Prefix::from_repr_len`] does not produce a prefix with the same mask and length.
+    InconsistentConstruction,
+    /// [`Prefix::contains`] is wrong for the prefix itself or for [`Prefix::zero`], or a node in
+    /// the tree has a child that is not strictly contained in it.
+    InconsistentContains,
+    /// A traversal reached a depth larger than the number of bits in the representation, which
+    /// is only possible if [`Prefix::contains`] is inconsistent with the prefix lengths.
+    DepthExceeded,
+}
+
+#[cfg(feature = "checked")]
+impl core::fmt::Display for PrefixError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PrefixError::LengthOutOfRange { len, max } => {
+                write!(f, "Prefix length {len} is larger than {max}")
+            }
+            PrefixError::InconsistentMask => f.write_str("Prefix::mask is inconsistent"),
+            PrefixError::InconsistentConstruction => {
+                f.write_str("Prefix::from_repr_len is inconsistent")
+            }
+            PrefixError::InconsistentContains => f.write_str("Prefix::contains is inconsistent"),
+            PrefixError::DepthExceeded => f.write_str("Maximum traversal depth exceeded"),
+        }
+    }
+}
+
+#[cfg(feature = "checked")]
+impl core::error::Error for PrefixError {}
+
+/// Check that the [`Prefix`] implementation behaves consistently for `prefix`. This is meant as
+/// a defensive check for custom implementations of [`Prefix`], before passing untrusted prefixes
+/// to a map.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// check_prefix(&"10.0.0.0/8".parse::<Ipv4Net>()?)?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "checked")]
+pub fn check_prefix<P: Prefix>(prefix: &P) -> Result<(), PrefixError> {
+    let len = prefix.prefix_len();
+    let max = P::R::zero().count_zeros() as u8;
+    if len > max {
+        return Err(PrefixError::LengthOutOfRange { len, max });
+    }
+    let mask = prefix.mask();
+    if mask != prefix.repr() & mask_from_prefix_len(len) {
+        return Err(PrefixError::InconsistentMask);
+    }
+    let copy = P::from_repr_len(mask, len);
+    if copy.prefix_len() != len || copy.mask() != mask {
+        return Err(PrefixError::InconsistentConstruction);
+    }
+    if !prefix.contains(&copy) || !copy.contains(prefix) || !P::zero().contains(prefix) {
+        return Err(PrefixError::InconsistentContains);
+    }
+    Ok(())
+}
+
 pub(crate) fn mask_from_prefix_len<R>(len: u8) -> R
 where
     R: PrimInt + Zero,
@@ -80,6 +185,139 @@ where
     }
 }
 
+/// Get the last address of a prefix, i.e., its network address with all host bits set.
+pub(crate) fn last_address<P: Prefix>(prefix: &P) -> P::R {
+    prefix.mask() | !mask_from_prefix_len::<P::R>(prefix.prefix_len())
+}
+
+/// Get the shortest prefix whose first address is `start` and whose last address is at most `end`.
+/// This requires `start <= end`.
+pub(crate) fn first_prefix_in_range<P: Prefix>(start: P::R, end: P::R) -> P {
+    let num_bits = P::R::zero().count_zeros();
+    let mut len = (num_bits - start.trailing_zeros()) as u8;
+    while start | !mask_from_prefix_len::<P::R>(len) > end {
+        len += 1;
+    }
+    P::from_repr_len(start, len)
+}
+
+/// Get the smallest list of prefixes that exactly cover all addresses from `start` to `end`
+/// (inclusive), in lexicographic order. This requires `start <= end`.
+pub(crate) fn prefixes_in_range<P: Prefix>(start: P::R, end: P::R) -> RangePrefixes<P> {
+    RangePrefixes {
+        next: Some(start),
+        end,
+        _prefix: PhantomData,
+    }
+}
+
+/// Iterator over the smallest list of prefixes that cover an address range. See
+/// [`prefixes_in_range`].
+pub(crate) struct RangePrefixes<P: Prefix> {
+    next: Option<P::R>,
+    end: P::R,
+    _prefix: PhantomData<P>,
+}
+
+impl<P: Prefix> Iterator for RangePrefixes<P> {
+    type Item = P;
+
+    fn next(&mut self) -> Option<P> {
+        let start = self.next?;
+        let prefix: P = first_prefix_in_range(start, self.end);
+        let last = last_address(&prefix);
+        self.next = (last < self.end).then(|| last + P::R::one());
+        Some(prefix)
+    }
+}
+
+/// Iterator over the maximal ranges of consecutive addresses (with inclusive bounds) that are
+/// covered by a sequence of prefixes. The prefixes must be in lexicographic order.
+pub(crate) struct CoveredRanges<'a, P: Prefix, I> {
+    iter: I,
+    current: Option<(P::R, P::R)>,
+    _prefix: PhantomData<&'a P>,
+}
+
+impl<'a, P: Prefix + 'a, I: Iterator<Item = &'a P>> CoveredRanges<'a, P, I> {
+    /// Create the iterator from a sequence of prefixes in lexicographic order.
+    pub(crate) fn new(iter: I) -> Self {
+        Self {
+            iter,
+            current: None,
+            _prefix: PhantomData,
+        }
+    }
+}
+
+impl<'a, P: Prefix + 'a, I: Iterator<Item = &'a P>> Iterator for CoveredRanges<'a, P, I> {
+    type Item = (P::R, P::R);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for p in self.iter.by_ref() {
+            let (start, end) = (p.mask(), last_address(p));
+            match self.current.as_mut() {
+                // `start` is never smaller than the current start.
+                Some((_, cur_end)) if start <= *cur_end || start - *cur_end == P::R::one() => {
+                    *cur_end = (*cur_end).max(end);
+                }
+                _ => {
+                    if let Some(range) = self.current.replace((start, end)) {
+                        return Some(range);
+                    }
+                }
+            }
+        }
+        self.current.take()
+    }
+}
+
+/// Iterator over the maximal ranges of consecutive addresses (with inclusive bounds) between
+/// `start` and `end` that are not part of any range in `iter`. The ranges in `iter` must be sorted,
+/// disjoint, non-adjacent, and lie between `start` and `end`, like the ones produced by
+/// [`CoveredRanges`].
+pub(crate) struct UncoveredRanges<R, I> {
+    iter: I,
+    /// The first address that is not yet processed, or `None` if all addresses are processed.
+    next: Option<R>,
+    end: R,
+}
+
+impl<R, I> UncoveredRanges<R, I> {
+    /// Create the iterator over all addresses from `start` to `end` not covered by `iter`.
+    pub(crate) fn new(start: R, end: R, iter: I) -> Self {
+        Self {
+            iter,
+            next: Some(start),
+            end,
+        }
+    }
+}
+
+impl<R: PrimInt, I: Iterator<Item = (R, R)>> Iterator for UncoveredRanges<R, I> {
+    type Item = (R, R);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.next?;
+        match self.iter.next() {
+            Some((covered_start, covered_end)) => {
+                self.next = (covered_end < self.end).then(|| covered_end + R::one());
+                if covered_start > start {
+                    Some((start, covered_start - R::one()))
+                } else {
+                    // the range starts exactly at `start`, so the gap (if any) is after it.
+                    self.next()
+                }
+            }
+            None => {
+                self.next = None;
+                Some((start, self.end))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ipnet")]
 impl Prefix for Ipv4Net {
     type R = u32;
 
@@ -122,6 +360,7 @@ impl Prefix for Ipv4Net {
     }
 }
 
+#[cfg(feature = "ipnet")]
 impl Prefix for Ipv6Net {
     type R = u128;
 
@@ -164,22 +403,83 @@ impl Prefix for Ipv6Net {
     }
 }
 
-impl<R> Prefix for (R, u8)
-where
-    R: Unsigned + PrimInt + Zero + CheckedShr,
-{
-    type R = R;
+/// Implement [`Prefix`] for `(R, u8)` tuples of a primitive integer representation. This is not a
+/// blanket implementation over all integers, such that tuples of other types (like
+/// `(Ipv4Addr, u8)`) can implement [`Prefix`] as well.
+macro_rules! impl_int_tuple {
+    ($($r:ty),*) => {$(
+        impl Prefix for ($r, u8) {
+            type R = $r;
+
+            fn repr(&self) -> $r {
+                self.0
+            }
+
+            fn prefix_len(&self) -> u8 {
+                self.1
+            }
+
+            fn from_repr_len(repr: $r, len: u8) -> Self {
+                (repr, len)
+            }
+
+            fn eq(&self, other: &Self) -> bool {
+                self == other
+            }
+        }
+    )*};
+}
+
+impl_int_tuple!(u8, u16, u32, u64, u128, usize);
+
+/// An IPv4 prefix stored as its address and prefix length. The address must not have any host
+/// bits set.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use std::net::Ipv4Addr;
+/// let mut pm: PrefixMap<(Ipv4Addr, u8), _> = PrefixMap::new();
+/// pm.insert((Ipv4Addr::new(10, 0, 0, 0), 8), 1);
+/// assert_eq!(
+///     pm.get_lpm(&(Ipv4Addr::new(10, 1, 2, 3), 32)),
+///     Some((&(Ipv4Addr::new(10, 0, 0, 0), 8), &1))
+/// );
+/// ```
+impl Prefix for (Ipv4Addr, u8) {
+    type R = u32;
+
+    fn repr(&self) -> u32 {
+        self.0.into()
+    }
+
+    fn prefix_len(&self) -> u8 {
+        self.1
+    }
+
+    fn from_repr_len(repr: u32, len: u8) -> Self {
+        (repr.into(), len)
+    }
 
-    fn repr(&self) -> R {
-        self.0
+    fn eq(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// An IPv6 prefix stored as its address and prefix length. The address must not have any host
+/// bits set.
+impl Prefix for (Ipv6Addr, u8) {
+    type R = u128;
+
+    fn repr(&self) -> u128 {
+        self.0.into()
     }
 
     fn prefix_len(&self) -> u8 {
         self.1
     }
 
-    fn from_repr_len(repr: R, len: u8) -> Self {
-        (repr, len)
+    fn from_repr_len(repr: u128, len: u8) -> Self {
+        (repr.into(), len)
     }
 
     fn eq(&self, other: &Self) -> bool {
@@ -187,7 +487,36 @@ where
     }
 }
 
-#[cfg(test)]
+/// Implement [`Prefix`] for `([u8; N], u8)` tuples, where the bytes are interpreted in network
+/// byte order (big endian). Only array sizes that match the width of a primitive integer are
+/// supported, as [`Prefix::R`] must be one of them.
+macro_rules! impl_array_tuple {
+    ($($n:literal => $r:ty),*) => {$(
+        impl Prefix for ([u8; $n], u8) {
+            type R = $r;
+
+            fn repr(&self) -> $r {
+                <$r>::from_be_bytes(self.0)
+            }
+
+            fn prefix_len(&self) -> u8 {
+                self.1
+            }
+
+            fn from_repr_len(repr: $r, len: u8) -> Self {
+                (repr.to_be_bytes(), len)
+            }
+
+            fn eq(&self, other: &Self) -> bool {
+                self == other
+            }
+        }
+    )*};
+}
+
+impl_array_tuple!(1 => u8, 2 => u16, 4 => u32, 8 => u64, 16 => u128);
+
+#[cfg(all(test, feature = "ipnet"))]
 mod test {
     use super::*;
 
@@ -252,6 +581,95 @@ mod test {
         assert_lcp!("1.2.3.4/24", "1.2.3.4/30", "1.2.3.0/24");
     }
 
+    #[test]
+    fn last_address() {
+        assert_eq!(super::last_address(&pfx!("10.0.0.0/8")), 0x0affffff);
+        assert_eq!(super::last_address(&pfx!("10.1.2.3/32")), 0x0a010203);
+        assert_eq!(super::last_address(&pfx!("0.0.0.0/0")), 0xffffffff);
+    }
+
+    #[test]
+    fn first_prefix_in_range() {
+        let f = |a: u32, b: u32| super::first_prefix_in_range::<Ipv4Net>(a, b);
+        assert_eq!(f(0, u32::MAX), pfx!("0.0.0.0/0"));
+        assert_eq!(f(0, 0x7fffffff), pfx!("0.0.0.0/1"));
+        assert_eq!(f(0, 0x7ffffffe), pfx!("0.0.0.0/2"));
+        assert_eq!(f(0x0a000000, 0x0affffff), pfx!("10.0.0.0/8"));
+        assert_eq!(f(0x0a000000, 0x0bffffff), pfx!("10.0.0.0/7"));
+        assert_eq!(f(0x0a010000, 0x0bffffff), pfx!("10.1.0.0/16"));
+        assert_eq!(f(0x0a010203, 0x0a010203), pfx!("10.1.2.3/32"));
+    }
+
+    #[test]
+    fn prefixes_in_range() {
+        let f = |a: &str, b: &str| {
+            let a: u32 = a.parse::<std::net::Ipv4Addr>().unwrap().into();
+            let b: u32 = b.parse::<std::net::Ipv4Addr>().unwrap().into();
+            super::prefixes_in_range::<Ipv4Net>(a, b).collect::<Vec<_>>()
+        };
+        assert_eq!(f("0.0.0.0", "255.255.255.255"), vec![pfx!("0.0.0.0/0")]);
+        assert_eq!(f("10.0.0.0", "10.0.0.0"), vec![pfx!("10.0.0.0/32")]);
+        assert_eq!(
+            f("10.0.0.1", "10.0.0.6"),
+            vec![
+                pfx!("10.0.0.1/32"),
+                pfx!("10.0.0.2/31"),
+                pfx!("10.0.0.4/31"),
+                pfx!("10.0.0.6/32")
+            ]
+        );
+        assert_eq!(
+            f("10.0.0.0", "10.2.255.255"),
+            vec![pfx!("10.0.0.0/15"), pfx!("10.2.0.0/16")]
+        );
+        assert_eq!(
+            f("255.255.255.254", "255.255.255.255"),
+            vec![pfx!("255.255.255.254/31")]
+        );
+    }
+
+    #[test]
+    fn covered_ranges() {
+        let prefixes = [
+            pfx!("10.0.0.0/16"),
+            pfx!("10.0.1.0/24"),
+            pfx!("10.1.0.0/16"),
+            pfx!("10.3.0.0/16"),
+            pfx!("255.255.255.255/32"),
+        ];
+        let ranges = super::CoveredRanges::new(prefixes.iter()).collect::<Vec<_>>();
+        assert_eq!(
+            ranges,
+            vec![
+                (0x0a000000, 0x0a01ffff),
+                (0x0a030000, 0x0a03ffff),
+                (0xffffffff, 0xffffffff)
+            ]
+        );
+    }
+
+    #[test]
+    fn uncovered_ranges() {
+        let ranges = [(0x10, 0x1f), (0x30, 0x3f)];
+        assert_eq!(
+            super::UncoveredRanges::new(0x10u32, 0x4f, ranges.into_iter()).collect::<Vec<_>>(),
+            vec![(0x20, 0x2f), (0x40, 0x4f)]
+        );
+        assert_eq!(
+            super::UncoveredRanges::new(0x00u32, 0x3f, ranges.into_iter()).collect::<Vec<_>>(),
+            vec![(0x00, 0x0f), (0x20, 0x2f)]
+        );
+        assert_eq!(
+            super::UncoveredRanges::new(0u32, u32::MAX, [(0, u32::MAX)].into_iter())
+                .collect::<Vec<_>>(),
+            vec![]
+        );
+        assert_eq!(
+            super::UncoveredRanges::new(0u32, u32::MAX, [].into_iter()).collect::<Vec<_>>(),
+            vec![(0, u32::MAX)]
+        );
+    }
+
     #[test]
     fn is_bit_set() {
         assert!(pfx!("255.0.0.0/8").is_bit_set(0));
@@ -352,4 +770,49 @@ mod test {
         #[instantiate_tests(<(u64, u8)>)]
         mod u64_u8 {}
     }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn check_inconsistent_prefix() {
+        /// A prefix that forgets its length when constructed from its representation.
+        #[derive(Clone, Copy)]
+        struct Forgetful(u32, u8);
+        impl Prefix for Forgetful {
+            type R = u32;
+            fn repr(&self) -> u32 {
+                self.0
+            }
+            fn prefix_len(&self) -> u8 {
+                self.1
+            }
+            fn from_repr_len(repr: u32, _len: u8) -> Self {
+                Forgetful(repr, 32)
+            }
+        }
+
+        assert_eq!(check_prefix(&pfx!("10.0.0.0/8")), Ok(()));
+        assert_eq!(check_prefix(&Forgetful(0, 32)), Ok(()));
+        assert_eq!(
+            check_prefix(&Forgetful(0, 33)),
+            Err(PrefixError::LengthOutOfRange { len: 33, max: 32 })
+        );
+        assert_eq!(
+            check_prefix(&Forgetful(0, 8)),
+            Err(PrefixError::InconsistentConstruction)
+        );
+    }
+
+    #[test]
+    fn array_tuple() {
+        let a: ([u8; 4], u8) = ([10, 1, 0, 0], 16);
+        let b: ([u8; 4], u8) = ([10, 1, 2, 0], 24);
+        assert_eq!(a.repr(), 0x0a010000);
+        assert!(a.contains(&b));
+        assert!(!b.contains(&a));
+        assert_eq!(a.longest_common_prefix(&b), a);
+        assert!(b.is_bit_set(22));
+        assert!(!b.is_bit_set(23));
+        let c: ([u8; 16], u8) = Prefix::from_repr_len(1 << 120, 8);
+        assert_eq!(c, ([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], 8));
+    }
 }
@@ -1,49 +1,116 @@
 //! Serialization and Deserialization implementation
+//!
+//! A [`PrefixMap`] is serialized as a sequence of `(prefix, value)` pairs, and a [`PrefixSet`] as a
+//! sequence of prefixes, both in lexicographic order. Deserializing rebuilds the tree by inserting
+//! all elements one by one. If a prefix appears multiple times, the last value is kept.
+//!
+//! Version 0.2 serialized a [`PrefixMap`] as a map from prefixes to values, and a [`PrefixSet`] as a
+//! sequence of prefixes in arbitrary order. Both forms are still accepted when deserializing. For
+//! self-describing formats (as indicated by `Deserializer::is_human_readable`), the map visitor
+//! accepts both a map and a sequence. Compact binary formats encode a map and a sequence of pairs
+//! identically, so they are read as a sequence.
 
-use std::collections::{HashMap, HashSet};
+use core::marker::PhantomData;
 
-use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ::serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 use super::*;
 
-impl<P: Prefix + Serialize + Eq + std::hash::Hash, T: Serialize> Serialize for PrefixMap<P, T> {
+impl<P: Prefix + Serialize, T: Serialize> Serialize for PrefixMap<P, T> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let map: HashMap<&P, &T> = HashMap::from_iter(self);
-        map.serialize(serializer)
+        serializer.collect_seq(self)
     }
 }
 
-impl<P: Prefix + Serialize + Eq + std::hash::Hash> Serialize for PrefixSet<P> {
+impl<P: Prefix + Serialize> Serialize for PrefixSet<P> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let set: HashSet<&P> = HashSet::from_iter(self);
-        set.serialize(serializer)
+        serializer.collect_seq(self)
     }
 }
 
-impl<'de, P: Prefix + Deserialize<'de> + Eq + std::hash::Hash, T: Deserialize<'de>> Deserialize<'de>
-    for PrefixMap<P, T>
-{
+impl<'de, P: Prefix + Deserialize<'de>, T: Deserialize<'de>> Deserialize<'de> for PrefixMap<P, T> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let map: HashMap<P, T> = HashMap::deserialize(deserializer)?;
-        Ok(Self::from_iter(map))
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(MapVisitor(PhantomData))
+        } else {
+            deserializer.deserialize_seq(MapVisitor(PhantomData))
+        }
     }
 }
 
-impl<'de, P: Prefix + Deserialize<'de> + Eq + std::hash::Hash> Deserialize<'de> for PrefixSet<P> {
+impl<'de, P: Prefix + Deserialize<'de>> Deserialize<'de> for PrefixSet<P> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let set: HashSet<P> = HashSet::deserialize(deserializer)?;
-        Ok(Self::from_iter(set))
+        deserializer.deserialize_seq(SetVisitor(PhantomData))
+    }
+}
+
+/// Visitor that builds a [`PrefixMap`] from a sequence of `(prefix, value)` pairs, or from a map
+/// (the format of version 0.2).
+struct MapVisitor<P, T>(PhantomData<(P, T)>);
+
+impl<'de, P: Prefix + Deserialize<'de>, T: Deserialize<'de>> Visitor<'de> for MapVisitor<P, T> {
+    type Value = PrefixMap<P, T>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a sequence of (prefix, value) pairs or a map")
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut map = PrefixMap::new();
+        while let Some((p, t)) = access.next_entry()? {
+            map.insert(p, t);
+        }
+        Ok(map)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut map = PrefixMap::new();
+        while let Some((p, t)) = seq.next_element()? {
+            map.insert(p, t);
+        }
+        Ok(map)
+    }
+}
+
+/// Visitor that builds a [`PrefixSet`] from a sequence of prefixes.
+struct SetVisitor<P>(PhantomData<P>);
+
+impl<'de, P: Prefix + Deserialize<'de>> Visitor<'de> for SetVisitor<P> {
+    type Value = PrefixSet<P>;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a sequence of prefixes")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut set = PrefixSet::new();
+        while let Some(p) = seq.next_element()? {
+            set.insert(p);
+        }
+        Ok(set)
     }
 }
@@ -0,0 +1,362 @@
+//! Operations on the address space covered by a [`PrefixSet`].
+
+use core::cell::Cell;
+
+use num_traits::One;
+
+use crate::prefix::{mask_from_prefix_len, prefixes_in_range, CoveredRanges};
+
+use super::*;
+
+/// An iterator over all individual addresses covered by a [`PrefixSet`], in ascending order. See
+/// [`PrefixSet::addresses`].
+pub struct Addresses<'a, P: Prefix> {
+    ranges: CoveredRanges<'a, P, Iter<'a, P>>,
+    current: Option<(P::R, P::R)>,
+}
+
+impl<'a, P: Prefix> Iterator for Addresses<'a, P> {
+    type Item = P::R;
+
+    fn next(&mut self) -> Option<P::R> {
+        let (start, end) = match self.current.take() {
+            Some(range) => range,
+            None => self.ranges.next()?,
+        };
+        // do not step past `end`, as it may be the last address of the address space.
+        if start < end {
+            self.current = Some((start + P::R::one(), end));
+        }
+        Some(start)
+    }
+}
+
+impl<P: Prefix> PrefixSet<P> {
+    /// Compute the smallest set of prefixes that covers exactly the same addresses as `self`
+    /// (classic CIDR aggregation). Prefixes that are contained within other elements are removed,
+    /// and adjacent prefixes are merged into their common parent wherever possible.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let set: PrefixSet<Ipv4Net> = [
+    ///     "192.168.0.0/24",
+    ///     "192.168.1.0/24",
+    ///     "192.168.2.0/23",
+    ///     "192.168.2.128/25",
+    ///     "192.168.4.0/24",
+    /// ]
+    /// .into_iter()
+    /// .map(|p| p.parse().unwrap())
+    /// .collect();
+    /// assert_eq!(
+    ///     set.aggregate().into_iter().collect::<Vec<_>>(),
+    ///     vec!["192.168.0.0/22".parse()?, "192.168.4.0/24".parse()?],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn aggregate(&self) -> Self {
+        // never cancelled
+        self.try_aggregate(|| false).unwrap()
+    }
+
+    /// Aggregate the set like [`PrefixSet::aggregate`], but stop early if the operation gets
+    /// cancelled. The function `cancelled` is called once for each element of the set. As soon as it
+    /// returns `true`, the aggregation is aborted and `None` is returned. See
+    /// [`PrefixMap::try_aggregate_with`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let set: PrefixSet<Ipv4Net> = ["10.0.0.0/24", "10.0.1.0/24"]
+    ///     .into_iter()
+    ///     .map(|p| p.parse().unwrap())
+    ///     .collect();
+    /// let aggr = set.try_aggregate(|| false);
+    /// assert_eq!(aggr.map(|s| s.into_iter().collect::<Vec<_>>()), Some(vec!["10.0.0.0/23".parse()?]));
+    /// assert!(set.try_aggregate(|| true).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_aggregate<C>(&self, mut cancelled: C) -> Option<Self>
+    where
+        C: FnMut() -> bool,
+    {
+        // poll `cancelled` for every element, as consecutive elements are merged into a single
+        // range before it is yielded.
+        let stopped = Cell::new(false);
+        let elements = self.iter().take_while(|_| {
+            stopped.set(cancelled());
+            !stopped.get()
+        });
+        let mut set = Self::new();
+        for (start, end) in CoveredRanges::new(elements) {
+            if stopped.get() {
+                return None;
+            }
+            prefixes_in_range(start, end).for_each(|p| {
+                set.insert(p);
+            });
+        }
+        (!stopped.get()).then_some(set)
+    }
+
+    /// Compute the set of prefixes that covers exactly those addresses within `within` that are
+    /// not covered by any element of `self`. The result is the smallest such set, i.e., it
+    /// contains no two prefixes that could be merged. If `within` is covered entirely, the
+    /// resulting set is empty.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let allow: PrefixSet<Ipv4Net> = ["10.0.0.0/24", "10.0.2.0/24", "192.168.0.0/16"]
+    ///     .into_iter()
+    ///     .map(|p| p.parse().unwrap())
+    ///     .collect();
+    /// let deny = allow.complement(&"10.0.0.0/22".parse()?);
+    /// assert_eq!(
+    ///     deny.into_iter().collect::<Vec<_>>(),
+    ///     vec!["10.0.1.0/24".parse()?, "10.0.3.0/24".parse()?],
+    /// );
+    /// assert_eq!(allow.complement(&"192.168.1.0/24".parse()?).iter().next(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn complement(&self, within: &P) -> Self {
+        self.0.gaps(within).collect()
+    }
+
+    /// Remove the coverage of `hole` from the set, such that no element contains any address of
+    /// `hole` afterwards, while all other addresses remain covered. All elements within `hole` are
+    /// removed, and every element that contains `hole` is split into the remainders around it.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("192.168.0.0/24".parse()?);
+    /// set.insert("192.168.0.64/27".parse()?);
+    /// set.remove_cover(&"192.168.0.64/26".parse()?);
+    /// assert_eq!(
+    ///     set.into_iter().collect::<Vec<_>>(),
+    ///     vec!["192.168.0.0/26".parse()?, "192.168.0.128/25".parse()?],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove_cover(&mut self, hole: &P)
+    where
+        P: Clone,
+    {
+        self.remove_children(hole);
+        while let Some(covering) = self.get_lpm(hole).cloned() {
+            self.remove(&covering);
+            // insert the sibling of every prefix on the path from `covering` to `hole`.
+            for len in covering.prefix_len() + 1..=hole.prefix_len() {
+                let mask: P::R = mask_from_prefix_len(len);
+                let bit = mask ^ mask_from_prefix_len(len - 1);
+                self.insert(P::from_repr_len((hole.mask() & mask) ^ bit, len));
+            }
+        }
+    }
+
+    /// Check if the elements of the set together cover every address of `prefix`. The prefix may
+    /// be covered by an element that contains it, or by (possibly many) more-specific elements.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("10.0.0.0/25".parse()?);
+    /// assert!(!set.covers_fully(&"10.0.0.0/24".parse()?));
+    /// set.insert("10.0.0.128/25".parse()?);
+    /// assert!(set.covers_fully(&"10.0.0.0/24".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn covers_fully(&self, prefix: &P) -> bool {
+        self.0.covers_fully(prefix)
+    }
+
+    /// Find the first prefix of length `len` that starts at or after `start` and that does not
+    /// overlap with any element in the set. See [`PrefixMap::find_first_gap_after`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("10.0.0.0/24".parse()?);
+    /// set.insert("10.0.1.0/26".parse()?);
+    /// let start = "10.0.0.0/24".parse()?;
+    /// assert_eq!(set.find_first_gap_after(&start, 26), Some("10.0.1.64/26".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_first_gap_after(&self, start: &P, len: u8) -> Option<P> {
+        self.0.find_first_gap_after(start, len)
+    }
+
+    /// Find the first prefix of length `len` that starts at or after `start` and that does not
+    /// overlap with any element in the set, wrapping around at the end of the address space. See
+    /// [`PrefixMap::find_first_gap_wrapping`].
+    pub fn find_first_gap_wrapping(&self, start: &P, len: u8) -> Option<P> {
+        self.0.find_first_gap_wrapping(start, len)
+    }
+
+    /// Count the number of individual addresses covered by the set, counting addresses covered by
+    /// multiple (overlapping) elements only once. See [`PrefixMap::coverage_size`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("10.0.0.0/24".parse()?);
+    /// set.insert("10.0.0.0/25".parse()?);
+    /// set.insert("10.0.1.0/30".parse()?);
+    /// assert_eq!(set.coverage_size(), 260);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn coverage_size(&self) -> u128 {
+        self.0.coverage_size()
+    }
+
+    /// Get the smallest prefix that covers all elements of the set, or `None` if the set is empty
+    /// or only the zero-length prefix covers all elements. See [`PrefixMap::spanning_prefix`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let set: PrefixSet<Ipv4Net> = ["192.168.1.0/24", "192.168.2.0/24"]
+    ///     .into_iter()
+    ///     .map(|p| p.parse().unwrap())
+    ///     .collect();
+    /// assert_eq!(set.spanning_prefix(), Some("192.168.0.0/22".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spanning_prefix(&self) -> Option<P> {
+        self.0.spanning_prefix()
+    }
+
+    /// Iterate over every individual address covered by the set, in ascending order. Each address
+    /// is yielded exactly once, even if it is covered by multiple elements.
+    ///
+    /// Note that the number of addresses grows exponentially with shorter prefix lengths; a single
+    /// IPv6 `/64` covers 2^64 addresses. Use [`PrefixSet::coverage_size`] to check the size
+    /// before collecting the iterator.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use std::net::Ipv4Addr;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let set: PrefixSet<Ipv4Net> = ["10.0.0.0/31", "10.0.0.1/32", "10.0.0.4/32"]
+    ///     .into_iter()
+    ///     .map(|p| p.parse().unwrap())
+    ///     .collect();
+    /// assert_eq!(
+    ///     set.addresses().map(Ipv4Addr::from).collect::<Vec<_>>(),
+    ///     vec![
+    ///         Ipv4Addr::new(10, 0, 0, 0),
+    ///         Ipv4Addr::new(10, 0, 0, 1),
+    ///         Ipv4Addr::new(10, 0, 0, 4),
+    ///     ],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn addresses(&self) -> Addresses<'_, P> {
+        Addresses {
+            ranges: CoveredRanges::new(self.iter()),
+            current: None,
+        }
+    }
+
+    /// Check if every address covered by `self` is also covered by `other`. Both sets are treated
+    /// as the address space they cover, so an element of `self` may be covered by a larger element
+    /// of `other`, or by a combination of more-specific ones.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let old: PrefixSet<Ipv4Net> = ["10.0.0.0/25", "10.0.0.128/25"]
+    ///     .into_iter()
+    ///     .map(|p| p.parse().unwrap())
+    ///     .collect();
+    /// let mut new: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// new.insert("10.0.0.0/24".parse()?);
+    /// assert!(new.is_subset(&old));
+    /// new.insert("10.0.1.0/32".parse()?);
+    /// assert!(!new.is_subset(&old));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.outermost().all(|p| other.covers_fully(p))
+    }
+
+    /// Check if every address covered by `other` is also covered by `self`. See
+    /// [`PrefixSet::is_subset`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut a: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// a.insert("10.0.0.0/8".parse()?);
+    /// let mut b: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// b.insert("10.1.0.0/16".parse()?);
+    /// assert!(a.is_superset(&b));
+    /// assert!(!b.is_superset(&a));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Check if no address is covered by both `self` and `other`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut a: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// a.insert("10.0.0.0/24".parse()?);
+    /// let mut b: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// b.insert("10.0.1.0/24".parse()?);
+    /// assert!(a.is_disjoint(&b));
+    /// b.insert("10.0.0.128/25".parse()?);
+    /// assert!(!a.is_disjoint(&b));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.outermost()
+            .all(|p| other.get_lpm(p).is_none() && other.0.children(p).next().is_none())
+    }
+
+    /// Iterate over all elements that are not contained within any other element, in
+    /// lexicographic order.
+    fn outermost(&self) -> impl Iterator<Item = &P> {
+        let mut last: Option<&P> = None;
+        self.iter().filter(move |p| {
+            if last.is_some_and(|last| last.contains(p)) {
+                return false;
+            }
+            last = Some(p);
+            true
+        })
+    }
+}
@@ -1,3 +1,5 @@
+use alloc::{vec, vec::Vec};
+
 use crate::{to_right, Prefix, PrefixMap};
 
 #[derive(Clone)]
@@ -28,14 +30,14 @@ impl<'a, P: Prefix> Iterator for Difference<'a, P> {
                     self.nodes.extend(next_indices(
                         self.set_a,
                         self.set_b,
-                        node_a.right,
-                        node_b.right,
+                        node_a.right(),
+                        node_b.right(),
                     ));
                     self.nodes.extend(next_indices(
                         self.set_a,
                         self.set_b,
-                        node_a.left,
-                        node_b.left,
+                        node_a.left(),
+                        node_b.left(),
                     ));
                     if node_a.value.is_some() && node_b.value.is_none() {
                         return Some(&node_a.prefix);
@@ -47,8 +49,8 @@ impl<'a, P: Prefix> Iterator for Difference<'a, P> {
                         self.set_a,
                         self.set_b,
                         a,
-                        node_a.left,
-                        node_a.right,
+                        node_a.left(),
+                        node_a.right(),
                         b,
                     ));
                     if node_a.value.is_some() {
@@ -62,16 +64,16 @@ impl<'a, P: Prefix> Iterator for Difference<'a, P> {
                         self.set_b,
                         a,
                         b,
-                        node_b.left,
-                        node_b.right,
+                        node_b.left(),
+                        node_b.right(),
                     ));
                 }
                 DifferenceIndex::OnlyA(a) => {
                     let node_a = &self.set_a.table[a];
-                    if let Some(right) = node_a.right {
+                    if let Some(right) = node_a.right() {
                         self.nodes.push(DifferenceIndex::OnlyA(right));
                     }
-                    if let Some(left) = node_a.left {
+                    if let Some(left) = node_a.left() {
                         self.nodes.push(DifferenceIndex::OnlyA(left));
                     }
                     if node_a.value.is_some() {
@@ -98,7 +100,7 @@ fn next_indices<'a, P: Prefix>(
             let p_b = &set_b.table[b].prefix;
             if p_a.prefix_len() == p_b.prefix_len() {
                 match p_a.mask().cmp(&p_b.mask()) {
-                    std::cmp::Ordering::Equal => {
+                    core::cmp::Ordering::Equal => {
                         vec![DifferenceIndex::Both(a, b)]
                     }
                     _ => {
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use crate::{to_right, Prefix, PrefixMap};
 
 #[derive(Clone)]
@@ -27,14 +29,14 @@ impl<'a, P: Prefix> Iterator for Intersection<'a, P> {
                     self.nodes.extend(next_indices(
                         self.set_a,
                         self.set_b,
-                        node_a.right,
-                        node_b.right,
+                        node_a.right(),
+                        node_b.right(),
                     ));
                     self.nodes.extend(next_indices(
                         self.set_a,
                         self.set_b,
-                        node_a.left,
-                        node_b.left,
+                        node_a.left(),
+                        node_b.left(),
                     ));
                     if node_a.value.is_some() && node_b.value.is_some() {
                         return Some(&node_a.prefix);
@@ -46,8 +48,8 @@ impl<'a, P: Prefix> Iterator for Intersection<'a, P> {
                         self.set_a,
                         self.set_b,
                         a,
-                        node_a.left,
-                        node_a.right,
+                        node_a.left(),
+                        node_a.right(),
                         b,
                     ));
                 }
@@ -58,8 +60,8 @@ impl<'a, P: Prefix> Iterator for Intersection<'a, P> {
                         self.set_b,
                         a,
                         b,
-                        node_b.left,
-                        node_b.right,
+                        node_b.left(),
+                        node_b.right(),
                     ));
                 }
             }
@@ -82,7 +84,7 @@ fn next_indices<'a, P: Prefix>(
             let p_b = &set_b.table[b].prefix;
             if p_a.prefix_len() == p_b.prefix_len() {
                 match p_a.mask().cmp(&p_b.mask()) {
-                    std::cmp::Ordering::Equal => Some(IntersectionIndex::Both(a, b)),
+                    core::cmp::Ordering::Equal => Some(IntersectionIndex::Both(a, b)),
                     _ => None,
                 }
             } else if p_a.contains(p_b) {
@@ -1,14 +1,24 @@
 //! PrefixSet, that is implemened as a simple binary tree, based on the [`PrefixMap`].
 
+use alloc::vec;
+use core::ops::RangeInclusive;
+
 use crate::{Prefix, PrefixMap};
 
+mod coverage;
 mod difference;
 mod intersection;
+mod ops;
+mod partition;
 mod union;
+pub use coverage::Addresses;
 pub use difference::Difference;
 pub use intersection::Intersection;
+pub use partition::PartitionError;
 pub use union::Union;
 
+pub use crate::fmt::{DisplayLines, DisplaySetTree};
+
 /// Set of prefixes, organized in a tree. This strucutre gives efficient access to the longest
 /// prefix in the set that contains another prefix.
 #[derive(Clone)]
@@ -20,6 +30,12 @@ impl<P: Prefix> PrefixSet<P> {
         Self(Default::default())
     }
 
+    /// Create a new, empty prefixset with space for at least `capacity` nodes. See
+    /// [`PrefixMap::with_capacity`].
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(PrefixMap::with_capacity(capacity))
+    }
+
     /// Check wether some prefix is present in the set, without using longest prefix match.
     ///
     /// ```
@@ -39,6 +55,100 @@ impl<P: Prefix> PrefixSet<P> {
         self.0.contains_key(prefix)
     }
 
+    /// Get the prefix stored in the set that is equal to the given prefix, without using longest
+    /// prefix match.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("192.168.1.0/24".parse()?);
+    /// assert_eq!(set.get(&"192.168.1.0/24".parse()?), Some(&"192.168.1.0/24".parse()?));
+    /// assert_eq!(set.get(&"192.168.0.0/23".parse()?), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get<'a>(&'a self, prefix: &P) -> Option<&'a P> {
+        self.0.get_key_value(prefix).map(|(p, _)| p)
+    }
+
+    /// Get the lexicographically smallest prefix of the set.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("192.168.1.0/24".parse()?);
+    /// set.insert("10.0.0.0/8".parse()?);
+    /// assert_eq!(set.first(), Some(&"10.0.0.0/8".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn first(&self) -> Option<&P> {
+        self.0.first().map(|(p, _)| p)
+    }
+
+    /// Get the lexicographically largest prefix of the set.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("192.168.1.0/24".parse()?);
+    /// set.insert("10.0.0.0/8".parse()?);
+    /// assert_eq!(set.last(), Some(&"192.168.1.0/24".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn last(&self) -> Option<&P> {
+        self.0.last().map(|(p, _)| p)
+    }
+
+    /// Remove and return the lexicographically smallest prefix of the set.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("192.168.1.0/24".parse()?);
+    /// set.insert("10.0.0.0/8".parse()?);
+    /// assert_eq!(set.pop_first(), Some("10.0.0.0/8".parse()?));
+    /// assert_eq!(set.first(), Some(&"192.168.1.0/24".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pop_first(&mut self) -> Option<P>
+    where
+        P: Clone,
+    {
+        self.0.pop_first().map(|(p, _)| p)
+    }
+
+    /// Remove and return the lexicographically largest prefix of the set.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("192.168.1.0/24".parse()?);
+    /// set.insert("10.0.0.0/8".parse()?);
+    /// assert_eq!(set.pop_last(), Some("192.168.1.0/24".parse()?));
+    /// assert_eq!(set.last(), Some(&"10.0.0.0/8".parse()?));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pop_last(&mut self) -> Option<P>
+    where
+        P: Clone,
+    {
+        self.0.pop_last().map(|(p, _)| p)
+    }
+
     /// Get the longest prefix in the set that contains the given preifx.
     ///
     /// ```
@@ -100,6 +210,34 @@ impl<P: Prefix> PrefixSet<P> {
         self.0.insert(prefix, ()).is_none()
     }
 
+    /// Insert all addresses of the inclusive `range` into the set. The range is decomposed into the
+    /// smallest list of prefixes that cover it exactly. Nothing is inserted if the range is empty.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # use std::net::Ipv4Addr;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// let start: Ipv4Addr = "10.0.0.1".parse()?;
+    /// let end: Ipv4Addr = "10.0.0.6".parse()?;
+    /// set.insert_range(start.into()..=end.into());
+    /// assert_eq!(
+    ///     set.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         "10.0.0.1/32".parse()?,
+    ///         "10.0.0.2/31".parse()?,
+    ///         "10.0.0.4/31".parse()?,
+    ///         "10.0.0.6/32".parse()?,
+    ///     ]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_range(&mut self, range: RangeInclusive<P::R>) {
+        self.0.insert_range(range, ())
+    }
+
     /// Removes a value from the set. Returns whether the value was present in the set.
     ///
     /// ```
@@ -188,11 +326,111 @@ impl<P: Prefix> PrefixSet<P> {
         self.0.clear()
     }
 
+    /// Get the number of nodes the set can hold without reallocating. See
+    /// [`PrefixMap::capacity`].
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    /// Reserve space for at least `additional` more nodes. See [`PrefixMap::reserve`].
+    pub fn reserve(&mut self, additional: usize) {
+        self.0.reserve(additional)
+    }
+
+    /// Shrink the allocated memory as much as possible. See [`PrefixMap::shrink_to_fit`].
+    pub fn shrink_to_fit(&mut self) {
+        self.0.shrink_to_fit()
+    }
+
+    /// Rebuild the node table to only contain the nodes that are needed, and release all remaining
+    /// memory. See [`PrefixMap::compact`].
+    pub fn compact(&mut self) {
+        self.0.compact()
+    }
+
+    /// Pick a prefix of the set uniformly at random, or return `None` if the set is empty. See
+    /// [`PrefixMap::sample`].
+    #[cfg(feature = "rand")]
+    pub fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Option<&P> {
+        self.0.sample(rng).map(|(p, _)| p)
+    }
+
+    /// Pick an address uniformly at random from all addresses that are covered by the set, or
+    /// return `None` if the set is empty. See [`PrefixMap::sample_addr`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("10.0.0.0/30".parse()?);
+    /// let addr = set.sample_addr(&mut rand::thread_rng()).unwrap();
+    /// assert!((0x0a00_0000..=0x0a00_0003).contains(&addr));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn sample_addr<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Option<P::R> {
+        self.0.sample_addr(rng)
+    }
+
+    /// Check that the internal tree structure of the set is consistent, and panic otherwise. See
+    /// [`PrefixMap::assert_invariants`].
+    #[cfg(any(test, feature = "test-utils"))]
+    #[track_caller]
+    pub fn assert_invariants(&self)
+    where
+        P: core::fmt::Debug,
+    {
+        self.0.assert_invariants()
+    }
+
+    /// Take all prefixes out of the set, leaving it empty, and return an owned iterator over them
+    /// in lexicographic order. The iterator does not borrow the set, so the set can be refilled
+    /// immediately. See [`PrefixMap::take_all`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("192.168.0.0/24".parse()?);
+    /// let drained = set.take_all();
+    /// set.insert("192.168.1.0/24".parse()?);
+    /// assert_eq!(drained.collect::<Vec<_>>(), vec!["192.168.0.0/24".parse()?]);
+    /// assert_eq!(set.into_iter().collect::<Vec<_>>(), vec!["192.168.1.0/24".parse()?]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_all(&mut self) -> IntoIter<P> {
+        IntoIter(self.0.take_all())
+    }
+
     /// Iterate over all prefixes in the set
     pub fn iter(&self) -> Iter<'_, P> {
         self.into_iter()
     }
 
+    /// Iterate over all prefixes in the set, converted into another prefix type `Q` with the same
+    /// representation. See [`PrefixMap::keys_mapped`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut set: PrefixSet<(u32, u8)> = PrefixSet::new();
+    /// set.insert((0xc0a8_0000, 24));
+    /// assert_eq!(
+    ///     set.iter_mapped::<Ipv4Net>().collect::<Vec<_>>(),
+    ///     vec!["192.168.0.0/24".parse()?]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_mapped<Q: Prefix<R = P::R>>(&self) -> crate::map::KeysMapped<'_, P, (), Q> {
+        self.0.keys_mapped()
+    }
+
     /// Keep only the elements in the map that satisfy the given condition `f`.
     ///
     /// ```
@@ -333,12 +571,21 @@ where
     P: Prefix + PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.iter().zip(other.iter()).all(|(a, b)| a == b)
+        self.iter().eq(other.iter())
     }
 }
 
 impl<P> Eq for PrefixSet<P> where P: Prefix + Eq {}
 
+impl<P> core::hash::Hash for PrefixSet<P>
+where
+    P: Prefix + core::hash::Hash,
+{
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
 #[derive(Clone)]
 /// An iterator over all entries of a [`PrefixSet`] in lexicographic order.
 pub struct Iter<'a, P>(crate::map::Iter<'a, P, ()>);
@@ -351,6 +598,12 @@ impl<'a, P: Prefix> Iterator for Iter<'a, P> {
     }
 }
 
+impl<'a, P: Prefix> DoubleEndedIterator for Iter<'a, P> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(p, _)| p)
+    }
+}
+
 #[derive(Clone)]
 /// A consuming iterator over all entries of a [`PrefixSet`] in lexicographic order.
 pub struct IntoIter<P>(crate::map::IntoIter<P, ()>);
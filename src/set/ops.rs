@@ -0,0 +1,172 @@
+//! Operator implementations for [`PrefixSet`].
+//!
+//! All operators work on the elements of the sets (like [`alloc::collections::BTreeSet`]), not on
+//! the address space they cover. For instance, `a & b` contains only the prefixes that are
+//! elements of both `a` and `b`.
+
+use core::ops::{BitAnd, BitOr, BitXor, Sub};
+
+use super::*;
+
+impl<P: Prefix + Clone> BitOr<&PrefixSet<P>> for &PrefixSet<P> {
+    type Output = PrefixSet<P>;
+
+    /// Returns the union of `self` and `rhs` as a new set.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a: PrefixSet<Ipv4Net> = ["10.0.0.0/8".parse()?].into_iter().collect();
+    /// let b: PrefixSet<Ipv4Net> = ["10.0.0.0/16".parse()?].into_iter().collect();
+    /// assert_eq!(
+    ///     (&a | &b).into_iter().collect::<Vec<_>>(),
+    ///     vec!["10.0.0.0/8".parse()?, "10.0.0.0/16".parse()?]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn bitor(self, rhs: &PrefixSet<P>) -> PrefixSet<P> {
+        self.union(rhs).cloned().collect()
+    }
+}
+
+impl<P: Prefix + Clone> BitAnd<&PrefixSet<P>> for &PrefixSet<P> {
+    type Output = PrefixSet<P>;
+
+    /// Returns the intersection of `self` and `rhs` as a new set.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a: PrefixSet<Ipv4Net> = ["10.0.0.0/8".parse()?, "10.0.0.0/16".parse()?]
+    ///     .into_iter()
+    ///     .collect();
+    /// let b: PrefixSet<Ipv4Net> = ["10.0.0.0/16".parse()?].into_iter().collect();
+    /// assert_eq!(
+    ///     (&a & &b).into_iter().collect::<Vec<_>>(),
+    ///     vec!["10.0.0.0/16".parse()?]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn bitand(self, rhs: &PrefixSet<P>) -> PrefixSet<P> {
+        self.intersection(rhs).cloned().collect()
+    }
+}
+
+impl<P: Prefix + Clone> Sub<&PrefixSet<P>> for &PrefixSet<P> {
+    type Output = PrefixSet<P>;
+
+    /// Returns the difference of `self` and `rhs` as a new set.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a: PrefixSet<Ipv4Net> = ["10.0.0.0/8".parse()?, "10.0.0.0/16".parse()?]
+    ///     .into_iter()
+    ///     .collect();
+    /// let b: PrefixSet<Ipv4Net> = ["10.0.0.0/16".parse()?].into_iter().collect();
+    /// assert_eq!(
+    ///     (&a - &b).into_iter().collect::<Vec<_>>(),
+    ///     vec!["10.0.0.0/8".parse()?]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn sub(self, rhs: &PrefixSet<P>) -> PrefixSet<P> {
+        self.difference(rhs).cloned().collect()
+    }
+}
+
+impl<P: Prefix + Clone> BitXor<&PrefixSet<P>> for &PrefixSet<P> {
+    type Output = PrefixSet<P>;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new set.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a: PrefixSet<Ipv4Net> = ["10.0.0.0/8".parse()?, "10.0.0.0/16".parse()?]
+    ///     .into_iter()
+    ///     .collect();
+    /// let b: PrefixSet<Ipv4Net> = ["10.0.0.0/16".parse()?, "10.0.0.0/24".parse()?]
+    ///     .into_iter()
+    ///     .collect();
+    /// assert_eq!(
+    ///     (&a ^ &b).into_iter().collect::<Vec<_>>(),
+    ///     vec!["10.0.0.0/8".parse()?, "10.0.0.0/24".parse()?]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn bitxor(self, rhs: &PrefixSet<P>) -> PrefixSet<P> {
+        self.difference(rhs)
+            .chain(rhs.difference(self))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<P: Prefix> BitOr<PrefixSet<P>> for PrefixSet<P> {
+    type Output = PrefixSet<P>;
+
+    /// Returns the union of `self` and `rhs`, reusing the memory of `self`.
+    fn bitor(mut self, rhs: PrefixSet<P>) -> PrefixSet<P> {
+        for p in rhs {
+            self.insert(p);
+        }
+        self
+    }
+}
+
+impl<P: Prefix> BitAnd<PrefixSet<P>> for PrefixSet<P> {
+    type Output = PrefixSet<P>;
+
+    /// Returns the intersection of `self` and `rhs`, reusing the memory of `self`.
+    fn bitand(mut self, rhs: PrefixSet<P>) -> PrefixSet<P> {
+        self.retain(|p| rhs.contains(p));
+        self
+    }
+}
+
+impl<P: Prefix> Sub<PrefixSet<P>> for PrefixSet<P> {
+    type Output = PrefixSet<P>;
+
+    /// Returns the difference of `self` and `rhs`, reusing the memory of `self`.
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let set = |s: &[&str]| s.iter().map(|p| p.parse::<Ipv4Net>().unwrap()).collect::<PrefixSet<_>>();
+    /// let base = set(&["10.0.0.0/8", "192.168.0.0/16"]);
+    /// let extra = set(&["172.16.0.0/12"]);
+    /// let banned = set(&["192.168.0.0/16"]);
+    /// let allowed = (base | extra) - banned;
+    /// assert_eq!(allowed, set(&["10.0.0.0/8", "172.16.0.0/12"]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn sub(mut self, rhs: PrefixSet<P>) -> PrefixSet<P> {
+        self.retain(|p| !rhs.contains(p));
+        self
+    }
+}
+
+impl<P: Prefix> BitXor<PrefixSet<P>> for PrefixSet<P> {
+    type Output = PrefixSet<P>;
+
+    /// Returns the symmetric difference of `self` and `rhs`, reusing the memory of `self`.
+    fn bitxor(mut self, rhs: PrefixSet<P>) -> PrefixSet<P> {
+        for p in rhs {
+            if !self.remove(&p) {
+                self.insert(p);
+            }
+        }
+        self
+    }
+}
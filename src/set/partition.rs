@@ -0,0 +1,107 @@
+//! Check whether a set of prefixes exactly tiles a prefix.
+
+use num_traits::One;
+
+use crate::prefix::{first_prefix_in_range, last_address};
+
+use super::*;
+
+/// The reason why a [`PrefixSet`] is not a partition of a prefix. See
+/// [`PrefixSet::is_partition_of`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitionError<P> {
+    /// The prefix is not covered entirely. The contained prefix is the largest prefix at the start
+    /// of the first gap that is not covered by any element in the set.
+    Gap(P),
+    /// Two elements in the set overlap, i.e., `covering` contains `covered`.
+    Overlap {
+        /// The element that contains `covered`.
+        covering: P,
+        /// The element that is contained within `covering`.
+        covered: P,
+    },
+    /// An element of the set is not contained within the prefix.
+    Outside(P),
+}
+
+impl<P: core::fmt::Display> core::fmt::Display for PartitionError<P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PartitionError::Gap(p) => write!(f, "Prefix {p} is not covered"),
+            PartitionError::Overlap { covering, covered } => {
+                write!(f, "Prefix {covering} overlaps with {covered}")
+            }
+            PartitionError::Outside(p) => write!(f, "Prefix {p} is outside of the partition"),
+        }
+    }
+}
+
+impl<P: core::fmt::Debug + core::fmt::Display> core::error::Error for PartitionError<P> {}
+
+impl<P: Prefix> PrefixSet<P> {
+    /// Check if the elements of the set exactly tile `prefix`, i.e., every address of `prefix` is
+    /// covered by exactly one element, and all elements are contained within `prefix`. Otherwise,
+    /// the first problem (in lexicographic order) is returned as a [`PartitionError`].
+    ///
+    /// ```
+    /// # use prefix_trie::*;
+    /// # use prefix_trie::set::PartitionError;
+    /// # use ipnet::Ipv4Net;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let prefix: Ipv4Net = "192.168.0.0/22".parse()?;
+    /// let mut set: PrefixSet<Ipv4Net> = PrefixSet::new();
+    /// set.insert("192.168.0.0/23".parse()?);
+    /// set.insert("192.168.3.0/24".parse()?);
+    /// assert_eq!(
+    ///     set.is_partition_of(&prefix),
+    ///     Err(PartitionError::Gap("192.168.2.0/24".parse()?))
+    /// );
+    /// set.insert("192.168.2.0/24".parse()?);
+    /// assert_eq!(set.is_partition_of(&prefix), Ok(()));
+    /// set.insert("192.168.2.128/25".parse()?);
+    /// assert_eq!(
+    ///     set.is_partition_of(&prefix),
+    ///     Err(PartitionError::Overlap {
+    ///         covering: "192.168.2.0/24".parse()?,
+    ///         covered: "192.168.2.128/25".parse()?,
+    ///     })
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_partition_of(&self, prefix: &P) -> Result<(), PartitionError<P>>
+    where
+        P: Clone,
+    {
+        let end = last_address(prefix);
+        // the first address that is not yet covered, or `None` if the entire prefix is covered.
+        let mut next = Some(prefix.mask());
+        let mut last: Option<&P> = None;
+        for p in self.iter() {
+            if !prefix.contains(p) {
+                return Err(PartitionError::Outside(p.clone()));
+            }
+            if let Some(last) = last.filter(|last| last.contains(p)) {
+                return Err(PartitionError::Overlap {
+                    covering: last.clone(),
+                    covered: p.clone(),
+                });
+            }
+            // `p` does not overlap with any previous element, so it starts after `next`.
+            let start = p.mask();
+            if let Some(next) = next.filter(|next| *next < start) {
+                return Err(PartitionError::Gap(first_prefix_in_range(
+                    next,
+                    start - P::R::one(),
+                )));
+            }
+            let p_end = last_address(p);
+            next = (p_end < end).then(|| p_end + P::R::one());
+            last = Some(p);
+        }
+        match next {
+            Some(next) => Err(PartitionError::Gap(first_prefix_in_range(next, end))),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use alloc::{vec, vec::Vec};
+
 use crate::{to_right, Prefix, PrefixMap};
 
 #[derive(Clone)]
@@ -29,14 +31,14 @@ impl<'a, P: Prefix> Iterator for Union<'a, P> {
                     self.nodes.extend(next_indices(
                         self.set_a,
                         self.set_b,
-                        node_a.right,
-                        node_b.right,
+                        node_a.right(),
+                        node_b.right(),
                     ));
                     self.nodes.extend(next_indices(
                         self.set_a,
                         self.set_b,
-                        node_a.left,
-                        node_b.left,
+                        node_a.left(),
+                        node_b.left(),
                     ));
                     if node_a.value.is_some() || node_b.value.is_some() {
                         return Some(&node_a.prefix);
@@ -48,8 +50,8 @@ impl<'a, P: Prefix> Iterator for Union<'a, P> {
                         self.set_a,
                         self.set_b,
                         a,
-                        node_a.left,
-                        node_a.right,
+                        node_a.left(),
+                        node_a.right(),
                         b,
                     ));
                     if node_a.value.is_some() {
@@ -63,8 +65,8 @@ impl<'a, P: Prefix> Iterator for Union<'a, P> {
                         self.set_b,
                         a,
                         b,
-                        node_b.left,
-                        node_b.right,
+                        node_b.left(),
+                        node_b.right(),
                     ));
                     if node_b.value.is_some() {
                         return Some(&node_b.prefix);
@@ -72,10 +74,10 @@ impl<'a, P: Prefix> Iterator for Union<'a, P> {
                 }
                 UnionIndex::OnlyA(a) => {
                     let node_a = &self.set_a.table[a];
-                    if let Some(right) = node_a.right {
+                    if let Some(right) = node_a.right() {
                         self.nodes.push(UnionIndex::OnlyA(right));
                     }
-                    if let Some(left) = node_a.left {
+                    if let Some(left) = node_a.left() {
                         self.nodes.push(UnionIndex::OnlyA(left));
                     }
                     if node_a.value.is_some() {
@@ -84,10 +86,10 @@ impl<'a, P: Prefix> Iterator for Union<'a, P> {
                 }
                 UnionIndex::OnlyB(b) => {
                     let node_b = &self.set_b.table[b];
-                    if let Some(right) = node_b.right {
+                    if let Some(right) = node_b.right() {
                         self.nodes.push(UnionIndex::OnlyB(right));
                     }
-                    if let Some(left) = node_b.left {
+                    if let Some(left) = node_b.left() {
                         self.nodes.push(UnionIndex::OnlyB(left));
                     }
                     if node_b.value.is_some() {
@@ -114,13 +116,13 @@ fn next_indices<'a, P: Prefix>(
             let p_b = &set_b.table[b].prefix;
             if p_a.prefix_len() == p_b.prefix_len() {
                 match p_a.mask().cmp(&p_b.mask()) {
-                    std::cmp::Ordering::Less => {
+                    core::cmp::Ordering::Less => {
                         vec![UnionIndex::OnlyB(b), UnionIndex::OnlyA(a)]
                     }
-                    std::cmp::Ordering::Equal => {
+                    core::cmp::Ordering::Equal => {
                         vec![UnionIndex::Both(a, b)]
                     }
-                    std::cmp::Ordering::Greater => {
+                    core::cmp::Ordering::Greater => {
                         vec![UnionIndex::OnlyA(a), UnionIndex::OnlyB(b)]
                     }
                 }
@@ -0,0 +1,442 @@
+//! Lockstep validation of a prefix map implementation against a simple reference implementation.
+//!
+//! A [`ShadowedPrefixMap`] applies every operation both to a prefix map and to a sorted vector of
+//! key-value pairs, and panics as soon as both implementations disagree. This is much slower than
+//! the prefix map itself (most operations on the reference are `O(n)`), and is meant to be used
+//! temporarily, e.g., in a staging environment, to gain confidence in the tree.
+//!
+//! The validated map can be any [`Backend`]: a [`PrefixMap`] (the default), a
+//! [`FrozenPrefixMap`], a [`PersistentPrefixMap`], or (with the `concurrent` feature) the writer
+//! and reader of a concurrent map.
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use crate::map::FrozenPrefixMap;
+use crate::persistent::PersistentPrefixMap;
+use crate::{Prefix, PrefixMap};
+
+/// A prefix map implementation that can be validated by a [`ShadowedPrefixMap`]. Lookups return
+/// owned values, such that implementations can answer them from behind a guard.
+pub trait Backend<P, T> {
+    /// Get the value of an element by matching exactly on the prefix.
+    fn get(&self, prefix: &P) -> Option<T>;
+
+    /// Get the longest prefix in the map that contains `prefix`, and its value.
+    fn get_lpm(&self, prefix: &P) -> Option<(P, T)>;
+
+    /// Get all entries in lexicographic order.
+    fn entries(&self) -> Vec<(P, T)>;
+
+    /// Insert a new item into the map, returning the value that existed before. The change must
+    /// be visible to all subsequent lookups.
+    fn insert(&mut self, prefix: P, value: T) -> Option<T>;
+
+    /// Remove a key from the map, returning its value. The change must be visible to all
+    /// subsequent lookups.
+    fn remove(&mut self, prefix: &P) -> Option<T>;
+}
+
+impl<P: Prefix + Clone, T: Clone> Backend<P, T> for PrefixMap<P, T> {
+    fn get(&self, prefix: &P) -> Option<T> {
+        PrefixMap::get(self, prefix).cloned()
+    }
+
+    fn get_lpm(&self, prefix: &P) -> Option<(P, T)> {
+        PrefixMap::get_lpm(self, prefix).map(|(p, t)| (p.clone(), t.clone()))
+    }
+
+    fn entries(&self) -> Vec<(P, T)> {
+        self.iter().map(|(p, t)| (p.clone(), t.clone())).collect()
+    }
+
+    fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        PrefixMap::insert(self, prefix, value)
+    }
+
+    fn remove(&mut self, prefix: &P) -> Option<T> {
+        PrefixMap::remove(self, prefix)
+    }
+}
+
+/// A frozen map cannot be modified, so [`Backend::insert`] and [`Backend::remove`] rebuild and
+/// freeze the map again, which takes `O(n)` time.
+impl<P: Prefix + Clone, T: Clone> Backend<P, T> for FrozenPrefixMap<P, T> {
+    fn get(&self, prefix: &P) -> Option<T> {
+        FrozenPrefixMap::get(self, prefix).cloned()
+    }
+
+    fn get_lpm(&self, prefix: &P) -> Option<(P, T)> {
+        FrozenPrefixMap::get_lpm(self, prefix).map(|(p, t)| (p.clone(), t.clone()))
+    }
+
+    fn entries(&self) -> Vec<(P, T)> {
+        self.iter().map(|(p, t)| (p.clone(), t.clone())).collect()
+    }
+
+    fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let mut map: PrefixMap<P, T> = self.entries().into_iter().collect();
+        let old = map.insert(prefix, value);
+        *self = map.freeze();
+        old
+    }
+
+    fn remove(&mut self, prefix: &P) -> Option<T> {
+        let mut map: PrefixMap<P, T> = self.entries().into_iter().collect();
+        let old = map.remove(prefix);
+        *self = map.freeze();
+        old
+    }
+}
+
+impl<P: Prefix + Clone, T: Clone> Backend<P, T> for PersistentPrefixMap<P, T> {
+    fn get(&self, prefix: &P) -> Option<T> {
+        PersistentPrefixMap::get(self, prefix).cloned()
+    }
+
+    fn get_lpm(&self, prefix: &P) -> Option<(P, T)> {
+        PersistentPrefixMap::get_lpm(self, prefix).map(|(p, t)| (p.clone(), t.clone()))
+    }
+
+    fn entries(&self) -> Vec<(P, T)> {
+        self.iter().map(|(p, t)| (p.clone(), t.clone())).collect()
+    }
+
+    fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        self.insert_mut(prefix, value)
+    }
+
+    fn remove(&mut self, prefix: &P) -> Option<T> {
+        self.remove_mut(prefix)
+    }
+}
+
+/// The writer and a reader of a concurrent map. Every change is published immediately, and all
+/// lookups go through the reader.
+#[cfg(feature = "concurrent")]
+impl<P, T> Backend<P, T>
+    for (
+        crate::concurrent::WriteHandle<P, T>,
+        crate::concurrent::ReadHandle<P, T>,
+    )
+where
+    P: Prefix + Clone,
+    T: Clone,
+{
+    fn get(&self, prefix: &P) -> Option<T> {
+        self.1.read().get(prefix).cloned()
+    }
+
+    fn get_lpm(&self, prefix: &P) -> Option<(P, T)> {
+        let map = self.1.read();
+        map.get_lpm(prefix).map(|(p, t)| (p.clone(), t.clone()))
+    }
+
+    fn entries(&self) -> Vec<(P, T)> {
+        let map = self.1.read();
+        map.iter().map(|(p, t)| (p.clone(), t.clone())).collect()
+    }
+
+    fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let old = self.0.insert(prefix, value);
+        self.0.publish();
+        old
+    }
+
+    fn remove(&mut self, prefix: &P) -> Option<T> {
+        let old = self.0.remove(prefix);
+        self.0.publish();
+        old
+    }
+}
+
+/// A prefix map that mirrors all operations on a reference implementation and asserts that both
+/// produce the same results. The validated map `M` is a [`PrefixMap`] by default, and can be any
+/// [`Backend`]. Lookups return references into the reference implementation, after asserting that
+/// the map returned the same.
+///
+/// ```
+/// # use prefix_trie::shadow::ShadowedPrefixMap;
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: ShadowedPrefixMap<Ipv4Net, _> = ShadowedPrefixMap::new();
+/// pm.insert("192.168.0.0/23".parse()?, 1);
+/// pm.insert("192.168.1.0/24".parse()?, 2);
+/// assert_eq!(pm.get_lpm(&"192.168.1.1/32".parse()?), Some((&"192.168.1.0/24".parse()?, &2)));
+/// assert_eq!(pm.remove(&"192.168.1.0/24".parse()?), Some(2));
+/// assert_eq!(pm.get_lpm(&"192.168.1.1/32".parse()?), Some((&"192.168.0.0/23".parse()?, &1)));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Other backends are validated in the same way:
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use prefix_trie::shadow::ShadowedPrefixMap;
+/// # use prefix_trie::persistent::PersistentPrefixMap;
+/// # use ipnet::Ipv4Net;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut pm: ShadowedPrefixMap<Ipv4Net, _, PersistentPrefixMap<_, _>> = ShadowedPrefixMap::new();
+/// pm.insert("192.168.0.0/23".parse()?, 1);
+/// assert_eq!(pm.get(&"192.168.0.0/23".parse()?), Some(&1));
+///
+/// let frozen = PrefixMap::from_iter([("10.0.0.0/8".parse::<Ipv4Net>()?, 1)]).freeze();
+/// let pm = ShadowedPrefixMap::with_backend(frozen);
+/// assert_eq!(pm.get_lpm(&"10.1.1.1/32".parse()?), Some((&"10.0.0.0/8".parse()?, &1)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ShadowedPrefixMap<P, T, M = PrefixMap<P, T>> {
+    map: M,
+    reference: Vec<(P, T)>,
+}
+
+impl<P, T, M> Default for ShadowedPrefixMap<P, T, M>
+where
+    M: Default,
+{
+    fn default() -> Self {
+        Self {
+            map: M::default(),
+            reference: Vec::new(),
+        }
+    }
+}
+
+impl<P, T, M> ShadowedPrefixMap<P, T, M>
+where
+    P: Prefix + Clone + PartialEq + Debug,
+    T: Clone + PartialEq + Debug,
+    M: Backend<P, T>,
+{
+    /// Create an empty shadowed prefix map.
+    pub fn new() -> Self
+    where
+        M: Default,
+    {
+        Self::default()
+    }
+
+    /// Validate `map`, which may already contain entries. The reference is initialized with the
+    /// entries of `map`.
+    pub fn with_backend(map: M) -> Self {
+        let reference = map.entries();
+        assert!(
+            reference.windows(2).all(
+                |w| (w[0].0.mask(), w[0].0.prefix_len()) < (w[1].0.mask(), w[1].0.prefix_len())
+            ),
+            "ShadowedPrefixMap: entries are not in lexicographic order: {reference:?}"
+        );
+        Self { map, reference }
+    }
+
+    /// Get the value of an element by matching exactly on the prefix. See [`PrefixMap::get`].
+    pub fn get(&self, prefix: &P) -> Option<&T> {
+        let exp = self.ref_position(prefix).ok().map(|i| &self.reference[i].1);
+        let acq = self.map.get(prefix);
+        assert_eq!(
+            acq.as_ref(),
+            exp,
+            "ShadowedPrefixMap: `get({prefix:?})` diverged"
+        );
+        exp
+    }
+
+    /// Check if a key is present in the map. See [`PrefixMap::contains_key`].
+    pub fn contains_key(&self, prefix: &P) -> bool {
+        self.get(prefix).is_some()
+    }
+
+    /// Get a value of an element by using longest prefix matching. See [`PrefixMap::get_lpm`].
+    pub fn get_lpm(&self, prefix: &P) -> Option<(&P, &T)> {
+        let exp = self
+            .reference
+            .iter()
+            .filter(|(p, _)| p.contains(prefix))
+            .max_by_key(|(p, _)| p.prefix_len())
+            .map(|(p, t)| (p, t));
+        let acq = self.map.get_lpm(prefix);
+        assert_eq!(
+            acq.as_ref().map(|(p, t)| (p, t)),
+            exp,
+            "ShadowedPrefixMap: `get_lpm({prefix:?})` diverged"
+        );
+        exp
+    }
+
+    /// Insert a new item into the map, returning the value that existed before. See
+    /// [`PrefixMap::insert`].
+    pub fn insert(&mut self, prefix: P, value: T) -> Option<T> {
+        let exp = match self.ref_position(&prefix) {
+            Ok(i) => Some(core::mem::replace(&mut self.reference[i].1, value.clone())),
+            Err(i) => {
+                self.reference.insert(i, (prefix.clone(), value.clone()));
+                None
+            }
+        };
+        let acq = self.map.insert(prefix.clone(), value);
+        assert_eq!(
+            acq, exp,
+            "ShadowedPrefixMap: `insert({prefix:?}, ..)` diverged"
+        );
+        self.check_iter();
+        acq
+    }
+
+    /// Remove a key from the map, returning its value. See [`PrefixMap::remove`].
+    pub fn remove(&mut self, prefix: &P) -> Option<T> {
+        let exp = self
+            .ref_position(prefix)
+            .ok()
+            .map(|i| self.reference.remove(i).1);
+        let acq = self.map.remove(prefix);
+        assert_eq!(acq, exp, "ShadowedPrefixMap: `remove({prefix:?})` diverged");
+        self.check_iter();
+        acq
+    }
+
+    /// Get a reference to the validated map.
+    pub fn map(&self) -> &M {
+        &self.map
+    }
+
+    /// Consume `self` and return the validated map.
+    pub fn into_inner(self) -> M {
+        self.map
+    }
+
+    /// Search the position of `prefix` in the reference vector.
+    fn ref_position(&self, prefix: &P) -> Result<usize, usize> {
+        self.reference
+            .binary_search_by_key(&(prefix.mask(), prefix.prefix_len()), |(p, _)| {
+                (p.mask(), p.prefix_len())
+            })
+    }
+
+    /// Make sure that iterating over the map yields the same sequence as the reference.
+    fn check_iter(&self) {
+        let acq = self.map.entries();
+        assert!(
+            acq == self.reference,
+            "ShadowedPrefixMap: iteration diverged\nmap: {acq:?}\nreference: {:?}",
+            self.reference,
+        );
+    }
+}
+
+/// Operations that only a [`PrefixMap`] supports.
+impl<P, T> ShadowedPrefixMap<P, T>
+where
+    P: Prefix + Clone + PartialEq + Debug,
+    T: Clone + PartialEq + Debug,
+{
+    /// Get a value of an element by using shortest prefix matching. See [`PrefixMap::get_spm`].
+    pub fn get_spm(&self, prefix: &P) -> Option<(&P, &T)> {
+        let exp = self
+            .reference
+            .iter()
+            .filter(|(p, _)| p.contains(prefix))
+            .min_by_key(|(p, _)| p.prefix_len())
+            .map(|(p, t)| (p, t));
+        let acq = self.map.get_spm(prefix);
+        assert_eq!(
+            acq, exp,
+            "ShadowedPrefixMap: `get_spm({prefix:?})` diverged"
+        );
+        acq
+    }
+
+    /// Remove a key from the map while keeping the tree structure. See
+    /// [`PrefixMap::remove_keep_tree`].
+    pub fn remove_keep_tree(&mut self, prefix: &P) -> Option<T> {
+        let exp = self
+            .ref_position(prefix)
+            .ok()
+            .map(|i| self.reference.remove(i).1);
+        let acq = self.map.remove_keep_tree(prefix);
+        assert_eq!(
+            acq, exp,
+            "ShadowedPrefixMap: `remove_keep_tree({prefix:?})` diverged"
+        );
+        self.check_iter();
+        acq
+    }
+
+    /// Remove all entries that are contained within `prefix`. See [`PrefixMap::remove_children`].
+    pub fn remove_children(&mut self, prefix: &P) {
+        self.reference.retain(|(p, _)| !prefix.contains(p));
+        self.map.remove_children(prefix);
+        self.check_iter();
+    }
+
+    /// Keep only the elements in the map that satisfy the given condition `f`. See
+    /// [`PrefixMap::retain`].
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&P, &T) -> bool,
+    {
+        let mut keep = Vec::new();
+        self.map.retain(|p, t| {
+            let k = f(p, t);
+            if k {
+                keep.push((p.mask(), p.prefix_len()));
+            }
+            k
+        });
+        // The map does not visit its entries in lexicographic order. Sort the decisions once and
+        // merge them with the (sorted) reference.
+        keep.sort_unstable();
+        let mut keep = keep.into_iter().peekable();
+        self.reference
+            .retain(|(p, _)| keep.next_if_eq(&(p.mask(), p.prefix_len())).is_some());
+        self.check_iter();
+    }
+
+    /// Clear the map. See [`PrefixMap::clear`].
+    pub fn clear(&mut self) {
+        self.reference.clear();
+        self.map.clear();
+        self.check_iter();
+    }
+
+    /// Iterate over all elements in lexicographic order. The elements are compared against the
+    /// reference before the iterator is returned.
+    pub fn iter(&self) -> crate::map::Iter<'_, P, T> {
+        self.check_iter();
+        self.map.iter()
+    }
+
+    /// Iterate over all elements contained within `prefix`. The elements are compared against the
+    /// reference before the iterator is returned. See [`PrefixMap::children`].
+    pub fn children(&self, prefix: &P) -> crate::map::Iter<'_, P, T> {
+        let exp = self
+            .reference
+            .iter()
+            .filter(|(p, _)| prefix.contains(p))
+            .map(|(p, t)| (p, t))
+            .collect::<Vec<_>>();
+        let acq = self.map.children(prefix).collect::<Vec<_>>();
+        assert_eq!(
+            acq, exp,
+            "ShadowedPrefixMap: `children({prefix:?})` diverged"
+        );
+        self.map.children(prefix)
+    }
+}
+
+impl<P, T, M> FromIterator<(P, T)> for ShadowedPrefixMap<P, T, M>
+where
+    P: Prefix + Clone + PartialEq + Debug,
+    T: Clone + PartialEq + Debug,
+    M: Backend<P, T> + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        iter.into_iter().for_each(|(p, v)| {
+            map.insert(p, v);
+        });
+        map
+    }
+}
@@ -0,0 +1,215 @@
+//! A dual-stack routing table that stores IPv4 and IPv6 prefixes side by side.
+
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+
+use crate::map::{dual_stack_summary, DualStackSummary};
+use crate::PrefixMap;
+
+/// A table keyed by [`IpNet`], which stores IPv4 and IPv6 prefixes in separate [`PrefixMap`]s and
+/// dispatches each operation to the map of the matching address family. IPv4 and IPv6 prefixes
+/// never match each other, e.g., `0.0.0.0/0` does not contain any IPv6 prefix.
+///
+/// ```
+/// # use prefix_trie::*;
+/// # use ipnet::IpNet;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut table: RoutingTable<_> = RoutingTable::new();
+/// table.insert("0.0.0.0/0".parse()?, "v4 default");
+/// table.insert("::/0".parse()?, "v6 default");
+/// table.insert("2001:db8::/32".parse()?, "documentation");
+/// assert_eq!(
+///     table.get_lpm(&"2001:db8::1/128".parse()?),
+///     Some(("2001:db8::/32".parse()?, &"documentation"))
+/// );
+/// assert_eq!(
+///     table.get_lpm(&"192.0.2.1/32".parse()?),
+///     Some(("0.0.0.0/0".parse()?, &"v4 default"))
+/// );
+/// assert_eq!(table.iter().count(), 3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RoutingTable<T> {
+    ipv4: PrefixMap<Ipv4Net, T>,
+    ipv6: PrefixMap<Ipv6Net, T>,
+}
+
+impl<T> Default for RoutingTable<T> {
+    fn default() -> Self {
+        Self {
+            ipv4: PrefixMap::new(),
+            ipv6: PrefixMap::new(),
+        }
+    }
+}
+
+impl<T> RoutingTable<T> {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a table from an IPv4 and an IPv6 map.
+    pub fn from_maps(ipv4: PrefixMap<Ipv4Net, T>, ipv6: PrefixMap<Ipv6Net, T>) -> Self {
+        Self { ipv4, ipv6 }
+    }
+
+    /// Get the value of an element by matching exactly on the prefix.
+    pub fn get(&self, prefix: &IpNet) -> Option<&T> {
+        match prefix {
+            IpNet::V4(p) => self.ipv4.get(p),
+            IpNet::V6(p) => self.ipv6.get(p),
+        }
+    }
+
+    /// Get a mutable reference to the value of an element by matching exactly on the prefix.
+    pub fn get_mut(&mut self, prefix: &IpNet) -> Option<&mut T> {
+        match prefix {
+            IpNet::V4(p) => self.ipv4.get_mut(p),
+            IpNet::V6(p) => self.ipv6.get_mut(p),
+        }
+    }
+
+    /// Check if a key is present in the table.
+    pub fn contains_key(&self, prefix: &IpNet) -> bool {
+        self.get(prefix).is_some()
+    }
+
+    /// Get the value of the longest prefix that contains `prefix`, in the map of the same address
+    /// family.
+    pub fn get_lpm(&self, prefix: &IpNet) -> Option<(IpNet, &T)> {
+        match prefix {
+            IpNet::V4(p) => self.ipv4.get_lpm(p).map(|(p, t)| (IpNet::V4(*p), t)),
+            IpNet::V6(p) => self.ipv6.get_lpm(p).map(|(p, t)| (IpNet::V6(*p), t)),
+        }
+    }
+
+    /// Get a mutable reference to the value of the longest prefix that contains `prefix`, in the
+    /// map of the same address family.
+    pub fn get_lpm_mut(&mut self, prefix: &IpNet) -> Option<(IpNet, &mut T)> {
+        match prefix {
+            IpNet::V4(p) => self.ipv4.get_lpm_mut(p).map(|(p, t)| (IpNet::V4(*p), t)),
+            IpNet::V6(p) => self.ipv6.get_lpm_mut(p).map(|(p, t)| (IpNet::V6(*p), t)),
+        }
+    }
+
+    /// Insert a new item into the table. If the key is already present, the old value is
+    /// replaced and returned.
+    pub fn insert(&mut self, prefix: IpNet, value: T) -> Option<T> {
+        match prefix {
+            IpNet::V4(p) => self.ipv4.insert(p, value),
+            IpNet::V6(p) => self.ipv6.insert(p, value),
+        }
+    }
+
+    /// Remove an element from the table, returning its value.
+    pub fn remove(&mut self, prefix: &IpNet) -> Option<T> {
+        match prefix {
+            IpNet::V4(p) => self.ipv4.remove(p),
+            IpNet::V6(p) => self.ipv6.remove(p),
+        }
+    }
+
+    /// Remove all entries that are contained within `prefix`.
+    pub fn remove_children(&mut self, prefix: &IpNet) {
+        match prefix {
+            IpNet::V4(p) => self.ipv4.remove_children(p),
+            IpNet::V6(p) => self.ipv6.remove_children(p),
+        }
+    }
+
+    /// Only keep the entries for which `f` returns `true`.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(IpNet, &T) -> bool,
+    {
+        self.ipv4.retain(|p, t| f(IpNet::V4(*p), t));
+        self.ipv6.retain(|p, t| f(IpNet::V6(*p), t));
+    }
+
+    /// Remove all entries of both address families.
+    pub fn clear(&mut self) {
+        self.ipv4.clear();
+        self.ipv6.clear();
+    }
+
+    /// Iterate over all entries, first all IPv4 prefixes and then all IPv6 prefixes, each in
+    /// lexicographic order.
+    pub fn iter(&self) -> impl Iterator<Item = (IpNet, &T)> {
+        let ipv4 = self.ipv4.iter().map(|(p, t)| (IpNet::V4(*p), t));
+        let ipv6 = self.ipv6.iter().map(|(p, t)| (IpNet::V6(*p), t));
+        ipv4.chain(ipv6)
+    }
+
+    /// Iterate over all entries with mutable references to the values, first all IPv4 prefixes
+    /// and then all IPv6 prefixes, each in lexicographic order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (IpNet, &mut T)> {
+        let ipv4 = self.ipv4.iter_mut().map(|(p, t)| (IpNet::V4(*p), t));
+        let ipv6 = self.ipv6.iter_mut().map(|(p, t)| (IpNet::V6(*p), t));
+        ipv4.chain(ipv6)
+    }
+
+    /// Get the map of all IPv4 entries.
+    pub fn ipv4(&self) -> &PrefixMap<Ipv4Net, T> {
+        &self.ipv4
+    }
+
+    /// Get the map of all IPv6 entries.
+    pub fn ipv6(&self) -> &PrefixMap<Ipv6Net, T> {
+        &self.ipv6
+    }
+
+    /// Get a mutable reference to the map of all IPv4 entries.
+    pub fn ipv4_mut(&mut self) -> &mut PrefixMap<Ipv4Net, T> {
+        &mut self.ipv4
+    }
+
+    /// Get a mutable reference to the map of all IPv6 entries.
+    pub fn ipv6_mut(&mut self) -> &mut PrefixMap<Ipv6Net, T> {
+        &mut self.ipv6
+    }
+
+    /// Split the table into the IPv4 and the IPv6 map.
+    pub fn into_maps(self) -> (PrefixMap<Ipv4Net, T>, PrefixMap<Ipv6Net, T>) {
+        (self.ipv4, self.ipv6)
+    }
+
+    /// Compute the summary statistics of both address families. See [`dual_stack_summary`].
+    pub fn summary(&self) -> DualStackSummary {
+        dual_stack_summary(&self.ipv4, &self.ipv6)
+    }
+}
+
+impl<T> FromIterator<(IpNet, T)> for RoutingTable<T> {
+    fn from_iter<I: IntoIterator<Item = (IpNet, T)>>(iter: I) -> Self {
+        let mut table = Self::new();
+        table.extend(iter);
+        table
+    }
+}
+
+impl<T> Extend<(IpNet, T)> for RoutingTable<T> {
+    fn extend<I: IntoIterator<Item = (IpNet, T)>>(&mut self, iter: I) {
+        for (p, t) in iter {
+            self.insert(p, t);
+        }
+    }
+}
+
+impl<T> IntoIterator for RoutingTable<T> {
+    type Item = (IpNet, T);
+    type IntoIter = core::iter::Chain<
+        core::iter::Map<crate::map::IntoIter<Ipv4Net, T>, fn((Ipv4Net, T)) -> (IpNet, T)>,
+        core::iter::Map<crate::map::IntoIter<Ipv6Net, T>, fn((Ipv6Net, T)) -> (IpNet, T)>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let ipv4: fn((Ipv4Net, T)) -> (IpNet, T) = |(p, t)| (IpNet::V4(p), t);
+        let ipv6: fn((Ipv6Net, T)) -> (IpNet, T) = |(p, t)| (IpNet::V6(p), t);
+        self.ipv4
+            .into_iter()
+            .map(ipv4)
+            .chain(self.ipv6.into_iter().map(ipv6))
+    }
+}
@@ -1,10 +1,12 @@
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     net::Ipv4Addr,
+    ops::{Bound, RangeInclusive},
 };
 
 use ipnet::Ipv4Net;
 
+use super::mac::{MacPrefix, MacPrefixParseError};
 use super::map::Node;
 use super::*;
 use rand::prelude::*;
@@ -16,6 +18,13 @@ fn ip(s: &str) -> Ipv4Net {
     s.parse().unwrap()
 }
 
+/// Generate a random prefix with a length in `len`, where the address is chosen uniformly at random.
+fn random_prefix(rng: &mut ThreadRng, len: RangeInclusive<u8>) -> Ipv4Net {
+    Ipv4Net::new(rng.gen::<u32>().into(), rng.gen_range(len))
+        .unwrap()
+        .trunc()
+}
+
 struct TestNode {
     prefix: Ipv4Net,
     value: Option<u32>,
@@ -37,19 +46,14 @@ impl TestNode {
         } else {
             map.table.len()
         };
-        map.table.push(Node {
-            prefix: self.prefix,
-            value: self.value,
-            left: None,
-            right: None,
-        });
+        map.table.push(Node::new(self.prefix, self.value));
         if let Some(left) = self.left.take() {
             let left = left.build(map);
-            map.table[idx].left = Some(left);
+            map.table[idx].set_left(Some(left));
         }
         if let Some(right) = self.right.take() {
             let right = right.build(map);
-            map.table[idx].right = Some(right);
+            map.table[idx].set_right(Some(right));
         }
         idx
     }
@@ -580,6 +584,623 @@ fn get_lpm() {
     assert_get_lpm!(pm, "9.0.0.0/8", "0.0.0.0/4", Some(4));
     assert_get_lpm!(pm, "10.0.0.0/8", "0.0.0.0/4", Some(4));
 }
+
+#[test]
+fn get_spm_root() {
+    let mut pm = Map::new();
+    pm.insert(ip("10.0.0.0/8"), 1);
+    assert_eq!(
+        pm.get_spm(&ip("10.1.0.0/16")),
+        Some((&ip("10.0.0.0/8"), &1))
+    );
+    // the value of the root node contains every prefix, and must be preferred.
+    pm.insert(ip("0.0.0.0/0"), 0);
+    assert_eq!(pm.get_spm(&ip("10.1.0.0/16")), Some((&ip("0.0.0.0/0"), &0)));
+    assert_eq!(pm.get_spm(&ip("0.0.0.0/0")), Some((&ip("0.0.0.0/0"), &0)));
+    assert_eq!(
+        pm.get_spm(&ip("192.168.0.0/16")),
+        Some((&ip("0.0.0.0/0"), &0))
+    );
+}
+
+#[test]
+fn transform_values() {
+    let mut pm = Map::new();
+    pm.insert(ip("0.0.0.0/8"), 1);
+    pm.insert(ip("4.0.0.0/8"), 2);
+    pm.insert(ip("8.0.0.0/8"), 3);
+    pm.insert(ip("0.0.0.0/4"), 4);
+    pm.remove_keep_tree(&ip("8.0.0.0/8"));
+    pm.transform_values(|p, v| v * 10 + p.prefix_len() as u32);
+    assert_map!(
+        pm,
+        (
+            "0.0.0.0/0",
+            (
+                "0.0.0.0/4",
+                44,
+                ("0.0.0.0/5", ("0.0.0.0/8", 18), ("4.0.0.0/8", 28)),
+                ("8.0.0.0/8")
+            ),
+            ()
+        )
+    );
+}
+
+#[test]
+fn take_all() {
+    let mut pm = Map::new();
+    pm.insert(ip("0.0.0.0/8"), 1);
+    pm.insert(ip("4.0.0.0/8"), 2);
+    pm.insert(ip("0.0.0.0/4"), 3);
+    let drained = pm.take_all();
+    assert_map!(pm, ("0.0.0.0/0"));
+    pm.insert(ip("8.0.0.0/8"), 4);
+    assert_eq!(
+        drained.collect::<Vec<_>>(),
+        vec![
+            (ip("0.0.0.0/4"), 3),
+            (ip("0.0.0.0/8"), 1),
+            (ip("4.0.0.0/8"), 2)
+        ]
+    );
+    assert_eq!(
+        pm.into_iter().collect::<Vec<_>>(),
+        vec![(ip("8.0.0.0/8"), 4)]
+    );
+}
+
+#[test]
+fn eq_and_hash() {
+    use std::hash::{BuildHasher, RandomState};
+    let hasher = RandomState::new();
+
+    let mut a = Map::new();
+    a.insert(ip("0.0.0.0/8"), 1);
+    a.insert(ip("4.0.0.0/8"), 2);
+    let mut b = Map::new();
+    b.insert(ip("4.0.0.0/8"), 2);
+    b.insert(ip("0.0.0.0/4"), 3);
+    b.insert(ip("0.0.0.0/8"), 1);
+    assert_ne!(a, b);
+    // the branch structure of `b` is different, as it keeps the node for `0.0.0.0/4`.
+    b.remove_keep_tree(&ip("0.0.0.0/4"));
+    assert_eq!(a, b);
+    assert_eq!(hasher.hash_one(&a), hasher.hash_one(&b));
+
+    // one map is a prefix of the other
+    b.insert(ip("8.0.0.0/8"), 4);
+    assert_ne!(a, b);
+    assert_ne!(b, a);
+    let (sa, sb): (Set, Set) = (a.keys().copied().collect(), b.keys().copied().collect());
+    assert_ne!(sa, sb);
+    b.remove(&ip("8.0.0.0/8"));
+    let sb: Set = b.keys().copied().collect();
+    assert_eq!(sa, sb);
+    assert_eq!(hasher.hash_one(&sa), hasher.hash_one(&sb));
+}
+
+#[test]
+fn summary() {
+    let mut pm = Map::new();
+    pm.insert(ip("0.0.0.0/8"), 1);
+    pm.insert(ip("4.0.0.0/8"), 2);
+    pm.insert(ip("0.0.0.0/4"), 3);
+    pm.insert(ip("1.2.3.4/32"), 4);
+    let summary = pm.summary();
+    assert_eq!(summary.entries, 4);
+    assert_eq!(summary.covered_addresses, 1 << 28);
+    let mut histogram = vec![0; 33];
+    histogram[4] = 1;
+    histogram[8] = 2;
+    histogram[32] = 1;
+    assert_eq!(summary.length_histogram, histogram);
+    assert!(summary.memory_bytes >= pm.table.len() * std::mem::size_of::<Node<Ipv4Net, u32>>());
+}
+
+#[test]
+fn stats() {
+    let mut pm = Map::new();
+    pm.insert(ip("0.0.0.0/8"), 1);
+    pm.insert(ip("4.0.0.0/8"), 2);
+    pm.insert(ip("0.0.0.0/4"), 3);
+    pm.insert(ip("1.2.3.4/32"), 4);
+    let stats = pm.stats();
+    assert_eq!(stats.value_nodes, 4);
+    // the root, 0.0.0.0/5, and 0.0.0.0/7
+    assert_eq!(stats.branch_nodes, 3);
+    assert_eq!(stats.free_slots, 0);
+    assert_eq!(stats.slots, pm.table.len());
+    assert_eq!(stats.max_depth, 4);
+    assert_eq!(stats.memory_bytes, pm.summary().memory_bytes);
+
+    pm.remove(&ip("4.0.0.0/8"));
+    let stats = pm.stats();
+    assert_eq!(stats.value_nodes, 3);
+    assert_eq!(stats.branch_nodes, 2);
+    assert_eq!(stats.free_slots, 2);
+    assert_eq!(stats.max_depth, 3);
+    pm.compact();
+    assert_eq!(pm.stats().free_slots, 0);
+}
+
+#[test]
+fn remove_frees_branch() {
+    let mut pm = Map::new();
+    pm.insert(ip("10.0.0.0/24"), 1);
+    pm.insert(ip("10.0.1.0/24"), 2);
+    assert_eq!(pm.table.len(), 4);
+    // removing a leaf also removes its parent branch node, whose slot must be freed.
+    pm.remove(&ip("10.0.1.0/24"));
+    let stats = pm.stats();
+    assert_eq!(stats.branch_nodes, 1);
+    assert_eq!(stats.free_slots, 2);
+    // re-inserting the leaf reuses both slots.
+    for _ in 0..10 {
+        pm.insert(ip("10.0.1.0/24"), 2);
+        pm.remove(&ip("10.0.1.0/24"));
+    }
+    assert_eq!(pm.table.len(), 4);
+    pm.assert_invariants();
+}
+
+#[test]
+fn retain_removed_branch() {
+    let mut pm = Map::new();
+    pm.insert(ip("10.0.0.0/24"), 1);
+    pm.insert(ip("10.0.1.0/24"), 2);
+    // removing the left leaf removes the branch node 10.0.0.0/23, but its right child must still
+    // be visited.
+    pm.retain(|_, _| false);
+    assert_eq!(pm.iter().count(), 0);
+    pm.assert_invariants();
+
+    pm.insert(ip("10.0.0.0/24"), 1);
+    pm.insert(ip("10.0.1.0/24"), 2);
+    pm.insert(ip("10.0.1.128/25"), 3);
+    pm.retain(|_, t| *t == 3);
+    assert_iter!(pm, ("10.0.1.128/25", 3));
+    pm.assert_invariants();
+}
+
+#[test]
+#[should_panic(expected = "stored on the wrong side")]
+fn assert_invariants_wrong_side() {
+    let mut pm = Map::new();
+    pm.insert(ip("10.0.0.0/8"), 1);
+    let child = pm.table[0].set_left(None);
+    pm.table[0].set_right(child);
+    pm.assert_invariants();
+}
+
+#[test]
+#[should_panic(expected = "has host bits set")]
+fn assert_invariants_host_bits() {
+    let mut pm = Map::new();
+    pm.insert(ip("10.0.0.0/24"), 1);
+    pm.insert(ip("10.0.1.0/24"), 2);
+    pm.assert_invariants();
+    // a branch node with host bits set still contains both children.
+    let branch = pm
+        .table
+        .iter_mut()
+        .find(|n| n.value.is_none() && n.prefix.prefix_len() == 23);
+    branch.unwrap().prefix = ip("10.0.0.1/23");
+    pm.assert_invariants();
+}
+
+#[cfg(feature = "checked")]
+mod checked_prefixes {
+    use super::*;
+
+    /// A prefix whose `contains` always returns `true`, which passes [`check_prefix`], but builds
+    /// a chain of nodes when inserting prefixes with the same length.
+    #[derive(Clone, Copy, Debug)]
+    struct ContainsAll(u32, u8);
+
+    impl Prefix for ContainsAll {
+        type R = u32;
+        fn repr(&self) -> u32 {
+            self.0
+        }
+        fn prefix_len(&self) -> u8 {
+            self.1
+        }
+        fn from_repr_len(repr: u32, len: u8) -> Self {
+            ContainsAll(repr, len)
+        }
+        fn contains(&self, _: &Self) -> bool {
+            true
+        }
+    }
+
+    /// A prefix whose mask keeps all host bits.
+    #[derive(Clone, Copy, Debug)]
+    struct Unmasked(u32, u8);
+
+    impl Prefix for Unmasked {
+        type R = u32;
+        fn repr(&self) -> u32 {
+            self.0
+        }
+        fn prefix_len(&self) -> u8 {
+            self.1
+        }
+        fn from_repr_len(repr: u32, len: u8) -> Self {
+            Unmasked(repr, len)
+        }
+        fn mask(&self) -> u32 {
+            self.0
+        }
+    }
+
+    /// A prefix that forgets its length when constructed from its representation.
+    #[derive(Clone, Copy, Debug)]
+    struct Forgetful(u32, u8);
+
+    impl Prefix for Forgetful {
+        type R = u32;
+        fn repr(&self) -> u32 {
+            self.0
+        }
+        fn prefix_len(&self) -> u8 {
+            self.1
+        }
+        fn from_repr_len(repr: u32, _len: u8) -> Self {
+            Forgetful(repr, 32)
+        }
+    }
+
+    /// A prefix that is not contained within itself.
+    #[derive(Clone, Copy, Debug)]
+    struct ContainsNone(u32, u8);
+
+    impl Prefix for ContainsNone {
+        type R = u32;
+        fn repr(&self) -> u32 {
+            self.0
+        }
+        fn prefix_len(&self) -> u8 {
+            self.1
+        }
+        fn from_repr_len(repr: u32, len: u8) -> Self {
+            ContainsNone(repr, len)
+        }
+        fn contains(&self, _: &Self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn depth_exceeded() {
+        let mut pm: PrefixMap<ContainsAll, u32> = PrefixMap::new();
+        // without checks, every insert extends the chain by one node.
+        for i in 0..40 {
+            pm.insert(ContainsAll(i, 32), i);
+        }
+        let missing = ContainsAll(100, 32);
+        assert_eq!(
+            pm.get_lpm_checked(&missing).err(),
+            Some(PrefixError::DepthExceeded)
+        );
+        assert_eq!(pm.remove_checked(&missing), Err(PrefixError::DepthExceeded));
+        assert_eq!(
+            pm.children_checked(&missing).err(),
+            Some(PrefixError::DepthExceeded)
+        );
+        assert_eq!(pm.check_tree(), Err(PrefixError::InconsistentContains));
+
+        // the checked insert refuses to grow the chain beyond the bit width.
+        let mut pm: PrefixMap<ContainsAll, u32> = PrefixMap::new();
+        let results = (0..40)
+            .map(|i| pm.insert_checked(ContainsAll(i, 32), i))
+            .collect::<Vec<_>>();
+        assert!(results[..32].iter().all(|r| *r == Ok(None)));
+        // the chain of 32 nodes below the root is as deep as any valid tree.
+        assert_eq!(results[32], Err(PrefixError::DepthExceeded));
+        assert!(results[33..]
+            .iter()
+            .all(|r| *r == Err(PrefixError::DepthExceeded)));
+    }
+
+    #[test]
+    fn inconsistent_prefixes() {
+        let mut pm: PrefixMap<(u32, u8), u32> = PrefixMap::new();
+        assert_eq!(
+            pm.insert_checked((0, 33), 1),
+            Err(PrefixError::LengthOutOfRange { len: 33, max: 32 })
+        );
+        assert_eq!(pm.check_tree(), Ok(()));
+
+        let mut pm: PrefixMap<Unmasked, u32> = PrefixMap::new();
+        assert_eq!(
+            pm.insert_checked(Unmasked(0x0a000001, 8), 1),
+            Err(PrefixError::InconsistentMask)
+        );
+        pm.insert(Unmasked(0x0a000001, 8), 1);
+        assert_eq!(pm.check_tree(), Err(PrefixError::InconsistentMask));
+
+        let pm: PrefixMap<Forgetful, u32> = PrefixMap::new();
+        assert_eq!(
+            pm.get_lpm_checked(&Forgetful(0x0a000000, 8)).err(),
+            Some(PrefixError::InconsistentConstruction)
+        );
+
+        let mut pm: PrefixMap<ContainsNone, u32> = PrefixMap::new();
+        assert_eq!(
+            pm.remove_checked(&ContainsNone(0x0a000000, 8)),
+            Err(PrefixError::InconsistentContains)
+        );
+        assert_eq!(pm.check_tree(), Err(PrefixError::InconsistentContains));
+    }
+}
+
+#[test]
+fn try_aggregate_contiguous() {
+    // all elements form a single contiguous range
+    let set: Set = ip("10.0.0.0/24").subnets(32).unwrap().collect();
+    let mut calls = 0;
+    let result = set.try_aggregate(|| {
+        calls += 1;
+        calls > 10
+    });
+    assert!(result.is_none());
+    assert_eq!(calls, 11);
+    let aggr = set.try_aggregate(|| false).unwrap();
+    assert_eq!(aggr.iter().collect::<Vec<_>>(), vec![&ip("10.0.0.0/24")]);
+}
+
+#[test]
+fn coverage_size_ipv6() {
+    let mut set: PrefixSet<ipnet::Ipv6Net> = PrefixSet::new();
+    set.insert("8000::/1".parse().unwrap());
+    assert_eq!(set.coverage_size(), 1 << 127);
+    set.insert("::/1".parse().unwrap());
+    assert_eq!(set.coverage_size(), u128::MAX);
+}
+
+#[test]
+fn addresses_end_of_space() {
+    let mut set = Set::new();
+    set.insert(ip("255.255.255.254/31"));
+    set.insert(ip("255.255.255.255/32"));
+    assert_eq!(
+        set.addresses().collect::<Vec<_>>(),
+        vec![u32::MAX - 1, u32::MAX]
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn text_roundtrip() {
+    let pm: Map = [("10.0.0.0/8", 1), ("10.1.0.0/16", 2), ("192.168.0.0/16", 3)]
+        .into_iter()
+        .map(|(p, t)| (ip(p), t))
+        .collect();
+    let mut out = Vec::new();
+    pm.to_writer(&mut out).unwrap();
+    let read: Map = PrefixMap::from_reader(out.as_slice()).unwrap();
+    assert_eq!(read, pm);
+
+    // windows line endings are accepted
+    let read: Map = PrefixMap::from_reader("10.0.0.0/8\t1\r\n".as_bytes()).unwrap();
+    assert_eq!(read.get(&ip("10.0.0.0/8")), Some(&1));
+
+    assert!(matches!(
+        Map::from_reader("10.0.0.0/8\t1\n10.0.0.0/33\t2\n".as_bytes()),
+        Err(map::FromReaderError::Prefix { line: 2, .. })
+    ));
+    assert!(matches!(
+        Map::from_reader("\n10.0.0.0/8\tx\n".as_bytes()),
+        Err(map::FromReaderError::Value { line: 2, .. })
+    ));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_map_roundtrip() {
+    let mut pm: PrefixMap<Ipv4Net, u32> = PrefixMap::new();
+    pm.insert(ip("192.168.0.0/23"), 1);
+    pm.insert(ip("192.168.0.0/24"), 2);
+    pm.insert(ip("10.0.0.0/8"), 3);
+    let json = serde_json::to_string(&pm).unwrap();
+    assert_eq!(
+        json,
+        r#"[["10.0.0.0/8",3],["192.168.0.0/23",1],["192.168.0.0/24",2]]"#
+    );
+    let de: PrefixMap<Ipv4Net, u32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(de.iter().collect::<Vec<_>>(), pm.iter().collect::<Vec<_>>());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_map_duplicates() {
+    let json = r#"[["10.0.0.0/8",1],["10.0.0.0/8",2]]"#;
+    let pm: PrefixMap<Ipv4Net, u32> = serde_json::from_str(json).unwrap();
+    assert_eq!(pm.iter().collect::<Vec<_>>(), vec![(&ip("10.0.0.0/8"), &2)]);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_set_roundtrip() {
+    let set: PrefixSet<Ipv4Net> = ["192.168.0.0/24", "10.0.0.0/8", "192.168.0.0/23"]
+        .into_iter()
+        .map(ip)
+        .collect();
+    let json = serde_json::to_string(&set).unwrap();
+    assert_eq!(json, r#"["10.0.0.0/8","192.168.0.0/23","192.168.0.0/24"]"#);
+    let de: PrefixSet<Ipv4Net> = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        de.iter().collect::<Vec<_>>(),
+        set.iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_map_legacy_format() {
+    // written by version 0.2, which serialized the map through a `HashMap`.
+    let json = r#"{"192.168.0.0/24":2,"10.0.0.0/8":3,"192.168.0.0/23":1}"#;
+    let pm: PrefixMap<Ipv4Net, u32> = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        pm.iter().collect::<Vec<_>>(),
+        vec![
+            (&ip("10.0.0.0/8"), &3),
+            (&ip("192.168.0.0/23"), &1),
+            (&ip("192.168.0.0/24"), &2)
+        ]
+    );
+    let json = serde_json::to_string(&pm).unwrap();
+    let de: PrefixMap<Ipv4Net, u32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(de.iter().collect::<Vec<_>>(), pm.iter().collect::<Vec<_>>());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_set_legacy_format() {
+    // written by version 0.2, which serialized the set through a `HashSet`.
+    let json = r#"["192.168.0.0/24","10.0.0.0/8","192.168.0.0/23"]"#;
+    let set: PrefixSet<Ipv4Net> = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        set.iter().collect::<Vec<_>>(),
+        vec![
+            &ip("10.0.0.0/8"),
+            &ip("192.168.0.0/23"),
+            &ip("192.168.0.0/24")
+        ]
+    );
+}
+
+#[cfg(feature = "mrt")]
+mod mrt_rib {
+    use std::net::Ipv6Addr;
+
+    use ipnet::Ipv6Net;
+
+    use super::*;
+    use crate::mrt::*;
+
+    fn record(subtype: u16, body: &[u8]) -> Vec<u8> {
+        let mut rec = Vec::new();
+        rec.extend(1_700_000_000u32.to_be_bytes());
+        rec.extend(TABLE_DUMP_V2.to_be_bytes());
+        rec.extend(subtype.to_be_bytes());
+        rec.extend((body.len() as u32).to_be_bytes());
+        rec.extend(body);
+        rec
+    }
+
+    fn peer_table() -> Vec<u8> {
+        let mut body = vec![192, 0, 2, 1, 0, 4];
+        body.extend(b"test");
+        body.extend(2u16.to_be_bytes());
+        // IPv4 peer with a 2-byte AS number
+        body.extend([0x00, 10, 0, 0, 1, 10, 0, 0, 1]);
+        body.extend(65000u16.to_be_bytes());
+        // IPv6 peer with a 4-byte AS number
+        body.extend([0x03, 10, 0, 0, 2]);
+        body.extend("2001:db8::2".parse::<Ipv6Addr>().unwrap().octets());
+        body.extend(4_200_000_000u32.to_be_bytes());
+        body
+    }
+
+    fn rib_entry(peer_index: u16, attributes: &[u8]) -> Vec<u8> {
+        let mut entry = Vec::new();
+        entry.extend(peer_index.to_be_bytes());
+        entry.extend(1_600_000_000u32.to_be_bytes());
+        entry.extend((attributes.len() as u16).to_be_bytes());
+        entry.extend(attributes);
+        entry
+    }
+
+    #[test]
+    fn load() {
+        let mut dump = record(PEER_INDEX_TABLE, &peer_table());
+
+        let mut body = vec![0, 0, 0, 0, 20, 10, 16, 0xf0];
+        body.extend(2u16.to_be_bytes());
+        body.extend(rib_entry(0, &[1, 2, 3]));
+        body.extend(rib_entry(1, &[]));
+        dump.extend(record(RIB_IPV4_UNICAST, &body));
+
+        let mut body = vec![0, 0, 0, 1, 32, 0x20, 0x01, 0x0d, 0xb8];
+        body.extend(1u16.to_be_bytes());
+        body.extend(rib_entry(1, &[4]));
+        dump.extend(record(RIB_IPV6_UNICAST, &body));
+
+        // unknown subtypes are skipped
+        dump.extend(record(3, &[1, 2, 3]));
+
+        let rib = load_rib(dump.as_slice()).unwrap();
+        assert_eq!(
+            rib.peers,
+            vec![
+                Peer {
+                    bgp_id: Ipv4Addr::new(10, 0, 0, 1),
+                    addr: "10.0.0.1".parse().unwrap(),
+                    asn: 65000,
+                },
+                Peer {
+                    bgp_id: Ipv4Addr::new(10, 0, 0, 2),
+                    addr: "2001:db8::2".parse().unwrap(),
+                    asn: 4_200_000_000,
+                },
+            ]
+        );
+        let v4 = rib.ipv4.iter().collect::<Vec<_>>();
+        assert_eq!(v4.len(), 1);
+        assert_eq!(v4[0].0, &"10.16.240.0/20".parse::<Ipv4Net>().unwrap());
+        assert_eq!(
+            v4[0].1,
+            &vec![
+                RibEntry {
+                    peer_index: 0,
+                    originated_time: 1_600_000_000,
+                    path_id: None,
+                    attributes: vec![1, 2, 3],
+                },
+                RibEntry {
+                    peer_index: 1,
+                    originated_time: 1_600_000_000,
+                    path_id: None,
+                    attributes: vec![],
+                },
+            ]
+        );
+        let v6 = rib.ipv6.iter().collect::<Vec<_>>();
+        assert_eq!(v6.len(), 1);
+        assert_eq!(v6[0].0, &"2001:db8::/32".parse::<Ipv6Net>().unwrap());
+        assert_eq!(v6[0].1[0].attributes, vec![4]);
+    }
+
+    #[test]
+    fn malformed() {
+        let dump = record(PEER_INDEX_TABLE, &peer_table());
+        assert!(matches!(
+            load_rib(&dump[..dump.len() - 1]),
+            Err(MrtError::Malformed("truncated record"))
+        ));
+        assert!(matches!(
+            load_rib(&dump[..5]),
+            Err(MrtError::Malformed("truncated header"))
+        ));
+        let body = [0, 0, 0, 0, 33, 10, 0, 0, 0, 0];
+        assert!(matches!(
+            load_rib(record(RIB_IPV4_UNICAST, &body).as_slice()),
+            Err(MrtError::Malformed("invalid prefix length"))
+        ));
+    }
+}
+
+#[test]
+#[should_panic(expected = "prefix not found in the map")]
+fn index_missing() {
+    let mut pm: Map = PrefixMap::new();
+    pm.insert(ip("10.0.0.0/8"), 1);
+    assert_eq!(pm[&ip("10.0.0.0/8")], 1);
+    let _ = pm[&ip("10.0.0.0/9")];
+}
+
 fn fuzzing(n: usize) {
     let mut reference = BTreeMap::new();
     let mut pm = Map::new();
@@ -806,6 +1427,1998 @@ fn fuzzing_retain(n: usize, m: usize) {
     }
 }
 
+fn fuzzing_try_retain(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = (0..n)
+        .map(|_| (random_prefix(&mut rng, 0..=8), rng.gen_range(0..4)))
+        .collect();
+    let mut reference: BTreeMap<Ipv4Net, u32> = pm.iter().map(|(p, t)| (*p, *t)).collect();
+
+    // cancel after a random number of entries, and resume until the operation completes.
+    loop {
+        let limit = rng.gen_range(0..=reference.len());
+        let mut steps = 0;
+        let mut visited = Vec::new();
+        let result = pm.try_retain(
+            |p, t| {
+                visited.push(*p);
+                t % 2 == 0
+            },
+            || {
+                steps += 1;
+                steps > limit
+            },
+        );
+        pm.assert_invariants();
+        assert_eq!(result.is_none(), visited.len() < reference.len());
+        assert_eq!(visited.len(), limit.min(reference.len()));
+        for p in visited {
+            if reference[&p] % 2 != 0 {
+                reference.remove(&p);
+            }
+        }
+        assert!(pm.iter().map(|(p, t)| (*p, *t)).eq(reference.clone()));
+        if result.is_some() {
+            break;
+        }
+    }
+    assert!(reference.values().all(|t| t % 2 == 0));
+}
+
+fn fuzzing_try_remove_children(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = (0..n)
+        .map(|_| (random_prefix(&mut rng, 0..=8), rng.gen()))
+        .collect();
+    let reference: BTreeMap<Ipv4Net, u32> = pm.iter().map(|(p, t)| (*p, *t)).collect();
+    let prefix = random_prefix(&mut rng, 0..=4);
+    let removed = reference.keys().filter(|p| prefix.contains(*p)).count();
+
+    let limit = rng.gen_range(0..=removed);
+    let mut steps = 0;
+    let result = pm.try_remove_children(&prefix, || {
+        steps += 1;
+        steps > limit
+    });
+    pm.assert_invariants();
+    assert_eq!(result.is_none(), limit < removed);
+    // only entries within `prefix` are removed, and only as many as were allowed.
+    assert_eq!(pm.iter().count(), reference.len() - limit);
+    for (p, t) in pm.iter() {
+        assert_eq!(reference.get(p), Some(t));
+    }
+    assert!(reference
+        .iter()
+        .filter(|(p, _)| !prefix.contains(*p))
+        .all(|(p, t)| pm.get(p) == Some(t)));
+
+    // resuming the operation removes the remaining entries
+    assert_eq!(pm.try_remove_children(&prefix, || false), Some(()));
+    pm.assert_invariants();
+    assert!(pm
+        .iter()
+        .map(|(p, t)| (*p, *t))
+        .eq(reference.into_iter().filter(|(p, _)| !prefix.contains(p))));
+}
+
+fn fuzzing_shadow(n: usize) {
+    let mut pm: shadow::ShadowedPrefixMap<Ipv4Net, u32> = shadow::ShadowedPrefixMap::new();
+
+    let mut rng = thread_rng();
+
+    for _ in 0..n {
+        let prefix = Ipv4Net::new(Ipv4Addr::new(rng.gen(), 0, 0, 0), rng.gen_range(0..=8)).unwrap();
+        let prefix = Ipv4Net::new(prefix.mask().into(), prefix.prefix_len()).unwrap();
+
+        match rng.gen_range(0..11) {
+            0..=5 => {
+                pm.insert(prefix, rng.gen::<u8>() as u32);
+            }
+            6 => {
+                pm.remove_keep_tree(&prefix);
+            }
+            7 => pm.remove_children(&prefix),
+            8 => pm.retain(|_, _| rng.gen_bool(0.9)),
+            _ => {
+                pm.remove(&prefix);
+            }
+        }
+
+        pm.get(&prefix);
+        pm.get_lpm(&prefix);
+        pm.get_spm(&prefix);
+        pm.children(&prefix);
+    }
+}
+
+fn fuzzing_shadow_backend<M>(mut pm: shadow::ShadowedPrefixMap<Ipv4Net, u32, M>, n: usize)
+where
+    M: shadow::Backend<Ipv4Net, u32>,
+{
+    let mut rng = thread_rng();
+    for _ in 0..n {
+        let prefix = random_prefix(&mut rng, 0..=8);
+        if rng.gen_bool(0.7) {
+            pm.insert(prefix, rng.gen::<u8>() as u32);
+        } else {
+            pm.remove(&prefix);
+        }
+        pm.get(&prefix);
+        pm.get_lpm(&random_prefix(&mut rng, 0..=16));
+    }
+}
+
+#[cfg(feature = "concurrent")]
+mod concurrent_map {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::concurrent::new;
+
+    #[test]
+    fn publish() {
+        let (mut writer, reader) = new(PrefixMap::<Ipv4Net, u32>::new());
+        let p: Ipv4Net = "10.0.0.0/8".parse().unwrap();
+        let q: Ipv4Net = "10.1.0.0/16".parse().unwrap();
+        assert_eq!(writer.insert(p, 1), None);
+        assert_eq!(writer.insert(p, 2), Some(1));
+        writer.insert(q, 3);
+        assert!(writer.has_pending());
+        assert_eq!(reader.read().get(&p), None);
+        writer.publish();
+        assert!(!writer.has_pending());
+        assert_eq!(reader.read().get(&p), Some(&2));
+
+        assert_eq!(writer.remove(&q), Some(3));
+        assert_eq!(writer.remove(&q), None);
+        assert_eq!(reader.read().get(&q), Some(&3));
+        writer.publish();
+        assert_eq!(reader.read().get(&q), None);
+
+        // both copies must contain the same entries after each publish.
+        writer.insert(q, 4);
+        writer.publish();
+        writer.publish();
+        writer.remove_children(&p);
+        writer.insert(q, 5);
+        writer.publish();
+        assert_eq!(
+            writer.unpublished().iter().collect::<Vec<_>>(),
+            vec![(&q, &5)]
+        );
+        assert_eq!(reader.read().iter().collect::<Vec<_>>(), vec![(&q, &5)]);
+        writer.clear();
+        writer.insert(p, 6);
+        writer.publish();
+        assert_eq!(reader.read().iter().collect::<Vec<_>>(), vec![(&p, &6)]);
+    }
+
+    #[test]
+    fn concurrent_readers() {
+        let (mut writer, reader) = new(PrefixMap::<Ipv4Net, u32>::new());
+        let p: Ipv4Net = "10.0.0.0/8".parse().unwrap();
+        let q: Ipv4Net = "10.1.0.0/16".parse().unwrap();
+        let addr: Ipv4Net = "10.1.1.1/32".parse().unwrap();
+        let done = Arc::new(AtomicBool::new(false));
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let reader = reader.clone();
+                let done = done.clone();
+                std::thread::spawn(move || {
+                    let mut last = 0;
+                    while !done.load(Ordering::Relaxed) {
+                        let map = reader.read();
+                        // both entries are always published together with the same value.
+                        let a = map.get(&p).copied();
+                        let b = map.get_lpm(&addr).map(|(_, t)| *t);
+                        assert_eq!(a, b);
+                        let a = a.unwrap_or(0);
+                        assert!(a >= last);
+                        last = a;
+                    }
+                })
+            })
+            .collect();
+        for i in 1..=200 {
+            writer.insert(p, i);
+            writer.insert(q, i);
+            writer.publish();
+        }
+        done.store(true, Ordering::Relaxed);
+        for r in readers {
+            r.join().unwrap();
+        }
+        assert_eq!(reader.read().get(&q), Some(&200));
+    }
+
+    #[test]
+    fn nested_guards() {
+        let (mut writer, reader) = new(PrefixMap::<Ipv4Net, u32>::new());
+        let p: Ipv4Net = "10.0.0.0/8".parse().unwrap();
+        writer.insert(p, 1);
+        writer.publish();
+        let other = reader.clone();
+        let outer = reader.read();
+        let handle = std::thread::spawn(move || {
+            writer.insert(p, 2);
+            // waits for `outer` to be dropped.
+            writer.publish();
+        });
+        // wait until the new state is published.
+        while other.read().get(&p) != Some(&2) {
+            std::thread::yield_now();
+        }
+        // a nested guard keeps accessing the same state as the outer one.
+        let inner = reader.read();
+        assert_eq!(inner.get(&p), Some(&1));
+        drop(inner);
+        assert_eq!(outer.get(&p), Some(&1));
+        drop(outer);
+        handle.join().unwrap();
+        assert_eq!(reader.read().get(&p), Some(&2));
+    }
+}
+
+fn fuzzing_cursor(n: usize, m: usize) {
+    let mut reference = BTreeMap::new();
+    let mut pm = Map::new();
+
+    let mut rng = thread_rng();
+
+    for _ in 0..n {
+        for _ in 0..m {
+            let prefix = random_prefix(&mut rng, 0..=8);
+            let value: u32 = rng.gen::<u8>() as u32;
+            if rng.gen_bool(0.8) {
+                pm.insert(prefix, value);
+                reference.insert(prefix, value);
+            } else {
+                pm.remove(&prefix);
+                reference.remove(&prefix);
+            }
+        }
+
+        let start = random_prefix(&mut rng, 0..=8);
+        let mut cursor = pm.cursor_mut(&start);
+        let mut exp = reference.range(start..).next().map(|(p, _)| *p);
+        assert_eq!(cursor.key().copied(), exp, "seek to {start}");
+
+        for _ in 0..m {
+            match rng.gen_range(0..10) {
+                0 | 1 => {
+                    cursor.move_next();
+                    exp = match exp {
+                        Some(p) => reference.range(p..).nth(1).map(|(p, _)| *p),
+                        None => reference.keys().next().copied(),
+                    };
+                }
+                2 => {
+                    cursor.move_prev();
+                    exp = match exp {
+                        Some(p) => reference.range(..p).next_back().map(|(p, _)| *p),
+                        None => reference.keys().next_back().copied(),
+                    };
+                }
+                3 => {
+                    let removed = cursor.remove_current();
+                    assert_eq!(removed, exp.and_then(|p| reference.remove(&p)));
+                    exp = exp.and_then(|p| reference.range(p..).next().map(|(p, _)| *p));
+                }
+                4 => {
+                    let next = match exp {
+                        Some(p) => reference.range(p..).nth(1),
+                        None => reference.iter().next(),
+                    };
+                    assert_eq!(
+                        cursor.peek_next().map(|(p, v)| (*p, *v)),
+                        next.map(|(p, v)| (*p, *v))
+                    );
+                    let prev = match exp {
+                        Some(p) => reference.range(..p).next_back(),
+                        None => reference.iter().next_back(),
+                    };
+                    assert_eq!(
+                        cursor.peek_prev().map(|(p, v)| (*p, *v)),
+                        prev.map(|(p, v)| (*p, *v))
+                    );
+                }
+                5 => {
+                    cursor.move_to_parent();
+                    exp = exp.and_then(|p| {
+                        reference
+                            .keys()
+                            .filter(|k| **k != p && k.contains(&p))
+                            .max_by_key(|k| k.prefix_len())
+                            .copied()
+                    });
+                }
+                6 => {
+                    cursor.remove_children();
+                    if let Some(p) = exp {
+                        reference.retain(|k, _| *k == p || !p.contains(k));
+                    }
+                }
+                7 => {
+                    let prefix = random_prefix(&mut rng, 0..=8);
+                    cursor.seek(&prefix);
+                    exp = reference.range(prefix..).next().map(|(p, _)| *p);
+                }
+                _ => {
+                    let prefix = random_prefix(&mut rng, 0..=8);
+                    let value: u32 = rng.gen::<u8>() as u32;
+                    assert_eq!(
+                        cursor.insert(prefix, value),
+                        reference.insert(prefix, value)
+                    );
+                }
+            }
+            assert_eq!(cursor.key().copied(), exp);
+            assert_eq!(cursor.value().copied(), exp.map(|p| reference[&p]));
+        }
+
+        let sorted = reference.iter().map(|(p, v)| (*p, *v)).collect::<Vec<_>>();
+        assert_iter!(pm, sorted);
+    }
+}
+
+fn fuzzing_double_ended(n: usize) {
+    let mut reference = BTreeMap::new();
+    let mut pm = Map::new();
+
+    let mut rng = thread_rng();
+
+    for _ in 0..n {
+        let prefix = random_prefix(&mut rng, 0..=8);
+        let value: u32 = rng.gen::<u8>() as u32;
+        pm.insert(prefix, value);
+        reference.insert(prefix, value);
+    }
+    for _ in 0..(n / 4) {
+        let prefix = random_prefix(&mut rng, 0..=8);
+        pm.remove(&prefix);
+        reference.remove(&prefix);
+    }
+
+    assert!(pm.iter().rev().eq(reference.iter().rev()));
+    assert!(pm.keys().rev().eq(reference.keys().rev()));
+    assert!(pm.values().rev().eq(reference.values().rev()));
+
+    // interleave calls from both ends
+    let mut acq = pm.iter();
+    let mut exp = reference.iter();
+    loop {
+        let (a, e) = if rng.gen_bool(0.5) {
+            (acq.next(), exp.next())
+        } else {
+            (acq.next_back(), exp.next_back())
+        };
+        assert_eq!(a, e);
+        if a.is_none() {
+            break;
+        }
+    }
+    assert_eq!(acq.next(), None);
+    assert_eq!(acq.next_back(), None);
+
+    let root = random_prefix(&mut rng, 0..=8);
+    assert!(pm
+        .children(&root)
+        .rev()
+        .eq(reference.iter().rev().filter(|(p, _)| root.contains(*p))));
+}
+
+fn fuzzing_non_overlapping(n: usize) {
+    let mut reference: Vec<(Ipv4Net, u32)> = Vec::new();
+    let mut pm = Map::new();
+
+    let mut rng = thread_rng();
+
+    for _ in 0..n {
+        let prefix = Ipv4Net::new(Ipv4Addr::new(rng.gen(), 0, 0, 0), rng.gen_range(0..=8)).unwrap();
+        let prefix = Ipv4Net::new(prefix.mask().into(), prefix.prefix_len()).unwrap();
+        let value: u32 = rng.gen::<u8>() as u32;
+        let overlaps = reference
+            .iter()
+            .any(|(p, _)| p.contains(&prefix) || prefix.contains(p));
+        match pm.insert_non_overlapping(prefix, value) {
+            Ok(()) => {
+                assert!(!overlaps, "{prefix} was inserted despite overlapping");
+                reference.push((prefix, value));
+            }
+            Err(e) => {
+                assert!(overlaps, "{prefix} was rejected without overlap");
+                assert!(e.existing.contains(&prefix) || prefix.contains(&e.existing));
+                assert!(reference.iter().any(|(p, _)| *p == e.existing));
+                assert_eq!((e.prefix, e.value), (prefix, value));
+            }
+        }
+    }
+
+    reference.sort();
+    assert_iter!(pm, reference);
+}
+
+fn fuzzing_range(n: usize) {
+    let mut reference = BTreeMap::new();
+    let mut pm = Map::new();
+
+    let mut rng = thread_rng();
+
+    fn random_bound(rng: &mut ThreadRng) -> Bound<Ipv4Net> {
+        match rng.gen_range(0..3) {
+            0 => Bound::Included(random_prefix(rng, 0..=8)),
+            1 => Bound::Excluded(random_prefix(rng, 0..=8)),
+            _ => Bound::Unbounded,
+        }
+    }
+
+    for _ in 0..n {
+        let prefix = random_prefix(&mut rng, 0..=8);
+        let value: u32 = rng.gen::<u8>() as u32;
+        pm.insert(prefix, value);
+        reference.insert(prefix, value);
+    }
+    for _ in 0..(n / 4) {
+        let prefix = random_prefix(&mut rng, 0..=8);
+        pm.remove_keep_tree(&prefix);
+        reference.remove(&prefix);
+    }
+
+    for _ in 0..10 {
+        let start = random_prefix(&mut rng, 0..=8);
+        assert!(pm.iter_from(&start).eq(reference.range(start..)));
+        assert!(pm
+            .iter_from(&start)
+            .rev()
+            .eq(reference.range(start..).rev()));
+
+        let mut range = (random_bound(&mut rng), random_bound(&mut rng));
+        if let (Bound::Included(a) | Bound::Excluded(a), Bound::Included(b) | Bound::Excluded(b)) =
+            &mut range
+        {
+            // BTreeMap::range panics if the start is larger than the end.
+            if a > b {
+                std::mem::swap(a, b);
+            }
+        }
+        if let (Bound::Excluded(a), Bound::Excluded(b)) = range {
+            if a == b {
+                continue;
+            }
+        }
+        assert!(pm.range(range).eq(reference.range(range)), "{range:?}");
+        assert!(
+            pm.range(range).rev().eq(reference.range(range).rev()),
+            "{range:?}"
+        );
+
+        // interleave calls from both ends
+        let mut acq = pm.range(range);
+        let mut exp = reference.range(range);
+        loop {
+            let (a, e) = if rng.gen_bool(0.5) {
+                (acq.next(), exp.next())
+            } else {
+                (acq.next_back(), exp.next_back())
+            };
+            assert_eq!(a, e, "{range:?}");
+            if a.is_none() {
+                break;
+            }
+        }
+    }
+}
+
+fn fuzzing_partition(n: usize) {
+    let mut rng = thread_rng();
+    let root = ip("10.0.0.0/24");
+
+    fn random_partition(rng: &mut ThreadRng, p: Ipv4Net, set: &mut Set) {
+        if p.prefix_len() < 28 && rng.gen_bool(0.6) {
+            let mut subnets = p.subnets(p.prefix_len() + 1).unwrap();
+            random_partition(rng, subnets.next().unwrap(), set);
+            random_partition(rng, subnets.next().unwrap(), set);
+        } else {
+            set.insert(p);
+        }
+    }
+
+    for _ in 0..n {
+        let mut set = Set::new();
+        random_partition(&mut rng, root, &mut set);
+        assert_eq!(set.is_partition_of(&root), Ok(()));
+
+        // randomly modify the set
+        for _ in 0..rng.gen_range(0..3) {
+            let len = rng.gen_range(20..=28);
+            let addr = Ipv4Addr::new(10, 0, rng.gen_range(0..=1), rng.gen());
+            let p = Ipv4Net::new(addr, len).unwrap().trunc();
+            if rng.gen_bool(0.5) {
+                set.insert(p);
+            } else {
+                set.remove(&p);
+            }
+        }
+
+        let elems = set.iter().copied().collect::<Vec<_>>();
+        let outside = elems.iter().find(|p| !root.contains(*p));
+        let overlap = elems
+            .iter()
+            .enumerate()
+            .any(|(i, a)| elems[i + 1..].iter().any(|b| a.contains(b)));
+        let gap = root
+            .subnets(28)
+            .unwrap()
+            .find(|a| !elems.iter().any(|p| p.contains(a)));
+        let result = set.is_partition_of(&root);
+        match &result {
+            Ok(()) => assert!(outside.is_none() && !overlap && gap.is_none()),
+            Err(set::PartitionError::Outside(p)) => assert!(!root.contains(p) && elems.contains(p)),
+            Err(set::PartitionError::Overlap { covering, covered }) => {
+                assert!(covering.contains(covered));
+                assert!(elems.contains(covering) && elems.contains(covered));
+                assert!(outside.is_none() || outside.unwrap() > covered);
+            }
+            Err(set::PartitionError::Gap(p)) => {
+                assert!(root.contains(p));
+                assert!(!elems.iter().any(|e| e.contains(p) || p.contains(e)));
+                assert_eq!(
+                    gap.map(|g| p.contains(&g)),
+                    Some(true),
+                    "{result:?}, {gap:?}"
+                );
+            }
+        }
+        if outside.is_some() || overlap || gap.is_some() {
+            assert!(result.is_err());
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn fuzzing_par_iter(n: usize) {
+    use rayon::prelude::*;
+
+    let mut pm = Map::new();
+    let mut rng = thread_rng();
+
+    for _ in 0..n {
+        let prefix = Ipv4Net::new(
+            Ipv4Addr::new(rng.gen(), rng.gen(), 0, 0),
+            rng.gen_range(0..=16),
+        )
+        .unwrap()
+        .trunc();
+        pm.insert(prefix, rng.gen::<u8>() as u32);
+    }
+    for _ in 0..(n / 4) {
+        let prefix = Ipv4Net::new(
+            Ipv4Addr::new(rng.gen(), rng.gen(), 0, 0),
+            rng.gen_range(0..=16),
+        )
+        .unwrap()
+        .trunc();
+        pm.remove_keep_tree(&prefix);
+    }
+
+    assert_eq!(
+        pm.par_iter().collect::<Vec<_>>(),
+        pm.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        pm.par_values().collect::<Vec<_>>(),
+        pm.values().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        pm.par_iter().find_first(|(_, t)| **t == 0),
+        pm.iter().find(|(_, t)| **t == 0)
+    );
+
+    let mut exp = pm.clone();
+    exp.iter_mut()
+        .for_each(|(p, t)| *t += p.prefix_len() as u32);
+    pm.par_iter_mut()
+        .for_each(|(p, t)| *t += p.prefix_len() as u32);
+    assert_eq!(
+        pm.iter().collect::<Vec<_>>(),
+        exp.iter().collect::<Vec<_>>()
+    );
+}
+
+fn fuzzing_lpm_addresses(n: usize) {
+    let mut pm = Map::new();
+    let mut rng = thread_rng();
+
+    for _ in 0..n {
+        pm.insert(random_prefix(&mut rng, 0..=12), rng.gen::<u8>() as u32);
+    }
+    let addrs = (0..n)
+        .map(|_| random_prefix(&mut rng, 0..=16))
+        .collect::<Vec<_>>();
+    let exp = addrs
+        .iter()
+        .map(|a| (*a, pm.get_lpm(a).map(|(_, t)| t)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        pm.lpm_all_addresses_of(addrs.iter().copied())
+            .collect::<Vec<_>>(),
+        exp
+    );
+    let batch_size = rng.gen_range(0..100);
+    assert_eq!(
+        pm.lpm_all_addresses_of(addrs.iter().copied())
+            .sorted_batches(batch_size)
+            .collect::<Vec<_>>(),
+        exp
+    );
+}
+
+fn fuzzing_aggregate(n: usize) {
+    let mut rng = thread_rng();
+
+    let set: Set = (0..n).map(|_| random_prefix(&mut rng, 0..=8)).collect();
+    let aggr = set.aggregate();
+    let exp = ip("0.0.0.0/0")
+        .subnets(8)
+        .unwrap()
+        .filter(|b| set.get_lpm(b).is_some())
+        .count() as u128;
+    assert_eq!(set.coverage_size(), exp << 24);
+    assert_eq!(aggr.coverage_size(), exp << 24);
+    let elems = aggr.iter().copied().collect::<Vec<_>>();
+
+    // both cover the same addresses
+    for block in ip("0.0.0.0/0").subnets(8).unwrap() {
+        assert_eq!(
+            set.get_spm(&block).is_some(),
+            aggr.get_spm(&block).is_some(),
+            "{block}"
+        );
+    }
+    // no element is contained in another, and no two siblings are present
+    for (i, a) in elems.iter().enumerate() {
+        for b in &elems[i + 1..] {
+            assert!(!a.contains(b), "{a} contains {b}");
+            assert!(
+                a.prefix_len() != b.prefix_len() || a.supernet() != b.supernet(),
+                "{a} and {b} are siblings"
+            );
+        }
+    }
+}
+
+fn fuzzing_rebase(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+    let mut reference = BTreeMap::new();
+
+    for _ in 0..n {
+        let prefix = random_prefix(&mut rng, 0..=16);
+        let value: u32 = rng.gen::<u8>() as u32;
+        pm.insert(prefix, value);
+        reference.insert(prefix, value);
+    }
+
+    for _ in 0..10 {
+        let from = random_prefix(&mut rng, 0..=16);
+        let to = random_prefix(&mut rng, 0..=16);
+        let exp = reference
+            .iter()
+            .filter(|(p, _)| from.contains(*p))
+            .filter_map(|(p, t)| {
+                let offset = u32::from(p.network()) - u32::from(from.network());
+                let addr = u32::from(to.network()).checked_add(offset)?;
+                let p = Ipv4Net::new(addr.into(), p.prefix_len()).unwrap();
+                to.contains(&p).then_some((p, *t))
+            })
+            .collect::<Vec<_>>();
+        let acq = pm.rebase(&from, &to).into_iter().collect::<Vec<_>>();
+        assert_eq!(acq, exp, "rebase {from} to {to}");
+    }
+}
+
+fn fuzzing_wildcard(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+    let mut reference = BTreeMap::new();
+
+    // only use the first octet, such that all addresses can be enumerated
+    for _ in 0..n {
+        let prefix = random_prefix(&mut rng, 0..=8);
+        let value: u32 = rng.gen::<u8>() as u32;
+        pm.insert(prefix, value);
+        reference.insert(prefix, value);
+    }
+
+    for _ in 0..10 {
+        let addr: u32 = (rng.gen::<u8>() as u32) << 24;
+        let mask: u32 = (rng.gen::<u8>() as u32) << 24;
+        let prefixes = map::WildcardPrefixes::<Ipv4Net>::new(addr, mask).collect::<Vec<_>>();
+        let matching = (0..=255u8)
+            .map(|x| Ipv4Net::new(Ipv4Addr::new(x, 0, 0, 0), 8).unwrap())
+            .filter(|p| u32::from(p.network()) & mask == addr & mask)
+            .collect::<Vec<_>>();
+
+        // the prefixes are sorted, disjoint, and cover exactly the matching /8 blocks
+        assert!(prefixes
+            .windows(2)
+            .all(|w| w[0] < w[1] && !w[0].contains(&w[1])));
+        for block in ip("0.0.0.0/0").subnets(8).unwrap() {
+            assert_eq!(
+                prefixes.iter().any(|p| p.contains(&block)),
+                matching.contains(&block),
+                "{block} for {addr:#x}/{mask:#x}"
+            );
+        }
+        // no two siblings are generated
+        assert!(prefixes
+            .windows(2)
+            .all(|w| w[0].supernet() != w[1].supernet()));
+
+        let exp = matching
+            .iter()
+            .any(|b| reference.keys().any(|p| p.contains(b) || b.contains(p)));
+        assert_eq!(pm.contains_wildcard(addr, mask), exp);
+
+        let exp = prefixes
+            .iter()
+            .map(|p| (*p, pm.get_lpm(p).map(|(_, t)| t)))
+            .collect::<Vec<_>>();
+        assert_eq!(pm.lpm_wildcard(addr, mask).collect::<Vec<_>>(), exp);
+    }
+}
+
+fn fuzzing_aggregate_with(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        pm.insert(random_prefix(&mut rng, 0..=8), rng.gen_range(0..3));
+    }
+
+    let aggr = pm.aggregate_with(|a, b| (a == b).then_some(*a));
+
+    // cancelling after a number of steps aborts the aggregation
+    let merge = |a: &u32, b: &u32| (a == b).then_some(*a);
+    let mut total_steps = 0;
+    let total = pm.try_aggregate_with(merge, || {
+        total_steps += 1;
+        false
+    });
+    assert!(total.unwrap().iter().eq(aggr.iter()));
+    let limit = rng.gen_range(0..=total_steps);
+    let mut steps = 0;
+    let result = pm.try_aggregate_with(merge, || {
+        steps += 1;
+        steps > limit
+    });
+    assert_eq!(result.is_none(), limit < total_steps);
+
+    // the longest prefix match of all addresses is the same
+    for block in ip("0.0.0.0/0").subnets(8).unwrap() {
+        assert_eq!(
+            pm.get_lpm(&block).map(|(_, t)| t),
+            aggr.get_lpm(&block).map(|(_, t)| t),
+            "{block}"
+        );
+    }
+    // no entry or pair of siblings can be merged further
+    let elems = aggr.iter().collect::<Vec<_>>();
+    for (p, t) in &elems {
+        if p.prefix_len() == 0 {
+            continue;
+        }
+        let parent = p.supernet().unwrap();
+        assert_ne!(aggr.get_lpm(&parent).map(|(_, t)| t), Some(*t), "{p}");
+        let sibling = parent.subnets(p.prefix_len()).unwrap().find(|s| s != *p);
+        assert_ne!(aggr.get(&sibling.unwrap()), Some(*t), "{p}");
+    }
+}
+
+fn fuzzing_complement(n: usize) {
+    let mut rng = thread_rng();
+
+    let set: Set = (0..n).map(|_| random_prefix(&mut rng, 0..=8)).collect();
+    for _ in 0..10 {
+        let within = random_prefix(&mut rng, 0..=8);
+        let complement = set.complement(&within);
+        let elems = complement.iter().copied().collect::<Vec<_>>();
+
+        // the complement covers exactly the addresses in `within` not covered by the set
+        for block in ip("0.0.0.0/0").subnets(8).unwrap() {
+            let exp = within.contains(&block) && set.get_lpm(&block).is_none();
+            assert_eq!(
+                complement.get_lpm(&block).is_some(),
+                exp,
+                "{block} in {within}"
+            );
+        }
+        assert_eq!(set.covers_fully(&within), elems.is_empty());
+        // the complement is minimal
+        assert_eq!(
+            complement.aggregate().iter().copied().collect::<Vec<_>>(),
+            elems
+        );
+    }
+}
+
+fn fuzzing_remove_cover(n: usize) {
+    let mut rng = thread_rng();
+
+    let mut set: Set = (0..n).map(|_| random_prefix(&mut rng, 0..=8)).collect();
+    for _ in 0..10 {
+        let hole = random_prefix(&mut rng, 0..=8);
+        let before = set.clone();
+        set.remove_cover(&hole);
+        for block in ip("0.0.0.0/0").subnets(8).unwrap() {
+            let exp = !hole.contains(&block) && before.get_lpm(&block).is_some();
+            assert_eq!(
+                set.get_lpm(&block).is_some(),
+                exp,
+                "{block} after removing {hole}"
+            );
+        }
+        // elements that do not overlap with the hole are not modified
+        for p in before
+            .iter()
+            .filter(|p| !p.contains(&hole) && !hole.contains(*p))
+        {
+            assert!(set.contains(p), "{p} after removing {hole}");
+        }
+    }
+}
+
+fn fuzzing_insert_range(n: usize) {
+    let mut rng = thread_rng();
+
+    for _ in 0..n {
+        let mut pm: Map = PrefixMap::new();
+        // only use the first octet, such that all addresses can be enumerated
+        let a = (rng.gen::<u8>() as u32) << 24;
+        let b = (rng.gen::<u8>() as u32) << 24 | 0x00ff_ffff;
+        pm.insert_range(a..=b, 1);
+        let elems = pm.keys().copied().collect::<Vec<_>>();
+
+        for block in ip("0.0.0.0/0").subnets(8).unwrap() {
+            let addr = u32::from(block.network());
+            assert_eq!(
+                pm.get_lpm(&block).is_some(),
+                a <= addr && addr <= b,
+                "{block} in {a:#x}..={b:#x}"
+            );
+        }
+        // the decomposition is minimal and disjoint
+        let set: Set = elems.iter().copied().collect();
+        assert_eq!(set.aggregate().into_iter().collect::<Vec<_>>(), elems);
+    }
+}
+
+fn fuzzing_gaps(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        pm.insert(random_prefix(&mut rng, 0..=8), 0);
+    }
+    for _ in 0..10 {
+        let within = random_prefix(&mut rng, 0..=8);
+        let gaps = pm.gaps(&within).collect::<Vec<_>>();
+        // all gaps are sorted and disjoint, within `within`, and not covered by the map.
+        assert!(gaps
+            .windows(2)
+            .all(|w| w[0] < w[1] && !w[0].contains(&w[1])));
+        for gap in &gaps {
+            assert!(within.contains(gap));
+            assert!(pm.get_lpm(gap).is_none() && pm.children(gap).next().is_none());
+        }
+        assert_eq!(pm.covers_fully(&within), gaps.is_empty());
+        // together, the gaps and the map cover all of `within`
+        for block in within.subnets(8.max(within.prefix_len())).unwrap() {
+            let in_gap = gaps.iter().any(|g| g.contains(&block));
+            assert_ne!(in_gap, pm.get_lpm(&block).is_some(), "{block} in {within}");
+        }
+    }
+}
+
+fn fuzzing_first_gap(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        pm.insert(random_prefix(&mut rng, 0..=8), 0);
+    }
+    for _ in 0..10 {
+        let start = random_prefix(&mut rng, 0..=8);
+        let len = rng.gen_range(0..=8);
+        let free = ip("0.0.0.0/0")
+            .subnets(len)
+            .unwrap()
+            .filter(|b| pm.get_lpm(b).is_none() && pm.children(b).next().is_none())
+            .collect::<Vec<_>>();
+        let after = free
+            .iter()
+            .find(|b| b.network() >= start.network())
+            .copied();
+        assert_eq!(
+            pm.find_first_gap_after(&start, len),
+            after,
+            "/{len} after {start}"
+        );
+        let wrapping = after.or(free.first().copied());
+        assert_eq!(
+            pm.find_first_gap_wrapping(&start, len),
+            wrapping,
+            "/{len} after {start}"
+        );
+    }
+}
+
+fn fuzzing_subset(n: usize) {
+    let mut rng = thread_rng();
+
+    let a: Set = (0..rng.gen_range(0..n))
+        .map(|_| random_prefix(&mut rng, 2..=8))
+        .collect();
+    let b: Set = (0..rng.gen_range(0..n))
+        .map(|_| random_prefix(&mut rng, 2..=8))
+        .collect();
+    let covered = |set: &Set| {
+        ip("0.0.0.0/0")
+            .subnets(8)
+            .unwrap()
+            .filter(|block| set.get_lpm(block).is_some())
+            .collect::<BTreeSet<_>>()
+    };
+    let (ca, cb) = (covered(&a), covered(&b));
+    assert_eq!(a.is_subset(&b), ca.is_subset(&cb));
+    assert_eq!(a.is_superset(&b), ca.is_superset(&cb));
+    assert_eq!(a.is_disjoint(&b), ca.is_disjoint(&cb));
+    assert!(a.is_subset(&a.aggregate()) && a.is_superset(&a.aggregate()));
+}
+
+fn fuzzing_budgeted(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        pm.insert(random_prefix(&mut rng, 0..=16), rng.gen());
+    }
+
+    let exp = pm.iter().map(|(p, t)| (*p, *t)).collect::<Vec<_>>();
+    let mut acq = Vec::new();
+    let mut batch = pm.iter_budgeted(rng.gen_range(0..10));
+    loop {
+        acq.extend(batch.by_ref().map(|(p, t)| (*p, *t)));
+        let Some(token) = batch.resume_token() else {
+            break;
+        };
+        batch = pm.resume_budgeted(&token, rng.gen_range(1..10));
+    }
+    assert_eq!(acq, exp);
+}
+
+fn fuzzing_set_ops(n: usize) {
+    let mut rng = thread_rng();
+
+    let ra: BTreeSet<Ipv4Net> = (0..n).map(|_| random_prefix(&mut rng, 0..=8)).collect();
+    let rb: BTreeSet<Ipv4Net> = (0..n).map(|_| random_prefix(&mut rng, 0..=8)).collect();
+    let a: Set = ra.iter().copied().collect();
+    let b: Set = rb.iter().copied().collect();
+
+    let check = |acq: Set, exp: BTreeSet<Ipv4Net>| assert!(acq.iter().eq(exp.iter()));
+    check(&a | &b, &ra | &rb);
+    check(&a & &b, &ra & &rb);
+    check(&a - &b, &ra - &rb);
+    check(&a ^ &b, &ra ^ &rb);
+    check(a.clone() | b.clone(), &ra | &rb);
+    check(a.clone() & b.clone(), &ra & &rb);
+    check(a.clone() - b.clone(), &ra - &rb);
+    check(a ^ b, &ra ^ &rb);
+}
+
+fn fuzzing_union_with(n: usize) {
+    let mut rng = thread_rng();
+
+    let ra: BTreeMap<Ipv4Net, u32> = (0..n)
+        .map(|_| (random_prefix(&mut rng, 0..=8), rng.gen()))
+        .collect();
+    let rb: BTreeMap<Ipv4Net, u32> = (0..n)
+        .map(|_| (random_prefix(&mut rng, 0..=8), rng.gen()))
+        .collect();
+    let a: Map = ra.iter().map(|(p, t)| (*p, *t)).collect();
+    let b: Map = rb.iter().map(|(p, t)| (*p, *t)).collect();
+
+    let f = |_: &Ipv4Net, x: Option<&u32>, y: Option<&u32>| match (x, y) {
+        (Some(x), Some(_)) if x % 2 == 0 => None,
+        (x, y) => Some((x.copied(), y.copied())),
+    };
+    let mut keys = ra.keys().chain(rb.keys()).copied().collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+    let exp = keys
+        .into_iter()
+        .filter_map(|p| f(&p, ra.get(&p), rb.get(&p)).map(|v| (p, v)))
+        .collect::<Vec<_>>();
+    let acq = a.union_with(&b, f).into_iter().collect::<Vec<_>>();
+    assert_eq!(acq, exp);
+
+    let exp = ra
+        .keys()
+        .chain(rb.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|p| (p, ra.get(p), rb.get(p)))
+        .collect::<Vec<_>>();
+    assert_eq!(a.joint_iter(&b).collect::<Vec<_>>(), exp);
+}
+
+fn fuzzing_compat(n: usize) {
+    use ip_network_table_deps_treebitmap::IpLookupTable as Reference;
+    let mut rng = thread_rng();
+    let mut table: compat::IpLookupTable<Ipv4Addr, u32> = compat::IpLookupTable::new();
+    let mut reference: Reference<Ipv4Addr, u32> = Reference::new();
+
+    fn random_addr(rng: &mut ThreadRng) -> (Ipv4Addr, u32) {
+        (
+            Ipv4Addr::new(rng.gen(), rng.gen(), 0, 0),
+            rng.gen_range(0..=16),
+        )
+    }
+
+    for _ in 0..n {
+        let (addr, len) = random_addr(&mut rng);
+        let addr: Ipv4Addr = (u32::from(addr) & !(u32::MAX.checked_shr(len).unwrap_or(0))).into();
+        let (exp, acq) = if rng.gen_bool(0.7) {
+            let value = rng.gen();
+            (
+                reference.insert(addr, len, value),
+                table.insert(addr, len, value),
+            )
+        } else {
+            (reference.remove(addr, len), table.remove(addr, len))
+        };
+        assert_eq!(acq, exp);
+        assert_eq!(table.len(), reference.len());
+        let (addr, len) = random_addr(&mut rng);
+        let addr: Ipv4Addr = (u32::from(addr) & !(u32::MAX.checked_shr(len).unwrap_or(0))).into();
+        assert_eq!(
+            table.exact_match(addr, len),
+            reference.exact_match(addr, len)
+        );
+        assert_eq!(table.longest_match(addr), reference.longest_match(addr));
+        let mut exp = reference.matches(addr).collect::<Vec<_>>();
+        exp.sort_by_key(|(_, len, _)| std::cmp::Reverse(*len));
+        assert_eq!(table.matches(addr).collect::<Vec<_>>(), exp);
+    }
+    let mut exp = reference.iter().collect::<Vec<_>>();
+    exp.sort_by_key(|(addr, len, _)| (*addr, *len));
+    assert_eq!(table.iter().collect::<Vec<_>>(), exp);
+}
+
+fn fuzzing_diff(n: usize) {
+    let mut rng = thread_rng();
+    let mut old: Map = PrefixMap::new();
+    let mut new: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        let value = rng.gen_range(0..4);
+        match rng.gen_range(0..4) {
+            0 => {
+                old.insert(p, value);
+            }
+            1 => {
+                new.insert(p, value);
+            }
+            _ => {
+                old.insert(p, value);
+                new.insert(p, value);
+            }
+        }
+    }
+
+    let exp = old
+        .joint_iter(&new)
+        .filter_map(|(p, a, b)| match (a, b) {
+            (Some(a), Some(b)) if a == b => None,
+            (Some(a), Some(b)) => Some(map::DiffItem::Changed(p, a, b)),
+            (Some(a), None) => Some(map::DiffItem::Removed(p, a)),
+            (None, Some(b)) => Some(map::DiffItem::Added(p, b)),
+            (None, None) => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(old.diff(&new).collect::<Vec<_>>(), exp);
+    assert_eq!(old.diff(&old).count(), 0);
+
+    // cancelling after a number of steps aborts the comparison
+    let total_steps = old.joint_iter(&new).count();
+    assert_eq!(old.try_diff(&new, || false).as_ref(), Some(&exp));
+    let limit = rng.gen_range(0..=total_steps);
+    let mut steps = 0;
+    let result = old.try_diff(&new, || {
+        steps += 1;
+        steps > limit
+    });
+    assert_eq!(result.is_none(), limit < total_steps);
+
+    // applying the diff to the old map results in the new map
+    let mut patched = old.clone();
+    for item in old.diff(&new) {
+        match item {
+            map::DiffItem::Added(p, t) | map::DiffItem::Changed(p, _, t) => {
+                patched.insert(*p, *t);
+            }
+            map::DiffItem::Removed(p, _) => {
+                patched.remove(p);
+            }
+        }
+    }
+    assert_eq!(patched, new);
+}
+
+fn fuzzing_append(n: usize) {
+    let mut rng = thread_rng();
+    let mut a: Map = PrefixMap::new();
+    let mut b: Map = PrefixMap::new();
+    let mut exp: BTreeMap<Ipv4Net, u32> = BTreeMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        let value = rng.gen_range(0..1000);
+        if rng.gen_bool(0.5) {
+            a.insert(p, value);
+        } else {
+            b.insert(p, value);
+        }
+    }
+    for (p, t) in b.iter() {
+        *exp.entry(*p).or_default() += t;
+    }
+    for (p, t) in a.iter() {
+        *exp.entry(*p).or_default() += t;
+    }
+
+    a.append(b, |_, x, y| x + y);
+    assert_eq!(
+        a.iter().map(|(p, t)| (*p, *t)).collect::<Vec<_>>(),
+        exp.clone().into_iter().collect::<Vec<_>>()
+    );
+    // the resulting tree is the same one as if all entries were inserted one by one.
+    let inserted: Map = exp.into_iter().collect();
+    assert_eq!(format!("{a:#?}"), format!("{inserted:#?}"));
+}
+
+fn fuzzing_split_off(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+    let mut exp: BTreeMap<Ipv4Net, u32> = BTreeMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        let t = rng.gen();
+        pm.insert(p, t);
+        exp.insert(p, t);
+    }
+    let p = random_prefix(&mut rng, 0..=8);
+    let split = pm.split_off(&p);
+    let (exp_split, exp): (BTreeMap<_, _>, BTreeMap<_, _>) =
+        exp.into_iter().partition(|(q, _)| p.contains(q));
+    assert_eq!(split, exp_split.into_iter().collect());
+    assert_eq!(pm, exp.clone().into_iter().collect());
+    // the remaining map is still usable and reuses the freed slots.
+    for _ in 0..n {
+        let q = random_prefix(&mut rng, 0..=8);
+        assert_eq!(
+            pm.get_lpm(&q),
+            exp.range(..=q).rev().find(|(r, _)| r.contains(&q))
+        );
+    }
+    for q in ip("0.0.0.0/0").subnets(8).unwrap() {
+        pm.insert(q, 0);
+    }
+    assert_eq!(
+        pm.iter().count(),
+        256 + exp.keys().filter(|q| q.prefix_len() < 8).count()
+    );
+}
+
+fn fuzzing_view(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        pm.insert(random_prefix(&mut rng, 0..=8), rng.gen());
+    }
+    let root = random_prefix(&mut rng, 0..=8);
+    let view = pm.view_at(&root);
+    let exp: Map = pm
+        .iter()
+        .filter(|(p, _)| root.contains(*p))
+        .map(|(p, t)| (*p, *t))
+        .collect();
+    assert_eq!(
+        view.iter().collect::<Vec<_>>(),
+        exp.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(view.len(), exp.iter().count());
+    for _ in 0..n {
+        let q = random_prefix(&mut rng, 0..=8);
+        assert_eq!(view.get(&q), exp.get(&q));
+        assert_eq!(
+            view.get_lpm(&q),
+            exp.get_lpm(&q).and_then(|(p, _)| pm.get_key_value(p))
+        );
+    }
+}
+
+fn fuzzing_view_mut(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+    let mut exp: BTreeMap<Ipv4Net, u32> = BTreeMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        let t = rng.gen();
+        pm.insert(p, t);
+        exp.insert(p, t);
+    }
+    let root = random_prefix(&mut rng, 0..=8);
+    let mut view = pm.view_at_mut(&root);
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        let inside = root.contains(&p);
+        if rng.gen_bool(0.5) {
+            let t = rng.gen();
+            let acq = view.insert(p, t);
+            if inside {
+                assert_eq!(acq, Ok(exp.insert(p, t)));
+            } else {
+                assert!(acq.is_err());
+            }
+        } else {
+            let exp = if inside { exp.remove(&p) } else { None };
+            assert_eq!(view.remove(&p), exp);
+        }
+        let q = random_prefix(&mut rng, 0..=8);
+        let lpm = exp
+            .iter()
+            .rev()
+            .find(|(p, _)| root.contains(*p) && p.contains(&q));
+        assert_eq!(view.get_lpm(&q), lpm);
+        assert_eq!(view.get_lpm(&q), view.as_view().get_lpm(&q));
+        assert_eq!(view.get(&q), exp.get(&q).filter(|_| root.contains(&q)));
+    }
+    assert_eq!(
+        view.iter().collect::<Vec<_>>(),
+        exp.iter()
+            .filter(|(p, _)| root.contains(*p))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        pm.iter().collect::<Vec<_>>(),
+        exp.iter().collect::<Vec<_>>()
+    );
+}
+
+fn fuzzing_addr_tuple(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm: Map = PrefixMap::new();
+    let mut tm: PrefixMap<(Ipv4Addr, u8), u32> = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        let t = (p.network(), p.prefix_len());
+        if rng.gen_bool(0.7) {
+            let value = rng.gen();
+            assert_eq!(tm.insert(t, value), pm.insert(p, value));
+        } else {
+            assert_eq!(tm.remove(&t), pm.remove(&p));
+        }
+        let q = random_prefix(&mut rng, 0..=8);
+        let lpm = tm.get_lpm(&(q.network(), q.prefix_len()));
+        let exp = pm
+            .get_lpm(&q)
+            .map(|(p, t)| ((p.network(), p.prefix_len()), t));
+        assert_eq!(lpm.map(|(p, t)| (*p, t)), exp);
+    }
+    assert_eq!(
+        tm.into_iter().collect::<Vec<_>>(),
+        pm.into_iter()
+            .map(|(p, t)| ((p.network(), p.prefix_len()), t))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn mac_prefix_parse() {
+    let p: MacPrefix = "00:1A:2b:c3:44:55/24".parse().unwrap();
+    assert_eq!(p.addr(), [0x00, 0x1a, 0x2b, 0, 0, 0]);
+    assert_eq!(p.prefix_len(), 24);
+    assert_eq!(p.to_string(), "00:1a:2b:00:00:00/24");
+    assert_eq!("00-1a-2b-00-00-00/24".parse(), Ok(p));
+    assert_eq!(
+        "00:1a:2b:00:00/24".parse::<MacPrefix>(),
+        Err(MacPrefixParseError)
+    );
+    assert_eq!(
+        "00:1a:2b:00:00:00:00".parse::<MacPrefix>(),
+        Err(MacPrefixParseError)
+    );
+    assert_eq!(
+        "00:1a:2b:00:00:00/49".parse::<MacPrefix>(),
+        Err(MacPrefixParseError)
+    );
+    assert_eq!(
+        "0:1a:2b:00:00:00/8".parse::<MacPrefix>(),
+        Err(MacPrefixParseError)
+    );
+}
+
+#[test]
+fn mac_prefix() {
+    let oui: MacPrefix = "00:1a:2b:00:00:00/24".parse().unwrap();
+    let host: MacPrefix = "00:1a:2b:c3:44:55".parse().unwrap();
+    let other: MacPrefix = "00:1a:2c:c3:44:55".parse().unwrap();
+    assert!(oui.contains(&host));
+    assert!(!oui.contains(&other));
+    assert_eq!(
+        host.longest_common_prefix(&other),
+        "00:1a:28:00:00:00/21".parse().unwrap()
+    );
+    assert_eq!(MacPrefix::zero(), MacPrefix::new([0; 6], 0).unwrap());
+}
+
+fn fuzzing_routing_table(n: usize) {
+    use ipnet::{IpNet, Ipv6Net};
+    let mut rng = thread_rng();
+    let mut table: RoutingTable<u32> = RoutingTable::new();
+    let mut compat_table: compat::IpNetworkTable<u32> = compat::IpNetworkTable::new();
+    let mut reference: BTreeMap<IpNet, u32> = BTreeMap::new();
+
+    fn random_net(rng: &mut ThreadRng) -> IpNet {
+        if rng.gen_bool(0.5) {
+            let addr = Ipv4Addr::new(rng.gen(), rng.gen(), 0, 0);
+            IpNet::V4(Ipv4Net::new(addr, rng.gen_range(0..=16)).unwrap().trunc())
+        } else {
+            let addr = std::net::Ipv6Addr::new(rng.gen(), rng.gen(), 0, 0, 0, 0, 0, 0);
+            IpNet::V6(Ipv6Net::new(addr, rng.gen_range(0..=32)).unwrap().trunc())
+        }
+    }
+
+    for _ in 0..n {
+        let net = random_net(&mut rng);
+        if rng.gen_bool(0.7) {
+            let value = rng.gen();
+            assert_eq!(
+                compat_table.insert(net, value),
+                reference.get(&net).copied()
+            );
+            assert_eq!(table.insert(net, value), reference.insert(net, value));
+        } else {
+            assert_eq!(compat_table.remove(net), reference.get(&net).copied());
+            assert_eq!(table.remove(&net), reference.remove(&net));
+        }
+        let num_v4 = reference
+            .keys()
+            .filter(|p| matches!(p, IpNet::V4(_)))
+            .count();
+        assert_eq!(compat_table.len(), (num_v4, reference.len() - num_v4));
+        let net = random_net(&mut rng);
+        assert_eq!(table.get(&net), reference.get(&net));
+        let exp = reference
+            .iter()
+            .filter(|(p, _)| p.contains(&net))
+            .max_by_key(|(p, _)| p.prefix_len())
+            .map(|(p, t)| (*p, t));
+        assert_eq!(table.get_lpm(&net), exp);
+        assert_eq!(compat_table.exact_match(net), reference.get(&net));
+        let exp = reference
+            .iter()
+            .filter(|(p, _)| p.contains(&net.addr()))
+            .max_by_key(|(p, _)| p.prefix_len())
+            .map(|(p, t)| (*p, t));
+        assert_eq!(compat_table.longest_match(net.addr()), exp);
+    }
+    assert!(table.iter().eq(reference.iter().map(|(p, t)| (*p, t))));
+    assert!(compat_table.iter().eq(table.iter()));
+    assert!(table.into_iter().eq(reference));
+}
+
+fn fuzzing_borrowed_query(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+
+    fn random_query(rng: &mut ThreadRng) -> ((u32, u8), Ipv4Net) {
+        let repr = u32::from_be_bytes([rng.gen(), rng.gen_range(0..4), 0, rng.gen()]);
+        let len = rng.gen_range(0..=16);
+        let net = Ipv4Net::new(repr.into(), len).unwrap().trunc();
+        ((repr, len), net)
+    }
+
+    for _ in 0..n {
+        let (_, net) = random_query(&mut rng);
+        map.insert(net, rng.gen());
+    }
+    for _ in 0..n {
+        let (query, net) = random_query(&mut rng);
+        assert_eq!(map.get_borrowed(&query), map.get(&net));
+        assert_eq!(map.get_key_value_borrowed(&query), map.get_key_value(&net));
+        assert_eq!(map.contains_key_borrowed(&query), map.contains_key(&net));
+        assert_eq!(map.get_lpm_borrowed(&query), map.get_lpm(&net));
+        if rng.gen_bool(0.5) {
+            let mut reference = map.clone();
+            assert_eq!(map.remove_borrowed(&query), reference.remove(&net));
+            assert_eq!(map, reference);
+        }
+    }
+}
+
+fn fuzzing_first_last(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+    let mut reference = BTreeMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        let t = rng.gen();
+        map.insert(p, t);
+        reference.insert(p, t);
+    }
+    while !reference.is_empty() {
+        assert_eq!(map.first(), reference.first_key_value());
+        assert_eq!(map.last(), reference.last_key_value());
+        if rng.gen_bool(0.5) {
+            assert_eq!(map.pop_first(), reference.pop_first());
+        } else {
+            assert_eq!(map.pop_last(), reference.pop_last());
+        }
+    }
+    assert_eq!(map.first(), None);
+    assert_eq!(map.pop_last(), None);
+}
+
+fn fuzzing_next_prev_key(n: usize) {
+    use std::ops::Bound;
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+    let mut reference = BTreeMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        map.insert(p, 0);
+        reference.insert(p, 0);
+    }
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        let exp_next = reference
+            .range((Bound::Excluded(p), Bound::Unbounded))
+            .next()
+            .map(|(p, _)| p);
+        let exp_prev = reference.range(..p).next_back().map(|(p, _)| p);
+        assert_eq!(map.next_key(&p), exp_next);
+        assert_eq!(map.prev_key(&p), exp_prev);
+    }
+}
+
+fn fuzzing_iter_leaves(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        if rng.gen_bool(0.8) {
+            map.insert(p, rng.gen());
+        } else {
+            map.remove_keep_tree(&p);
+        }
+    }
+    let exp = map
+        .iter()
+        .filter(|(p, _)| map.children(p).count() == 1)
+        .collect::<Vec<_>>();
+    assert_eq!(map.iter_leaves().collect::<Vec<_>>(), exp);
+}
+
+fn fuzzing_direct_children(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        if rng.gen_bool(0.8) {
+            map.insert(p, rng.gen());
+        } else {
+            map.remove_keep_tree(&p);
+        }
+    }
+    for _ in 0..n {
+        let root = random_prefix(&mut rng, 0..=8);
+        let exp = map
+            .iter()
+            .filter(|(p, _)| root.contains(*p) && **p != root)
+            .filter(|(p, _)| {
+                !map.iter()
+                    .any(|(q, _)| *q != root && *q != **p && root.contains(q) && q.contains(*p))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(map.direct_children(&root).collect::<Vec<_>>(), exp);
+    }
+}
+
+fn fuzzing_get_parent(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        if rng.gen_bool(0.8) {
+            map.insert(p, rng.gen());
+        } else {
+            map.remove_keep_tree(&p);
+        }
+    }
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        let exp = map
+            .iter()
+            .filter(|(q, _)| q.contains(&p) && **q != p)
+            .max_by_key(|(q, _)| q.prefix_len());
+        assert_eq!(map.get_parent(&p), exp);
+    }
+}
+
+fn fuzzing_get_lpm_bounded(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        map.insert(p, rng.gen());
+    }
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=8);
+        let max_len = rng.gen_range(0..=9);
+        let exp = map
+            .iter()
+            .filter(|(q, _)| q.contains(&p) && q.prefix_len() <= max_len)
+            .max_by_key(|(q, _)| q.prefix_len());
+        assert_eq!(map.get_lpm_bounded(&p, max_len), exp);
+    }
+}
+
+fn fuzzing_get_lpm_batch(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=12);
+        map.insert(p, rng.gen());
+    }
+    let queries = (0..n)
+        .map(|_| random_prefix(&mut rng, 0..=16))
+        .collect::<Vec<_>>();
+    let exp = queries.iter().map(|q| map.get_lpm(q)).collect::<Vec<_>>();
+    assert_eq!(map.get_lpm_batch(&queries), exp);
+}
+
+fn fuzzing_stride_index(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        map.insert(p, rng.gen());
+    }
+    let index = map.stride_index(rng.gen_range(0..=12));
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        assert_eq!(index.get_lpm(&p), map.get_lpm(&p));
+    }
+}
+
+fn fuzzing_freeze(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        if rng.gen_bool(0.8) {
+            map.insert(p, rng.gen());
+        } else {
+            map.remove_keep_tree(&p);
+        }
+    }
+    let frozen = map.clone().freeze();
+    assert_eq!(frozen.len(), map.iter().count());
+
+    // cancelling after a number of steps aborts freezing
+    let mut total_steps = 0;
+    let copy = map.try_freeze(|| {
+        total_steps += 1;
+        false
+    });
+    assert!(copy.unwrap().iter().eq(frozen.iter()));
+    let limit = rng.gen_range(0..=total_steps);
+    let mut steps = 0;
+    let result = map.try_freeze(|| {
+        steps += 1;
+        steps > limit
+    });
+    assert_eq!(result.is_none(), limit < total_steps);
+
+    assert!(frozen.iter().eq(map.iter()));
+    assert!(frozen.iter().rev().eq(map.iter().rev()));
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        assert_eq!(frozen.get(&p), map.get(&p));
+        assert_eq!(frozen.get_lpm(&p), map.get_lpm(&p));
+    }
+}
+
+fn fuzzing_path_compression(n: usize) {
+    use ipnet::Ipv6Net;
+    let mut rng = thread_rng();
+    let mut pm: PrefixMap<Ipv6Net, u32> = PrefixMap::new();
+
+    for i in 0..n {
+        let addr = std::net::Ipv6Addr::from(rng.gen::<u128>());
+        let p = Ipv6Net::new(addr, rng.gen_range(96..=128)).unwrap().trunc();
+        pm.insert(p, 0);
+        // every entry adds at most one branching node, besides its own node.
+        assert!(pm.table.len() <= 2 * (i + 1) + 1);
+    }
+    assert!(pm.table.len() <= 2 * pm.iter().count() + 1);
+    // no node (except the root) without a value has less than two children.
+    for node in pm.table.iter().skip(1) {
+        assert!(node.value.is_some() || (node.left().is_some() && node.right().is_some()));
+    }
+}
+
+fn fuzzing_shrink_to_fit(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::with_capacity(2 * n + 1);
+    let mut reference: BTreeMap<Ipv4Net, u32> = BTreeMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        let v = rng.gen();
+        assert_eq!(map.insert(p, v), reference.insert(p, v));
+    }
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        assert_eq!(map.remove(&p), reference.remove(&p));
+    }
+    let capacity = map.capacity();
+    map.shrink_to_fit();
+    assert!(map.capacity() <= capacity);
+    assert!(map.iter().map(|(p, t)| (*p, *t)).eq(reference.clone()));
+    map.reserve(n);
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        let v = rng.gen();
+        assert_eq!(map.insert(p, v), reference.insert(p, v));
+    }
+    assert!(map.iter().map(|(p, t)| (*p, *t)).eq(reference));
+}
+
+fn fuzzing_compact(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+    let mut reference: BTreeMap<Ipv4Net, u32> = BTreeMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        if rng.gen_bool(0.6) {
+            let v = rng.gen();
+            assert_eq!(map.insert(p, v), reference.insert(p, v));
+        } else if rng.gen_bool(0.5) {
+            assert_eq!(map.remove(&p), reference.remove(&p));
+        } else {
+            assert_eq!(map.remove_keep_tree(&p), reference.remove(&p));
+        }
+    }
+    map.compact();
+    // every node except the root either stores a value or has two children, so no free slots
+    // are left.
+    for node in &map.table[1..] {
+        assert!(node.value.is_some() || (node.left().is_some() && node.right().is_some()));
+    }
+    assert!(map.iter().map(|(p, t)| (*p, *t)).eq(reference.clone()));
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        assert_eq!(map.get(&p), reference.get(&p));
+        assert_eq!(
+            map.get_lpm(&p).map(|(p, t)| (*p, *t)),
+            reference
+                .iter()
+                .filter(|(q, _)| q.contains(&p))
+                .max_by_key(|(q, _)| q.prefix_len())
+                .map(|(q, t)| (*q, *t))
+        );
+    }
+}
+
+fn fuzzing_invariants(n: usize) {
+    let mut rng = thread_rng();
+    let mut map: Map = PrefixMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        match rng.gen_range(0..10) {
+            0..=4 => {
+                map.insert(p, rng.gen());
+            }
+            5 | 6 => {
+                map.remove(&p);
+            }
+            7 => {
+                map.remove_keep_tree(&p);
+            }
+            8 => map.remove_children(&p),
+            _ => {
+                let v: u32 = rng.gen();
+                map.retain(|_, t| *t > v);
+            }
+        }
+        map.assert_invariants();
+    }
+    map.shrink_to_fit();
+    map.assert_invariants();
+    map.compact();
+    map.assert_invariants();
+}
+
+fn fuzzing_persistent(n: usize) {
+    use crate::persistent::PersistentPrefixMap;
+    let mut rng = thread_rng();
+
+    let mut versions = vec![(PersistentPrefixMap::new(), BTreeMap::new())];
+    for _ in 0..n {
+        let (map, reference): &(PersistentPrefixMap<Ipv4Net, u32>, BTreeMap<Ipv4Net, u32>) =
+            &versions[rng.gen_range(0..versions.len())];
+        let mut reference = reference.clone();
+        let p = random_prefix(&mut rng, 0..=16);
+        let map = if rng.gen_bool(0.7) {
+            let v = rng.gen();
+            reference.insert(p, v);
+            map.insert(p, v)
+        } else {
+            reference.remove(&p);
+            map.remove(&p)
+        };
+        versions.push((map, reference));
+    }
+    for (map, reference) in &versions {
+        assert_eq!(map.len(), reference.len());
+        assert!(map.iter().map(|(p, t)| (*p, *t)).eq(reference.clone()));
+        let pm: Map = reference.clone().into_iter().collect();
+        for _ in 0..10 {
+            let p = random_prefix(&mut rng, 0..=16);
+            assert_eq!(map.get(&p), reference.get(&p));
+            assert_eq!(map.get_lpm(&p), pm.get_lpm(&p));
+        }
+    }
+}
+
+fn fuzzing_persistent_snapshot(n: usize) {
+    use crate::persistent::PersistentPrefixMap;
+    let mut rng = thread_rng();
+
+    let mut map: PersistentPrefixMap<Ipv4Net, u32> = PersistentPrefixMap::new();
+    let mut reference = BTreeMap::new();
+    let mut snapshots = Vec::new();
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        if rng.gen_bool(0.7) {
+            let v = rng.gen();
+            assert_eq!(map.insert_mut(p, v), reference.insert(p, v));
+        } else {
+            assert_eq!(map.remove_mut(&p), reference.remove(&p));
+        }
+        if rng.gen_bool(0.1) {
+            snapshots.push((map.snapshot(), reference.clone()));
+        }
+    }
+    let converted = reference.iter().map(|(p, t)| (*p, *t)).collect::<Map>();
+    assert!(PersistentPrefixMap::from(converted) == map);
+    snapshots.push((map, reference));
+    for (map, reference) in snapshots {
+        assert_eq!(map.len(), reference.len());
+        assert!(map.iter().map(|(p, t)| (*p, *t)).eq(reference));
+    }
+}
+
+fn fuzzing_fixed(n: usize) {
+    use crate::fixed::FixedPrefixMap;
+    let mut rng = thread_rng();
+    let mut map: FixedPrefixMap<Ipv4Net, u32, 64> = FixedPrefixMap::new();
+    let mut reference = BTreeMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        if rng.gen_bool(0.6) {
+            let v = rng.gen();
+            match map.insert(p, v) {
+                Ok(old) => assert_eq!(old, reference.insert(p, v)),
+                Err(e) => {
+                    assert_eq!((e.prefix, e.value), (p, v));
+                    assert!(!reference.contains_key(&p));
+                    // a map with n entries needs at most 2n + 1 slots.
+                    assert!(2 * reference.len() + 1 > 64 - 2);
+                }
+            }
+        } else {
+            assert_eq!(map.remove(&p), reference.remove(&p));
+        }
+        assert_eq!(map.len(), reference.len());
+        assert!(map.iter().map(|(p, t)| (*p, *t)).eq(reference.clone()));
+        let pm: Map = reference.clone().into_iter().collect();
+        for _ in 0..5 {
+            let p = random_prefix(&mut rng, 0..=16);
+            assert_eq!(map.get(&p), reference.get(&p));
+            assert_eq!(map.get_lpm(&p), pm.get_lpm(&p));
+        }
+    }
+}
+
+fn fuzzing_augmented(n: usize) {
+    use crate::map::{AugmentedPrefixMap, Max, Sum};
+    let mut rng = thread_rng();
+    let mut sum: AugmentedPrefixMap<Ipv4Net, u64, Sum> = AugmentedPrefixMap::new();
+    let mut max: AugmentedPrefixMap<Ipv4Net, u64, Max> = AugmentedPrefixMap::new();
+    let mut reference: BTreeMap<Ipv4Net, u64> = BTreeMap::new();
+
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        match rng.gen_range(0..10) {
+            0..=5 => {
+                let v = rng.gen_range(0..1000);
+                assert_eq!(sum.insert(p, v), reference.insert(p, v));
+                max.insert(p, v);
+            }
+            6..=8 => {
+                assert_eq!(sum.remove(&p), reference.remove(&p));
+                max.remove(&p);
+            }
+            _ => {
+                sum.remove_children(&p);
+                max.remove_children(&p);
+                reference.retain(|q, _| !p.contains(q));
+            }
+        }
+        for _ in 0..5 {
+            let q = random_prefix(&mut rng, 0..=16);
+            let values = reference
+                .iter()
+                .filter(|(r, _)| q.contains(*r))
+                .map(|(_, t)| *t);
+            assert_eq!(sum.aggregate(&q), values.clone().sum::<u64>());
+            assert_eq!(max.aggregate(&q), values.max());
+            assert_eq!(
+                sum.count_under(&q),
+                reference.keys().filter(|r| q.contains(*r)).count()
+            );
+            assert_eq!(sum.rank(&q), reference.range(..q).count());
+        }
+        assert_eq!(sum.total(), reference.values().sum::<u64>());
+        assert_eq!(sum.len(), reference.len());
+        for k in 0..=reference.len() {
+            assert_eq!(sum.nth(k), reference.iter().nth(k));
+        }
+        for (k, p) in reference.keys().enumerate() {
+            assert_eq!(sum.rank(p), k);
+        }
+    }
+    assert!(sum.map().iter().map(|(p, t)| (*p, *t)).eq(reference));
+}
+
+#[cfg(feature = "rand")]
+fn fuzzing_sample(n: usize) {
+    let mut rng = thread_rng();
+    let mut set: Set = PrefixSet::new();
+    assert_eq!(set.sample(&mut rng), None);
+    assert_eq!(set.sample_addr(&mut rng), None);
+    for _ in 0..n {
+        let p = Ipv4Net::new(
+            Ipv4Addr::new(rng.gen(), rng.gen(), 0, 0),
+            rng.gen_range(8..=32),
+        )
+        .unwrap()
+        .trunc();
+        set.insert(p);
+    }
+    for _ in 0..n {
+        let p = set.sample(&mut rng).unwrap();
+        assert!(set.contains(p));
+        let addr = set.sample_addr(&mut rng).unwrap();
+        let addr = Ipv4Net::new(Ipv4Addr::from(addr), 32).unwrap();
+        assert!(set.get_lpm(&addr).is_some());
+    }
+}
+
+fn fuzzing_addresses(n: usize) {
+    let mut rng = thread_rng();
+    let set: Set = (0..n)
+        .map(|_| {
+            Ipv4Net::new(Ipv4Addr::new(10, 0, 0, rng.gen()), rng.gen_range(26..=32))
+                .unwrap()
+                .trunc()
+        })
+        .collect();
+    let exp = (0..=255u8)
+        .map(|i| Ipv4Addr::new(10, 0, 0, i))
+        .filter(|a| set.get_lpm(&Ipv4Net::from(*a)).is_some())
+        .map(u32::from)
+        .collect::<Vec<_>>();
+    assert_eq!(set.addresses().collect::<Vec<_>>(), exp);
+    assert_eq!(exp.len() as u128, set.coverage_size());
+}
+
+fn fuzzing_spanning_prefix(n: usize) {
+    let mut rng = thread_rng();
+    let prefixes = (0..rng.gen_range(0..=n))
+        .map(|_| {
+            let first = if rng.gen_bool(0.1) { rng.gen() } else { 10 };
+            let addr = Ipv4Addr::new(first, rng.gen_range(0..4), rng.gen(), 0);
+            Ipv4Net::new(addr, rng.gen_range(0..=24)).unwrap().trunc()
+        })
+        .collect::<Vec<_>>();
+    let set: Set = prefixes.iter().copied().collect();
+    // the spanning prefix must be a supernet of any element.
+    let exp = prefixes.first().and_then(|p| {
+        (1..=p.prefix_len())
+            .rev()
+            .map(|len| Ipv4Net::new(p.network(), len).unwrap().trunc())
+            .find(|s| prefixes.iter().all(|x| s.contains(x)))
+    });
+    assert_eq!(set.spanning_prefix(), exp);
+}
+
+fn fuzzing_partition_balanced(n: usize) {
+    let mut rng = thread_rng();
+    let pm: Map = (0..rng.gen_range(0..=n))
+        .map(|_| (random_prefix(&mut rng, 0..=16), rng.gen()))
+        .collect();
+    let exp = pm.iter().map(|(p, t)| (*p, *t)).collect::<Vec<_>>();
+    let k = rng.gen_range(1..=8);
+    let shards = pm.partition(k);
+    assert_eq!(shards.len(), k);
+    shards.iter().for_each(|s| s.assert_invariants());
+    let sizes = shards.iter().map(|s| s.iter().count()).collect::<Vec<_>>();
+    let (min, max) = (sizes.iter().min().unwrap(), sizes.iter().max().unwrap());
+    assert!(max - min <= 1, "unbalanced shards: {sizes:?}");
+    // the shards are contiguous ranges of entries in lexicographic order.
+    let joined = shards
+        .iter()
+        .flat_map(|s| s.iter().map(|(p, t)| (*p, *t)))
+        .collect::<Vec<_>>();
+    assert_eq!(joined, exp);
+}
+
+fn fuzzing_get_or_insert_with(n: usize) {
+    let mut rng = thread_rng();
+    let mut pm = Map::new();
+    let mut reference = BTreeMap::new();
+    for _ in 0..n {
+        let p = random_prefix(&mut rng, 0..=16);
+        if rng.gen_bool(0.2) {
+            assert_eq!(pm.remove(&p), reference.remove(&p));
+            continue;
+        }
+        let v = rng.gen::<u16>() as u32;
+        let mut called = false;
+        *pm.get_or_insert_with(p, || {
+            called = true;
+            v
+        }) += 1;
+        assert_eq!(called, !reference.contains_key(&p));
+        *reference.entry(p).or_insert(v) += 1;
+    }
+    pm.assert_invariants();
+    assert!(pm.iter().map(|(p, t)| (*p, *t)).eq(reference));
+}
+
 macro_rules! repeat_same {
     ($name:ident, $content:expr, 100) => {
         repeat_same!(
@@ -842,3 +3455,100 @@ repeat_same!(fuzzing_remove, fuzzing_check_removal(500), 100);
 repeat_same!(fuzzing_remove_children, fuzzing_remove_children(2000), 100);
 repeat_same!(fuzzing_set, fuzzing_set_union(500), 100);
 repeat_same!(fuzzing_retain, fuzzing_retain(100, 10), 100);
+repeat_same!(
+    fuzzing_partition_balanced,
+    fuzzing_partition_balanced(50),
+    100
+);
+repeat_same!(fuzzing_shadow, fuzzing_shadow(500), 100);
+repeat_same!(
+    fuzzing_shadow_persistent,
+    fuzzing_shadow_backend::<persistent::PersistentPrefixMap<_, _>>(
+        shadow::ShadowedPrefixMap::new(),
+        200,
+    ),
+    100
+);
+repeat_same!(
+    fuzzing_shadow_frozen,
+    fuzzing_shadow_backend(
+        shadow::ShadowedPrefixMap::with_backend(Map::new().freeze()),
+        100,
+    ),
+    100
+);
+#[cfg(feature = "concurrent")]
+repeat_same!(
+    fuzzing_shadow_concurrent,
+    fuzzing_shadow_backend(
+        shadow::ShadowedPrefixMap::with_backend(concurrent::new(Map::new())),
+        200,
+    ),
+    100
+);
+repeat_same!(fuzzing_cursor, fuzzing_cursor(100, 10), 100);
+repeat_same!(fuzzing_double_ended, fuzzing_double_ended(200), 100);
+repeat_same!(fuzzing_non_overlapping, fuzzing_non_overlapping(200), 100);
+repeat_same!(fuzzing_range, fuzzing_range(200), 100);
+repeat_same!(fuzzing_partition, fuzzing_partition(20), 100);
+#[cfg(feature = "rayon")]
+repeat_same!(fuzzing_par_iter, fuzzing_par_iter(2000), 100);
+repeat_same!(fuzzing_lpm_addresses, fuzzing_lpm_addresses(200), 100);
+repeat_same!(fuzzing_aggregate, fuzzing_aggregate(20), 100);
+repeat_same!(fuzzing_rebase, fuzzing_rebase(100), 100);
+repeat_same!(fuzzing_wildcard, fuzzing_wildcard(100), 100);
+repeat_same!(fuzzing_aggregate_with, fuzzing_aggregate_with(100), 100);
+repeat_same!(fuzzing_complement, fuzzing_complement(20), 100);
+repeat_same!(fuzzing_remove_cover, fuzzing_remove_cover(20), 100);
+repeat_same!(fuzzing_insert_range, fuzzing_insert_range(20), 100);
+repeat_same!(fuzzing_gaps, fuzzing_gaps(20), 100);
+repeat_same!(fuzzing_first_gap, fuzzing_first_gap(20), 100);
+repeat_same!(fuzzing_try_retain, fuzzing_try_retain(100), 100);
+repeat_same!(
+    fuzzing_try_remove_children,
+    fuzzing_try_remove_children(100),
+    100
+);
+repeat_same!(fuzzing_subset, fuzzing_subset(10), 100);
+repeat_same!(fuzzing_budgeted, fuzzing_budgeted(100), 100);
+repeat_same!(fuzzing_set_ops, fuzzing_set_ops(50), 100);
+repeat_same!(fuzzing_union_with, fuzzing_union_with(50), 100);
+repeat_same!(fuzzing_compat, fuzzing_compat(200), 100);
+repeat_same!(fuzzing_diff, fuzzing_diff(200), 100);
+repeat_same!(fuzzing_append, fuzzing_append(200), 100);
+repeat_same!(fuzzing_split_off, fuzzing_split_off(100), 100);
+repeat_same!(fuzzing_view, fuzzing_view(100), 100);
+repeat_same!(fuzzing_view_mut, fuzzing_view_mut(100), 100);
+repeat_same!(fuzzing_addr_tuple, fuzzing_addr_tuple(100), 100);
+repeat_same!(fuzzing_routing_table, fuzzing_routing_table(200), 100);
+repeat_same!(fuzzing_borrowed_query, fuzzing_borrowed_query(200), 100);
+repeat_same!(
+    fuzzing_get_or_insert_with,
+    fuzzing_get_or_insert_with(100),
+    100
+);
+repeat_same!(fuzzing_first_last, fuzzing_first_last(100), 100);
+repeat_same!(fuzzing_next_prev_key, fuzzing_next_prev_key(100), 100);
+repeat_same!(fuzzing_iter_leaves, fuzzing_iter_leaves(100), 100);
+repeat_same!(fuzzing_direct_children, fuzzing_direct_children(50), 100);
+repeat_same!(fuzzing_get_parent, fuzzing_get_parent(100), 100);
+repeat_same!(fuzzing_get_lpm_bounded, fuzzing_get_lpm_bounded(100), 100);
+repeat_same!(fuzzing_get_lpm_batch, fuzzing_get_lpm_batch(200), 100);
+repeat_same!(fuzzing_stride_index, fuzzing_stride_index(200), 100);
+repeat_same!(fuzzing_freeze, fuzzing_freeze(200), 100);
+repeat_same!(fuzzing_path_compression, fuzzing_path_compression(100), 100);
+repeat_same!(fuzzing_shrink_to_fit, fuzzing_shrink_to_fit(200), 100);
+repeat_same!(fuzzing_compact, fuzzing_compact(200), 100);
+repeat_same!(fuzzing_invariants, fuzzing_invariants(200), 100);
+repeat_same!(fuzzing_persistent, fuzzing_persistent(200), 100);
+repeat_same!(
+    fuzzing_persistent_snapshot,
+    fuzzing_persistent_snapshot(200),
+    100
+);
+repeat_same!(fuzzing_fixed, fuzzing_fixed(200), 100);
+repeat_same!(fuzzing_augmented, fuzzing_augmented(200), 100);
+#[cfg(feature = "rand")]
+repeat_same!(fuzzing_sample, fuzzing_sample(100), 100);
+repeat_same!(fuzzing_addresses, fuzzing_addresses(20), 100);
+repeat_same!(fuzzing_spanning_prefix, fuzzing_spanning_prefix(10), 100);